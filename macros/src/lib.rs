@@ -0,0 +1,229 @@
+//! Procedural macros of `message-format`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitStr, Token,
+};
+
+struct CompileMessage {
+    pattern: LitStr,
+    locale: LitStr,
+}
+
+impl Parse for CompileMessage {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let name: Ident = input.parse()?;
+        if name != "locale" {
+            return Err(syn::Error::new(name.span(), "expected `locale`"));
+        }
+        input.parse::<Token![=]>()?;
+        let locale = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self { pattern, locale })
+    }
+}
+
+/// Compiles a message to a closure formatting it with parameters of type
+/// `&[(&str, ParamValue)]`, e.g.
+///
+/// ```ignore
+/// let items = compile_message!("{N, plural, one {# item} other {# items}}", locale = "en");
+/// assert_eq!(items(&[("N", 3.into())]), "3 items");
+/// ```
+///
+/// The pattern is parsed when compiling, so invalid patterns fail to compile
+/// and plural and select blocks become `match` expressions.
+#[proc_macro]
+pub fn compile_message(input: TokenStream) -> TokenStream {
+    let CompileMessage { pattern, locale } = parse_macro_input!(input);
+    match message_format::compile_message(&pattern.value(), &locale.value()) {
+        Ok(code) => code.parse().expect("invalid generated code"),
+        Err(err) => syn::Error::new(pattern.span(), err)
+            .to_compile_error()
+            .into(),
+    }
+}
+
+struct MsgFormat {
+    pattern: LitStr,
+    locale: Expr,
+    params: Vec<(Ident, Expr)>,
+}
+
+impl Parse for MsgFormat {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let locale = input.parse()?;
+        let mut params = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = input.call(Ident::parse_any)?;
+            input.parse::<Token![=]>()?;
+            params.push((name, input.parse()?));
+        }
+        Ok(Self {
+            pattern,
+            locale,
+            params,
+        })
+    }
+}
+
+/// Formats a message with named arguments, e.g.
+///
+/// ```ignore
+/// let greeting = msgformat!("Hello {NAME}!", &locale, NAME = user);
+/// ```
+///
+/// The pattern is parsed when compiling, so malformed patterns, missing
+/// arguments and arguments which are not used by the pattern fail to compile.
+/// Arguments are converted with `Into<ParamValue>`.
+#[proc_macro]
+pub fn msgformat(input: TokenStream) -> TokenStream {
+    let MsgFormat {
+        pattern,
+        locale,
+        params,
+    } = parse_macro_input!(input);
+    let arguments = match message_format::message_arguments(&pattern.value()) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            return syn::Error::new(pattern.span(), err)
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let mut errors = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for (name, _) in &params {
+        let unraw = name.unraw().to_string();
+        if names.contains(&unraw) {
+            errors.push(syn::Error::new(
+                name.span(),
+                format!("duplicate argument {unraw}"),
+            ));
+        } else if !arguments.contains(&unraw) {
+            errors.push(syn::Error::new(
+                name.span(),
+                format!("argument {unraw} is not used by the message"),
+            ));
+        }
+        names.push(unraw);
+    }
+    for argument in &arguments {
+        if !names.contains(argument) {
+            errors.push(syn::Error::new(
+                pattern.span(),
+                format!("missing argument {argument}"),
+            ));
+        }
+    }
+    if let Some(error) = errors.into_iter().reduce(|mut error, other| {
+        error.combine(other);
+        error
+    }) {
+        // several `compile_error!` invocations are only valid as statements
+        let errors = error.to_compile_error();
+        return quote!({ #errors }).into();
+    }
+
+    let message = quote!(::message_format::MessageFormat::new(#pattern, #locale));
+    if params.is_empty() {
+        return quote!(#message.format()).into();
+    }
+    let values = params.iter().map(|(_, value)| value);
+    quote!(
+        #message.format_with_params([#((
+            #names,
+            ::std::convert::Into::<::message_format::ParamValue>::into(#values),
+        )),*])
+    )
+    .into()
+}
+
+/// Implements `MessageArgs` for a struct with named fields, which are passed
+/// as the parameters of the same name, e.g.
+///
+/// ```ignore
+/// #[derive(MessageArgs)]
+/// struct Greeting {
+///     #[message_args(rename = "NAME")]
+///     name: String,
+///     count: u32,
+/// }
+/// ```
+#[proc_macro_derive(MessageArgs, attributes(message_args))]
+pub fn derive_message_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match message_args(&input) {
+        Ok(code) => code.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn message_args(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => &Default::default(),
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    &data.fields,
+                    "MessageArgs requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "MessageArgs can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut params = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut name = ident.unraw().to_string();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("message_args"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename`"))
+                }
+            })?;
+        }
+        params.push(quote!((
+            #name,
+            ::std::convert::Into::<::message_format::ParamValue>::into(self.#ident),
+        )));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote!(
+        impl #impl_generics ::message_format::MessageArgs for #ident #ty_generics #where_clause {
+            fn into_params(
+                self,
+            ) -> ::std::vec::Vec<(&'static str, ::message_format::ParamValue)> {
+                ::std::vec![#(#params),*]
+            }
+        }
+    ))
+}
@@ -0,0 +1,58 @@
+use icu::locid::locale;
+use message_format::{MessageFormat, ParamValue};
+use message_format_macros::compile_message;
+
+#[test]
+fn test_compile_message() {
+    let items = compile_message!(
+        "{N, plural, =0 {no items} one {# item} other {# items}} for '{'{NAME}'}'",
+        locale = "en"
+    );
+    assert_eq!(
+        items(&[("N", 0.into()), ("NAME", "Alice".into())]),
+        "no items for {Alice}"
+    );
+    assert_eq!(
+        items(&[("N", 1.into()), ("NAME", "Alice".into())]),
+        "1 item for {Alice}"
+    );
+    assert_eq!(
+        items(&[("N", 1234.into()), ("NAME", "Bob".into())]),
+        "1,234 items for {Bob}"
+    );
+    assert_eq!(
+        items(&[("NAME", "Bob".into())]),
+        "Undefined parameter - N for {Bob}"
+    );
+
+    let plain = compile_message!("Hello, world!", locale = "en");
+    assert_eq!(plain(&[]), "Hello, world!");
+}
+
+#[test]
+fn test_compile_message_as_interpreted() {
+    let en = locale!("en");
+    let pattern = "{G, select, female {{N, plural, offset:1 =0 {nobody} one {her and # other} \
+                   other {her and # others}}} other {{N, selectordinal, one {#st} two {#nd} \
+                   few {#rd} other {#th}} with {P, number, ::.00 group-off} of {Q, number, ::compact-short}}}";
+    let compiled = compile_message!(
+        "{G, select, female {{N, plural, offset:1 =0 {nobody} one {her and # other} \
+         other {her and # others}}} other {{N, selectordinal, one {#st} two {#nd} \
+         few {#rd} other {#th}} with {P, number, ::.00 group-off} of {Q, number, ::compact-short}}}",
+        locale = "en"
+    );
+    for g in ["female", "male"] {
+        for n in [0, 1, 2, 3, 22, 1001] {
+            let params: [(&str, ParamValue); 4] = [
+                ("G", g.into()),
+                ("N", n.into()),
+                ("P", 1234.5.into()),
+                ("Q", 56789.into()),
+            ];
+            assert_eq!(
+                compiled(&params),
+                MessageFormat::new(pattern, &en).format_with_params(params.clone())
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use icu::locid::locale;
+use message_format::{MessageArgs, MessageFormat};
+use message_format_macros::MessageArgs;
+
+#[derive(MessageArgs)]
+struct Invite {
+    #[message_args(rename = "WHO")]
+    who: String,
+    count: u32,
+    r#type: &'static str,
+}
+
+#[derive(MessageArgs)]
+struct Empty;
+
+#[test]
+fn test_derive_message_args() {
+    let en = locale!("en");
+    let message = MessageFormat::new(
+        "{WHO} sent {count, plural, one {# {type}} other {# {type}s}}",
+        &en,
+    );
+    let invite = Invite {
+        who: "Alice".to_owned(),
+        count: 2,
+        r#type: "invite",
+    };
+    assert_eq!(
+        message.format_with_params(invite.into_params()),
+        "Alice sent 2 invites"
+    );
+    assert!(Empty.into_params().is_empty());
+}
@@ -0,0 +1,35 @@
+use icu::locid::locale;
+use message_format_macros::msgformat;
+
+#[test]
+fn test_msgformat() {
+    let en = locale!("en");
+    let user = String::from("Alice");
+    assert_eq!(
+        msgformat!("Hello {NAME}!", &en, NAME = user),
+        "Hello Alice!"
+    );
+
+    let count = 3;
+    assert_eq!(
+        msgformat!(
+            "{WHO} has {N, plural, one {# file} other {# files}}",
+            &en,
+            N = count,
+            WHO = "Bob",
+        ),
+        "Bob has 3 files"
+    );
+    assert_eq!(
+        msgformat!(
+            "{type, select, dir {folder} other {file}}",
+            &en,
+            r#type = "dir"
+        ),
+        "folder"
+    );
+    assert_eq!(
+        msgformat!("No arguments, '{'quoted'}'", &en),
+        "No arguments, {quoted}"
+    );
+}
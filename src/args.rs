@@ -0,0 +1,21 @@
+use crate::ParamValue;
+
+/// Named parameters from the fields of a struct, usually derived with
+/// `#[derive(MessageArgs)]` of the `message-format-macros` crate, e.g.
+///
+/// ```ignore
+/// #[derive(MessageArgs)]
+/// struct Invite {
+///     #[message_args(rename = "WHO")]
+///     who: String,
+///     count: u32,
+/// }
+///
+/// let text = message.format_with_params(Invite { who, count }.into_params());
+/// ```
+///
+/// Fields are converted with `Into<ParamValue>`, and named like the field
+/// unless renamed.
+pub trait MessageArgs {
+    fn into_params(self) -> Vec<(&'static str, ParamValue)>;
+}
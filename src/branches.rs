@@ -0,0 +1,143 @@
+use fixed_decimal::FixedDecimal;
+use icu::locid::Locale;
+use indexmap::IndexMap;
+
+use crate::{
+    param::{subtract_offset, ARGUMENT_NAME, ARGUMENT_OFFSET},
+    plural_samples, Block, ParamValue, OTHER,
+};
+
+/// Branches taken in a message, as pairs of argument name and key, e.g.
+/// `[("GENDER", "female"), ("N", "one")]`
+pub type BranchPath = Vec<(String, String)>;
+
+/// Variant of a message in which every select, plural and ordinal block has
+/// only one branch left
+pub(crate) struct Combination {
+    pub(crate) blocks: Vec<Block>,
+    pub(crate) path: BranchPath,
+    /// Parameter values selecting the branches, e.g. a sample number of the
+    /// plural category
+    pub(crate) params: Vec<(String, ParamValue)>,
+}
+
+pub(crate) fn combinations(blocks: &[Block], locale: &Locale) -> Vec<Combination> {
+    let mut result = vec![Combination {
+        blocks: Vec::new(),
+        path: Vec::new(),
+        params: Vec::new(),
+    }];
+    for block in blocks {
+        let block_combinations = block_combinations(block, locale);
+        result = result
+            .iter()
+            .flat_map(|prefix| {
+                block_combinations.iter().map(|suffix| Combination {
+                    blocks: prefix
+                        .blocks
+                        .iter()
+                        .chain(&suffix.blocks)
+                        .cloned()
+                        .collect(),
+                    path: prefix.path.iter().chain(&suffix.path).cloned().collect(),
+                    params: prefix
+                        .params
+                        .iter()
+                        .chain(&suffix.params)
+                        .cloned()
+                        .collect(),
+                })
+            })
+            .collect();
+    }
+    result
+}
+
+fn block_combinations(block: &Block, locale: &Locale) -> Vec<Combination> {
+    let (Block::Select(branches) | Block::Plural(branches, _) | Block::Ordinal(branches, _)) =
+        block
+    else {
+        return vec![Combination {
+            blocks: vec![block.clone()],
+            path: Vec::new(),
+            params: Vec::new(),
+        }];
+    };
+    let Some(Block::String(argument)) = branches[&ARGUMENT_NAME].first() else {
+        panic!("invalid argument name");
+    };
+    let is_select = matches!(block, Block::Select(_));
+
+    let mut result = Vec::new();
+    for (key, branch) in branches {
+        if *key == ARGUMENT_NAME || *key == ARGUMENT_OFFSET {
+            continue;
+        }
+        let (key, param) = if is_select {
+            (key.to_string(), Some(key.clone()))
+        } else {
+            plural_key_and_sample(key, branches, locale)
+        };
+
+        for combination in combinations(branch, locale) {
+            // the branch becomes the only option of the block
+            let mut forced = IndexMap::new();
+            for (k, v) in branches {
+                if *k == ARGUMENT_NAME || *k == ARGUMENT_OFFSET {
+                    forced.insert(k.clone(), v.clone());
+                }
+            }
+            forced.insert(OTHER, combination.blocks);
+            let forced = match block {
+                Block::Select(_) => Block::Select(forced),
+                Block::Plural(..) => Block::plural(forced),
+                _ => Block::ordinal(forced),
+            };
+
+            let mut path = vec![(argument.clone(), key.clone())];
+            path.extend(combination.path);
+            let mut params: Vec<_> = param
+                .iter()
+                .map(|p| (argument.clone(), p.clone()))
+                .collect();
+            params.extend(combination.params);
+            result.push(Combination {
+                blocks: vec![forced],
+                path,
+                params,
+            });
+        }
+    }
+    result
+}
+
+/// Returns the display key (`=1` for exact matches) and a number in the
+/// branch, if any.
+fn plural_key_and_sample(
+    key: &ParamValue,
+    branches: &IndexMap<ParamValue, Vec<Block>>,
+    locale: &Locale,
+) -> (String, Option<ParamValue>) {
+    if key.as_decimal().is_some() {
+        return (format!("={key}"), Some(key.clone()));
+    }
+    let offset: FixedDecimal = match branches[&ARGUMENT_OFFSET].first() {
+        Some(Block::String(offset)) => offset.parse().unwrap_or_default(),
+        _ => FixedDecimal::default(),
+    };
+    // the sample is shifted by the offset, so `#` shows a number of the category
+    let negated_offset = subtract_offset(&FixedDecimal::default(), &offset);
+    let samples = plural_samples(locale, &key.to_string());
+    let sample = samples
+        .integers
+        .first()
+        .map(|&n| FixedDecimal::from(n))
+        // exact decimals keep their fraction digits, e.g. `0.0`
+        .or_else(|| samples.decimals.first()?.parse().ok())
+        .and_then(|n| subtract_offset(&n, negated_offset.as_ref()?))
+        .map(|n| match n.to_string().parse::<i64>() {
+            Ok(n) => ParamValue::from(n),
+            Err(_) => ParamValue::from(n),
+        });
+    (key.to_string(), sample)
+}
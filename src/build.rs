@@ -0,0 +1,236 @@
+//! Helpers for build scripts.
+
+use std::fmt::Write;
+#[cfg(feature = "build")]
+use std::{fs, path::Path};
+
+use icu::locid::Locale;
+
+use crate::MessageFormat;
+#[cfg(any(feature = "arb", feature = "build", feature = "async"))]
+use crate::Metadata;
+
+/// Generates a Rust module with a typed handle for every message, e.g. for
+/// `("cart.items", "{N, plural, one {# item} other {# items}}")`:
+///
+/// ```ignore
+/// pub const CART_ITEMS: &str = "cart.items";
+///
+/// pub fn cart_items(n: impl Into<ParamValue>) -> (&'static str, [(&'static str, ParamValue); 1]) {
+///     (CART_ITEMS, [("N", n.into())])
+/// }
+/// ```
+///
+/// The key and parameters returned by a handle are passed to e.g.
+/// [`crate::Registry::format`], so misspelled keys and missing arguments
+/// fail to compile. Panics if a pattern is invalid.
+pub fn generate_module<'a>(messages: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut module = String::from(
+        "// Generated by message_format::build::generate_module, do not edit.\n\n\
+         use message_format::ParamValue;\n",
+    );
+    for (key, pattern) in messages {
+        let arguments = match MessageFormat::parse_strict(pattern, &Locale::UND) {
+            Ok(message) => message.arguments(),
+            Err(err) => panic!("invalid message {key}: {err}"),
+        };
+        let name = identifier(key);
+        let constant = name.to_uppercase();
+
+        writeln!(module).unwrap();
+        for line in pattern.lines() {
+            writeln!(module, "/// `{line}`").unwrap();
+        }
+        writeln!(module, "pub const {constant}: &str = {key:?};\n").unwrap();
+        write!(module, "pub fn {name}(").unwrap();
+        for (i, argument) in arguments.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(
+                module,
+                "{separator}{}: impl Into<ParamValue>",
+                identifier(argument)
+            )
+            .unwrap();
+        }
+        writeln!(
+            module,
+            ") -> (&'static str, [(&'static str, ParamValue); {}]) {{",
+            arguments.len()
+        )
+        .unwrap();
+        write!(module, "    ({constant}, [").unwrap();
+        for (i, argument) in arguments.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(
+                module,
+                "{separator}({argument:?}, {}.into())",
+                identifier(argument)
+            )
+            .unwrap();
+        }
+        writeln!(module, "])\n}}").unwrap();
+    }
+    module
+}
+
+/// Converts a message key or an argument name to a snake case identifier.
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+        "type", "unsafe", "use", "where", "while",
+    ];
+
+    let mut identifier: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    if KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+/// Parses every message of the locale files in `dir`, e.g. `en.json` or
+/// `de-CH.arb`, and panics with the problems of all files, so that a build
+/// script fails on broken translations.
+///
+/// See [`parse_locale_file`] for the format of the files.
+#[cfg(feature = "build")]
+pub fn validate_dir(dir: impl AsRef<Path>) {
+    let dir = dir.as_ref();
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let diagnostics = diagnostics(dir);
+    if !diagnostics.is_empty() {
+        panic!(
+            "{} invalid messages in {}:\n{}",
+            diagnostics.len(),
+            dir.display(),
+            diagnostics.join("\n")
+        );
+    }
+}
+
+#[cfg(feature = "build")]
+fn diagnostics(dir: &Path) -> Vec<String> {
+    let mut diagnostics = Vec::new();
+    let mut paths: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("json" | "arb")
+                )
+            })
+            .collect(),
+        Err(err) => return vec![format!("{}: {err}", dir.display())],
+    };
+    paths.sort();
+
+    for path in paths {
+        let file = path.display();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let Ok(locale) = stem.replace('_', "-").parse::<Locale>() else {
+            diagnostics.push(format!("{file}: invalid locale {stem}"));
+            continue;
+        };
+        let entries = match read_locale_file(&path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                diagnostics.push(format!("{file}: {err}"));
+                continue;
+            }
+        };
+
+        for Entry { key, pattern, .. } in entries {
+            if let Err(err) = MessageFormat::parse_strict(pattern, &locale) {
+                diagnostics.push(format!("{file}: {key}: {err}"));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Message of a locale file, see [`parse_locale_file`].
+#[cfg(any(feature = "arb", feature = "build", feature = "async"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    pub pattern: String,
+    pub metadata: Metadata,
+}
+
+/// Reads the messages of a locale file, see [`parse_locale_file`].
+#[cfg(feature = "build")]
+pub fn read_locale_file(path: impl AsRef<Path>) -> Result<Vec<Entry>, String> {
+    let json = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parse_locale_file(&json)
+}
+
+/// Parses the messages of a locale file, which is a JSON object mapping keys
+/// to patterns, e.g. an ARB file.
+///
+/// As in ARB files, the metadata of a key is read from the object of `@key`,
+/// e.g. `"@greeting": {"description": "Greeting on the start page"}`, incl.
+/// argument examples like `"placeholders": {"NAME": {"example": "Alice"}}`.
+#[cfg(any(feature = "arb", feature = "build", feature = "async"))]
+pub fn parse_locale_file(json: &str) -> Result<Vec<Entry>, String> {
+    use serde_json::{Map, Value};
+
+    let messages: Map<String, Value> = serde_json::from_str(json).map_err(|err| err.to_string())?;
+
+    let mut entries = Vec::new();
+    for (key, pattern) in &messages {
+        if key.starts_with('@') {
+            continue;
+        }
+        let Some(pattern) = pattern.as_str() else {
+            return Err(format!("{key}: pattern is not a string"));
+        };
+        let attribute = |name: &str| {
+            let value = messages.get(&format!("@{key}"))?.get(name)?;
+            Some(value.as_str()?.to_owned())
+        };
+        let examples = messages
+            .get(&format!("@{key}"))
+            .and_then(|metadata| metadata.get("placeholders")?.as_object())
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, placeholder)| {
+                let example = match placeholder.get("example")? {
+                    Value::String(example) => example.clone(),
+                    Value::Number(example) => example.to_string(),
+                    _ => return None,
+                };
+                Some((name.clone(), example))
+            })
+            .collect();
+        entries.push(Entry {
+            key: key.clone(),
+            pattern: pattern.to_owned(),
+            metadata: Metadata {
+                description: attribute("description"),
+                meaning: attribute("meaning"),
+                context: attribute("context"),
+                examples,
+            },
+        });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,134 @@
+use std::fmt::Write;
+
+use icu::locid::Locale;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::MessageFormat;
+
+/// Builds a message in code, e.g.
+/// `MessageBuilder::new().text("Hi ").arg("NAME")` for `Hi {NAME}`.
+///
+/// Text is escaped, so it is always formatted verbatim.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageBuilder {
+    pattern: String,
+}
+
+/// Branches of a select, plural or ordinal block, see [`MessageBuilder`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Branches {
+    offset: Option<u32>,
+    branches: Vec<(String, MessageBuilder)>,
+}
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends literal text.
+    pub fn text(mut self, text: &str) -> Self {
+        self.pattern.push_str(&MessageFormat::escape(text));
+        self
+    }
+
+    /// Appends a simple argument, e.g. `{NAME}`.
+    pub fn arg(mut self, name: &str) -> Self {
+        check_argument_name(name);
+        write!(self.pattern, "{{{name}}}").unwrap();
+        self
+    }
+
+    /// Appends a number argument with an optional style, e.g.
+    /// `{N, number, ::.00}`.
+    pub fn number(mut self, name: &str, style: Option<&str>) -> Self {
+        check_argument_name(name);
+        match style {
+            Some(style) => write!(self.pattern, "{{{name}, number, {style}}}").unwrap(),
+            None => write!(self.pattern, "{{{name}, number}}").unwrap(),
+        }
+        self
+    }
+
+    /// Appends `#`, the number of the enclosing plural or ordinal block.
+    pub fn pound(mut self) -> Self {
+        self.pattern.push('#');
+        self
+    }
+
+    /// Appends a select block.
+    pub fn select(self, name: &str, branches: impl FnOnce(Branches) -> Branches) -> Self {
+        self.block(name, "select", branches(Branches::default()))
+    }
+
+    /// Appends a plural block.
+    pub fn plural(self, name: &str, branches: impl FnOnce(Branches) -> Branches) -> Self {
+        self.block(name, "plural", branches(Branches::default()))
+    }
+
+    /// Appends an ordinal block.
+    pub fn selectordinal(self, name: &str, branches: impl FnOnce(Branches) -> Branches) -> Self {
+        self.block(name, "selectordinal", branches(Branches::default()))
+    }
+
+    fn block(mut self, name: &str, typ: &str, branches: Branches) -> Self {
+        check_argument_name(name);
+        assert!(
+            branches.branches.iter().any(|(key, _)| key == "other"),
+            "missing other branch in {typ} block {name}"
+        );
+        write!(self.pattern, "{{{name}, {typ},").unwrap();
+        if let Some(offset) = branches.offset {
+            write!(self.pattern, " offset:{offset}").unwrap();
+        }
+        for (key, message) in branches.branches {
+            write!(self.pattern, " {key} {{{}}}", message.pattern).unwrap();
+        }
+        self.pattern.push('}');
+        self
+    }
+
+    /// Returns the pattern of the message.
+    pub fn to_pattern(&self) -> String {
+        self.pattern.clone()
+    }
+
+    /// Returns the message, ready to be formatted.
+    pub fn build(self, locale: &Locale) -> MessageFormat<'_> {
+        MessageFormat::new(self.pattern, locale)
+    }
+}
+
+impl Branches {
+    /// Adds a branch for a key, i.e. a select value or a plural category.
+    pub fn branch(mut self, key: &str, message: MessageBuilder) -> Self {
+        check_argument_name(key);
+        self.branches.push((key.to_owned(), message));
+        self
+    }
+
+    /// Adds a branch for an exact plural value, e.g. `=0`.
+    pub fn exact(mut self, value: u64, message: MessageBuilder) -> Self {
+        self.branches.push((format!("={value}"), message));
+        self
+    }
+
+    /// Adds the required `other` branch.
+    pub fn other(self, message: MessageBuilder) -> Self {
+        self.branch("other", message)
+    }
+
+    /// Sets the offset of a plural block, which is subtracted from the value
+    /// before the category is selected.
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+fn check_argument_name(name: &str) {
+    static NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\w+$").unwrap());
+
+    assert!(NAME_RE.is_match(name), "invalid name {name:?}");
+}
@@ -0,0 +1,217 @@
+#[cfg(feature = "precompiled")]
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use icu::locid::Locale;
+use indexmap::IndexMap;
+
+#[cfg(feature = "arb")]
+use crate::build::{parse_locale_file, Entry};
+#[cfg(feature = "fluent")]
+use crate::fluent::{convert_resource, FtlMessage};
+use crate::{MessageFormat, Metadata, ParamValue, ParseError, RenderContext};
+
+/// Messages of one locale looked up by key, e.g. loaded from a translation
+/// file at runtime. Unlike [`crate::Registry`], the keys need not be known at
+/// compile time.
+///
+/// Patterns are parsed when they are added, and all messages share the
+/// formatters of one [`RenderContext`].
+///
+/// ```
+/// use icu::locid::locale;
+/// use message_format::MessageBundle;
+///
+/// let mut bundle = MessageBundle::new(locale!("en"));
+/// bundle.add("greeting", "Hello {NAME}!").unwrap();
+/// assert_eq!(
+///     bundle.format("greeting", [("NAME", "Alice".into())]).as_deref(),
+///     Some("Hello Alice!")
+/// );
+/// ```
+#[derive(Debug)]
+pub struct MessageBundle {
+    context: RenderContext,
+    messages: IndexMap<String, MessageFormat<'static>>,
+    metadata: HashMap<String, Metadata>,
+}
+
+impl MessageBundle {
+    pub fn new(locale: Locale) -> Self {
+        Self {
+            context: RenderContext::new(locale),
+            messages: IndexMap::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn locale(&self) -> &Locale {
+        self.context.locale()
+    }
+
+    /// Parses `pattern` and adds it as the message of `key`, replacing the
+    /// previous message. Malformed patterns are not added.
+    pub fn add(
+        &mut self,
+        key: impl Into<String>,
+        pattern: impl Into<String>,
+    ) -> Result<(), ParseError> {
+        let message = MessageFormat::new_owned(pattern, self.locale().clone()).try_parse()?;
+        self.messages.insert(key.into(), message);
+        Ok(())
+    }
+
+    /// Adds the messages of an ARB file, e.g. `{"greeting": "Hello {NAME}!"}`,
+    /// with their metadata from the `@` entries, see
+    /// [`crate::build::parse_locale_file`].
+    ///
+    /// Returns the keys whose patterns are malformed, with their errors; the
+    /// other messages are added. Fails if the file is not a valid ARB file.
+    #[cfg(feature = "arb")]
+    pub fn load_arb(&mut self, json: &str) -> Result<Vec<(String, ParseError)>, String> {
+        let mut failed = Vec::new();
+        for Entry {
+            key,
+            pattern,
+            metadata,
+        } in parse_locale_file(json)?
+        {
+            match self.add(key.clone(), pattern) {
+                Ok(()) => self.set_metadata(key, metadata),
+                Err(err) => failed.push((key, err)),
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Adds the messages of a Fluent resource, converted to ICU patterns, e.g.
+    /// for `files = { $n -> [one] one file *[other] { $n } files }` the
+    /// pattern `{n, plural, one {one file} other {{n} files}}`. Attributes
+    /// are added as `message.attribute`, and message comments become the
+    /// description of the metadata.
+    ///
+    /// Selectors with plural categories or numeric keys are plural blocks,
+    /// `NUMBER($n, type: "ordinal")` selects ordinals, terms are inlined,
+    /// and hyphens in variable names become underscores. Other functions and
+    /// message references are not supported.
+    ///
+    /// Returns the keys which are not supported or malformed, with the
+    /// reason; the other messages are added. Fails on syntax errors.
+    #[cfg(feature = "fluent")]
+    pub fn load_ftl(&mut self, ftl: &str) -> Result<Vec<(String, String)>, String> {
+        let mut failed = Vec::new();
+        for FtlMessage {
+            key,
+            pattern,
+            comment,
+        } in convert_resource(ftl)?
+        {
+            let result = pattern.and_then(|pattern| {
+                self.add(key.clone(), pattern)
+                    .map_err(|err| err.to_string())
+            });
+            match result {
+                Ok(()) => self.set_metadata(
+                    key,
+                    Metadata {
+                        description: comment,
+                        ..Default::default()
+                    },
+                ),
+                Err(err) => failed.push((key, err)),
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Returns the parsed messages as JSON, see
+    /// [`MessageFormat::to_precompiled`], e.g. to parse a catalog in a build
+    /// step. Metadata is not stored.
+    #[cfg(feature = "precompiled")]
+    pub fn to_precompiled(&self) -> String {
+        let messages: Vec<_> = self
+            .messages
+            .iter()
+            .map(|(key, message)| {
+                serde_json::json!({"key": key, "message": crate::precompiled::to_json(message)})
+            })
+            .collect();
+        serde_json::json!({"locale": self.locale().to_string(), "messages": messages}).to_string()
+    }
+
+    /// Loads the messages written by [`MessageBundle::to_precompiled`]
+    /// without parsing their patterns, or returns why the JSON is invalid.
+    #[cfg(feature = "precompiled")]
+    pub fn from_precompiled(json: &str) -> Result<Self, String> {
+        use serde_json::Value;
+
+        let value: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let locale: Locale = match value.get("locale").and_then(Value::as_str) {
+            Some(locale) => locale
+                .parse()
+                .map_err(|_| format!("invalid locale {locale}"))?,
+            None => return Err("expected a locale".to_owned()),
+        };
+        let Some(messages) = value.get("messages").and_then(Value::as_array) else {
+            return Err("expected an array of messages".to_owned());
+        };
+        let mut bundle = Self::new(locale);
+        for entry in messages {
+            let Some(key) = entry.get("key").and_then(Value::as_str) else {
+                return Err("expected a key".to_owned());
+            };
+            let message = crate::precompiled::from_json(
+                entry.get("message").unwrap_or(&Value::Null),
+                Cow::Owned(bundle.locale().clone()),
+            )
+            .map_err(|err| format!("{key}: {err}"))?;
+            bundle.messages.insert(key.to_owned(), message);
+        }
+        Ok(bundle)
+    }
+
+    /// Removes the message of `key` and returns it, if any.
+    pub fn remove(&mut self, key: &str) -> Option<MessageFormat<'static>> {
+        self.metadata.remove(key);
+        self.messages.shift_remove(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&MessageFormat<'static>> {
+        self.messages.get(key)
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<&Metadata> {
+        self.metadata.get(key)
+    }
+
+    pub fn set_metadata(&mut self, key: impl Into<String>, metadata: Metadata) {
+        self.metadata.insert(key.into(), metadata);
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.messages.contains_key(key)
+    }
+
+    /// Keys in the order their messages were added.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Formats the message of `key`. Returns `None` if there is no such key.
+    pub fn format(
+        &self,
+        key: &str,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Option<String> {
+        let message = self.messages.get(key)?;
+        Some(message.format_with_context(&self.context, named_parameters))
+    }
+}
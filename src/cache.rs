@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{Block, Compatibility};
+
+#[derive(Default)]
+struct ParseCache {
+    capacity: usize,
+    entries: HashMap<Compatibility, HashMap<String, Vec<Block>>>,
+}
+
+static PARSE_CACHE: Lazy<Mutex<ParseCache>> = Lazy::new(Default::default);
+/// Whether the capacity is positive, so that parsing with the cache disabled
+/// takes no lock
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Keeps up to `capacity` parsed patterns in a process-wide cache, so that
+/// messages created again for a pattern, e.g. per request, are not parsed
+/// again. The cache is cleared when it is full. It is disabled by default,
+/// i.e. with a capacity of 0.
+///
+/// Messages with an unknown argument handler and strictly parsed messages
+/// are not cached.
+pub fn set_parse_cache_capacity(capacity: usize) {
+    let mut cache = PARSE_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    cache.capacity = capacity;
+    ENABLED.store(capacity > 0, Ordering::Relaxed);
+    if capacity == 0 {
+        cache.entries.clear();
+    }
+}
+
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn get(pattern: &str, compatibility: Compatibility) -> Option<Vec<Block>> {
+    let cache = PARSE_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    cache.entries.get(&compatibility)?.get(pattern).cloned()
+}
+
+pub(crate) fn insert(pattern: String, compatibility: Compatibility, blocks: Vec<Block>) {
+    let mut cache = PARSE_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    if cache.capacity == 0 {
+        return;
+    }
+    if cache.entries.values().map(HashMap::len).sum::<usize>() >= cache.capacity {
+        cache.entries.clear();
+    }
+    cache
+        .entries
+        .entry(compatibility)
+        .or_default()
+        .insert(pattern, blocks);
+}
@@ -0,0 +1,42 @@
+use icu::{
+    casemap::{
+        titlecase::{TitlecaseOptions, TrailingCase},
+        CaseMapper, TitlecaseMapper,
+    },
+    locid::Locale,
+};
+
+/// Case adjustments applied to a formatted message, see
+/// [`crate::MessageFormat::with_casing`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Casing {
+    /// Leaves the message as it is.
+    #[default]
+    Unchanged,
+    /// Uppercases the first letter of the message.
+    CapitalizeFirst,
+    /// Uppercases the first letter of the message, and lowercases the first
+    /// letter of string parameters inserted elsewhere, e.g. for a noun that is
+    /// passed capitalized but may also appear mid-sentence.
+    SentenceCase,
+}
+
+/// Uppercases (titlecases) the first letter of `s`, skipping leading
+/// punctuation.
+pub(crate) fn capitalize_first(s: &str, locale: &Locale) -> String {
+    let mut options = TitlecaseOptions::default();
+    options.trailing_case = TrailingCase::Unchanged;
+    TitlecaseMapper::new().titlecase_segment_to_string(s, &locale.id, options)
+}
+
+/// Lowercases the first character of `s`.
+pub(crate) fn lowercase_first(s: &str, locale: &Locale) -> String {
+    let mut chars = s.chars();
+    let Some(first) = chars.next() else {
+        return String::new();
+    };
+    let mut result =
+        CaseMapper::new().lowercase_to_string(first.encode_utf8(&mut [0; 4]), &locale.id);
+    result.push_str(chars.as_str());
+    result
+}
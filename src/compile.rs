@@ -0,0 +1,406 @@
+//! Code generation for `compile_message!` of the `message-format-macros`
+//! crate, which turns a message into Rust code that is neither parsed nor
+//! interpreted at runtime.
+
+use std::fmt::Write;
+
+use icu::locid::Locale;
+use indexmap::IndexMap;
+
+use crate::{
+    param::{ARGUMENT_NAME, ARGUMENT_OFFSET},
+    Block, MessageFormat, ParamValue, ParseError, OTHER,
+};
+
+const RUNTIME: &str = "::message_format::__private";
+
+/// Returns the code of a closure formatting the message with parameters of
+/// type `&[(&str, ParamValue)]`, or the reason why the message is invalid.
+#[doc(hidden)]
+pub fn compile_message(pattern: &str, locale: &str) -> Result<String, String> {
+    let Ok(parsed_locale) = locale.parse::<Locale>() else {
+        return Err(format!("invalid locale {locale}"));
+    };
+    let message =
+        MessageFormat::parse_strict(pattern, &parsed_locale).map_err(|err| err.to_string())?;
+
+    let mut compiler = Compiler {
+        statics: String::new(),
+        numbers: 0,
+        plurals: 0,
+    };
+    let body = compiler.blocks(message.parsed(), None)?;
+    Ok(format!(
+        "{{\
+            {statics}\
+            static CONTEXT: {RUNTIME}::Lazy<::message_format::RenderContext> = \
+                {RUNTIME}::Lazy::new(|| {RUNTIME}::context({locale:?}));\
+            |params: &[(&str, ::message_format::ParamValue)]| -> ::std::string::String {{\
+                let context: &::message_format::RenderContext = &CONTEXT;\
+                let _ = (params, context);\
+                #[allow(unused_mut)]\
+                let mut out = ::std::string::String::new();\
+                {body}\
+                out\
+            }}\
+        }}",
+        statics = compiler.statics,
+    ))
+}
+
+/// Returns the arguments of a message, or the reason why the message is
+/// malformed, for `msgformat!` of the `message-format-macros` crate.
+#[doc(hidden)]
+pub fn message_arguments(pattern: &str) -> Result<Vec<String>, String> {
+    let locale = Locale::UND;
+    let message = MessageFormat::try_new(pattern, &locale).map_err(|err| err.to_string())?;
+    Ok(message.arguments())
+}
+
+struct Compiler {
+    /// Number styles, parsed once
+    statics: String,
+    numbers: usize,
+    plurals: usize,
+}
+
+impl Compiler {
+    /// `pound` is the variable holding the formatted number of the enclosing
+    /// plural or ordinal block.
+    fn blocks(&mut self, blocks: &[Block], pound: Option<&str>) -> Result<String, String> {
+        let mut code = String::new();
+        for block in blocks {
+            match block {
+                Block::String(text) => {
+                    let mut segments = text.split('#');
+                    self.push_text(&mut code, segments.next().unwrap_or_default());
+                    for segment in segments {
+                        let Some(pound) = pound else {
+                            return Err(ParseError::StrayPound.to_string());
+                        };
+                        write!(code, "out.push_str(&{pound});").unwrap();
+                        self.push_text(&mut code, segment);
+                    }
+                }
+                Block::Literal(text) => self.push_text(&mut code, text),
+                Block::Simple(name) => {
+                    write!(
+                        code,
+                        "{RUNTIME}::format_simple(&mut out, params, {name:?}, context);"
+                    )
+                    .unwrap();
+                }
+                Block::Number(name, options) => {
+                    let style = format!("NUMBER_{}", self.numbers);
+                    self.numbers += 1;
+                    write!(
+                        self.statics,
+                        "static {style}: {RUNTIME}::Lazy<{RUNTIME}::NumberStyle> = \
+                         {RUNTIME}::Lazy::new(|| {RUNTIME}::NumberStyle::parse({:?}));",
+                        options.to_skeleton()
+                    )
+                    .unwrap();
+                    write!(
+                        code,
+                        "{RUNTIME}::format_number(&mut out, params, {name:?}, &{style}, context);"
+                    )
+                    .unwrap();
+                }
+                Block::DateTime(name, style) => {
+                    write!(
+                        code,
+                        "{RUNTIME}::format_date_time(&mut out, params, {name:?}, {:?}, {:?}, context);",
+                        style.typ(),
+                        style.style()
+                    )
+                    .unwrap();
+                }
+                Block::TagOpen(_) | Block::TagClose(_) => {
+                    return Err("tags are not supported".to_owned());
+                }
+                Block::Custom(_, typ, _) => {
+                    return Err(format!("argument type {typ} is not supported"));
+                }
+                Block::Select(branches) => {
+                    let (name, keys) = argument_and_keys(branches);
+                    write!(
+                        code,
+                        "match {RUNTIME}::select(params, {name:?}, &{keys:?}) {{\
+                            ::std::result::Result::Err(err) => out.push_str(&err),"
+                    )
+                    .unwrap();
+                    self.branches(&mut code, branches, &keys, true, pound)?;
+                    code.push('}');
+                }
+                Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+                    let (name, keys) = argument_and_keys(branches);
+                    let offset = match branches[&ARGUMENT_OFFSET].first() {
+                        Some(Block::String(offset)) => offset.as_str(),
+                        _ => "0",
+                    };
+                    let ordinal = matches!(block, Block::Ordinal(..));
+                    // nested blocks have their own `#`
+                    let pound = format!("pound_{}", self.plurals);
+                    self.plurals += 1;
+                    write!(
+                        code,
+                        "match {RUNTIME}::plural(params, {name:?}, {offset:?}, {ordinal}, &{keys:?}, context) {{\
+                            ::std::result::Result::Err(err) => out.push_str(&err),\
+                            ::std::result::Result::Ok((index, {pound})) => match index {{"
+                    )
+                    .unwrap();
+                    self.branches(&mut code, branches, &keys, false, Some(&pound))?;
+                    code.push_str("}}");
+                }
+            }
+        }
+        Ok(code)
+    }
+
+    /// Match arms of the branches at the indices of `keys`, and of `other`.
+    /// The indices of select blocks are wrapped in `Ok`.
+    fn branches(
+        &mut self,
+        code: &mut String,
+        branches: &IndexMap<ParamValue, Vec<Block>>,
+        keys: &[String],
+        is_select: bool,
+        pound: Option<&str>,
+    ) -> Result<(), String> {
+        for (index, key) in keys.iter().enumerate() {
+            let branch = &branches[&key_value(key)];
+            if is_select {
+                write!(
+                    code,
+                    "::std::result::Result::Ok(::std::option::Option::Some({index})) => {{"
+                )
+                .unwrap();
+            } else {
+                write!(code, "::std::option::Option::Some({index}) => {{").unwrap();
+            }
+            code.push_str(&self.blocks(branch, pound)?);
+            code.push('}');
+        }
+        code.push_str("_ => {");
+        code.push_str(&self.blocks(&branches[&OTHER], pound)?);
+        code.push('}');
+        Ok(())
+    }
+
+    fn push_text(&self, code: &mut String, text: &str) {
+        if !text.is_empty() {
+            write!(code, "out.push_str({text:?});").unwrap();
+        }
+    }
+}
+
+/// Returns the argument name and the keys of the branches except `other`.
+fn argument_and_keys(branches: &IndexMap<ParamValue, Vec<Block>>) -> (String, Vec<String>) {
+    let Some(Block::String(name)) = branches[&ARGUMENT_NAME].first() else {
+        panic!("invalid argument name");
+    };
+    let keys = branches
+        .keys()
+        .filter(|key| **key != ARGUMENT_NAME && **key != ARGUMENT_OFFSET && **key != OTHER)
+        .map(ToString::to_string)
+        .collect();
+    (name.clone(), keys)
+}
+
+/// Inverse of `ToString` for the keys of branches.
+fn key_value(key: &str) -> ParamValue {
+    ParamValue::parse_number(key).unwrap_or_else(|| key.to_owned().into())
+}
+
+/// Functions called by the code of [`compile_message`].
+#[doc(hidden)]
+pub mod runtime {
+    use fixed_decimal::FixedDecimal;
+
+    pub use once_cell::sync::Lazy;
+
+    use super::key_value;
+    use crate::{
+        datetime::DateTimeStyle, number::NumberOptions, param::subtract_offset, MessageFormat,
+        ParamValue, RenderContext,
+    };
+
+    const CATEGORIES: [&str; 5] = ["zero", "one", "two", "few", "many"];
+
+    pub struct NumberStyle(NumberOptions);
+
+    impl NumberStyle {
+        pub fn parse(skeleton: &str) -> Self {
+            Self(NumberOptions::parse_style(skeleton).expect("invalid number style"))
+        }
+    }
+
+    pub fn context(locale: &str) -> RenderContext {
+        RenderContext::new(locale.parse().expect("invalid locale"))
+    }
+
+    /// The last parameter of a name wins, as in a `HashMap` collected from
+    /// the parameters.
+    fn param<'p>(params: &'p [(&str, ParamValue)], name: &str) -> Result<&'p ParamValue, String> {
+        params
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| format!("Undefined parameter - {name}"))
+    }
+
+    pub fn format_simple(
+        out: &mut String,
+        params: &[(&str, ParamValue)],
+        name: &str,
+        context: &RenderContext,
+    ) {
+        let value = match param(params, name) {
+            Ok(value) if value.is_none() => return,
+            Ok(value) => value,
+            Err(err) => return out.push_str(&err),
+        };
+        if let Some((pattern, bound_params)) = value.as_message() {
+            let mut sub_params: Vec<(String, ParamValue)> = params
+                .iter()
+                .filter(|(n, _)| *n != name)
+                .map(|(n, value)| ((*n).to_owned(), value.clone()))
+                .collect();
+            sub_params.extend(bound_params.iter().cloned());
+            out.push_str(
+                &MessageFormat::new(pattern, context.locale())
+                    .format_with_context(context, sub_params),
+            );
+        } else {
+            out.push_str(&value.format_with_context(context));
+        }
+    }
+
+    pub fn format_number(
+        out: &mut String,
+        params: &[(&str, ParamValue)],
+        name: &str,
+        style: &NumberStyle,
+        context: &RenderContext,
+    ) {
+        let value = match param(params, name) {
+            Ok(value) => value,
+            Err(err) => return out.push_str(&err),
+        };
+        let overridden_options = value
+            .number_options()
+            .map(|overrides| style.0.overridden_by(overrides));
+        let options = overridden_options.as_ref().unwrap_or(&style.0);
+        if let Some((start, end)) = value.as_fixed_decimal_range() {
+            return out.push_str(&options.format_range(start, end, context));
+        }
+        let Some(value) = value.as_fixed_decimal() else {
+            return out.push_str(&format!("Invalid parameter - {name}"));
+        };
+        out.push_str(&options.format(value, context));
+    }
+
+    pub fn format_date_time(
+        out: &mut String,
+        params: &[(&str, ParamValue)],
+        name: &str,
+        typ: &str,
+        style: &str,
+        context: &RenderContext,
+    ) {
+        let value = match param(params, name) {
+            Ok(value) => value,
+            Err(err) => return out.push_str(&err),
+        };
+        let style = DateTimeStyle::parse(typ, style).expect("invalid date or time style");
+        match style.format(value, context) {
+            Some(formatted) => out.push_str(&formatted),
+            None => out.push_str(&format!("Invalid parameter - {name}")),
+        }
+    }
+
+    /// Returns the index of the key matching the parameter, if any.
+    pub fn select(
+        params: &[(&str, ParamValue)],
+        name: &str,
+        keys: &[&str],
+    ) -> Result<Option<usize>, String> {
+        let value = param(params, name)?;
+        Ok(keys.iter().position(|key| key_value(key) == *value))
+    }
+
+    /// Returns the index of the key selected by the parameter, if any, and
+    /// the formatted number for `#`.
+    pub fn plural(
+        params: &[(&str, ParamValue)],
+        name: &str,
+        offset: &str,
+        ordinal: bool,
+        keys: &[&str],
+        context: &RenderContext,
+    ) -> Result<(Option<usize>, String), String> {
+        let value = param(params, name)?;
+        let number_options = value.number_options();
+        let has_categories = keys.iter().any(|key| CATEGORIES.contains(key));
+        let fdf = context.fixed_decimal_formatter();
+        let format = |value: FixedDecimal| match number_options {
+            Some(number_options) => number_options.format(value, context),
+            None => fdf.format_to_string(&value),
+        };
+        let position = |category: &str| keys.iter().position(|key| *key == category);
+
+        let offset: FixedDecimal = offset
+            .parse()
+            .map_err(|_| format!("Invalid offset - {offset}"))?;
+
+        if let Some((start, end)) = value.as_plural_operand_range() {
+            let (Some(start), Some(end)) = (
+                subtract_offset(&start, &offset),
+                subtract_offset(&end, &offset),
+            ) else {
+                return Err(format!("Invalid parameter - {name}"));
+            };
+            let category = if !has_categories {
+                "other"
+            } else if ordinal {
+                context.ordinal_range_category((&start).into(), (&end).into())
+            } else {
+                context.plural_range_category((&start).into(), (&end).into())
+            };
+            let options = number_options.cloned().unwrap_or_default();
+            return Ok((
+                position(category),
+                options.format_range(start, end, context),
+            ));
+        }
+
+        let Some(mut diff) = value
+            .as_plural_operand()
+            .and_then(|number| subtract_offset(&number, &offset))
+        else {
+            return Err(format!("Invalid parameter - {name}"));
+        };
+        if let Some(number_options) = number_options {
+            number_options.apply_precision(&mut diff);
+        }
+
+        let index = keys
+            .iter()
+            .position(|key| key_value(key) == *value)
+            .or_else(|| {
+                let category = if has_categories {
+                    let operands = value.plural_operands().unwrap_or((&diff).into());
+                    if ordinal {
+                        context.ordinal_category(operands)
+                    } else {
+                        context.plural_category(operands)
+                    }
+                } else {
+                    "other"
+                };
+                position(category)
+            });
+        Ok((index, format(diff)))
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use fixed_decimal::FixedDecimal;
+use icu::{
+    datetime::{options::length, DateFormatter, DateTimeFormatter, TimeFormatter},
+    locid::{extensions::unicode::Value, Locale},
+    plurals::{PluralOperands, PluralRuleType, PluralRules, PluralRulesWithRanges},
+};
+use icu_decimal::{options::GroupingStrategy, FixedDecimalFormatter};
+use icu_experimental::{
+    compactdecimal::CompactDecimalFormatter, dimension::currency::formatter::CurrencyFormatter,
+};
+use icu_provider::AnyProvider;
+#[cfg(feature = "buffer_provider")]
+use icu_provider::BufferProvider;
+use once_cell::sync::OnceCell;
+use tinystr::{tinystr, TinyAsciiStr};
+
+use crate::{
+    format::category_name,
+    number::{
+        parse_currency_code, region_currency, with_numbering_system, Notation, NumberSymbols,
+        PercentPattern,
+    },
+    provider::{try_new, DataSource},
+};
+
+/// Numbering system and grouping of a number style
+type DecimalOptions = (Option<Value>, GroupingStrategy);
+
+/// Compact display, numbering system and grouping of a compact number style
+type CompactOptions = (Notation, Option<Value>, GroupingStrategy);
+
+/// Formatters of currency patterns by numbering system
+#[derive(Default)]
+struct CurrencyFormatters(Mutex<HashMap<Option<Value>, Arc<CurrencyFormatter>>>);
+
+impl fmt::Debug for CurrencyFormatters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CurrencyFormatters")
+    }
+}
+
+/// Locale and ICU formatters, which are created on first use and reused by
+/// every message formatted with the context, see
+/// [`crate::MessageFormat::format_with_context`].
+///
+/// A context is `Send + Sync`, so e.g. a server can keep one context per
+/// locale in its state and share it between requests.
+#[derive(Debug)]
+pub struct RenderContext {
+    locale: Locale,
+    data: DataSource,
+    default_currency: Option<TinyAsciiStr<3>>,
+    cardinal: OnceCell<PluralRulesWithRanges<PluralRules>>,
+    ordinal: OnceCell<PluralRules>,
+    fdf: OnceCell<FixedDecimalFormatter>,
+    /// Formatters of number styles with a numbering system or grouping
+    decimal_formatters: Mutex<HashMap<DecimalOptions, Arc<FixedDecimalFormatter>>>,
+    number_symbols: OnceCell<NumberSymbols>,
+    /// Formatters of compact number styles, e.g. `::compact-short`
+    compact_decimal_formatters: Mutex<HashMap<CompactOptions, Arc<CompactDecimalFormatter>>>,
+    currency_formatters: CurrencyFormatters,
+    /// Percent patterns by numbering system
+    percent_patterns: Mutex<HashMap<Option<Value>, Arc<PercentPattern>>>,
+    /// Formatters of `date` and `time` arguments by style
+    date_formatters: Mutex<HashMap<length::Date, Arc<DateFormatter>>>,
+    time_formatters: Mutex<HashMap<length::Time, Arc<TimeFormatter>>>,
+    /// Formatter of date-time parameters of simple arguments
+    date_time_formatter: OnceCell<DateTimeFormatter>,
+}
+
+impl RenderContext {
+    pub fn new(locale: Locale) -> Self {
+        Self::with_data(locale, DataSource::Compiled)
+    }
+
+    /// Creates a context whose formatters load their data from `provider`
+    /// instead of the data compiled into the binary.
+    pub fn new_with_any_provider(
+        locale: Locale,
+        provider: Arc<dyn AnyProvider + Send + Sync>,
+    ) -> Self {
+        Self::with_data(locale, DataSource::Any(provider))
+    }
+
+    /// Creates a context whose formatters deserialize their data from
+    /// `provider`, e.g. a postcard blob of a subset of the locales.
+    #[cfg(feature = "buffer_provider")]
+    pub fn new_with_buffer_provider(
+        locale: Locale,
+        provider: Arc<dyn BufferProvider + Send + Sync>,
+    ) -> Self {
+        Self::with_data(locale, DataSource::Buffer(provider))
+    }
+
+    pub(crate) fn with_data(locale: Locale, data: DataSource) -> Self {
+        Self {
+            locale,
+            data,
+            default_currency: None,
+            cardinal: OnceCell::new(),
+            ordinal: OnceCell::new(),
+            fdf: OnceCell::new(),
+            decimal_formatters: Mutex::new(HashMap::new()),
+            number_symbols: OnceCell::new(),
+            compact_decimal_formatters: Mutex::new(HashMap::new()),
+            currency_formatters: CurrencyFormatters::default(),
+            percent_patterns: Mutex::new(HashMap::new()),
+            date_formatters: Mutex::new(HashMap::new()),
+            time_formatters: Mutex::new(HashMap::new()),
+            date_time_formatter: OnceCell::new(),
+        }
+    }
+
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    pub(crate) fn data(&self) -> &DataSource {
+        &self.data
+    }
+
+    /// Sets the currency of `{N, number, currency}` arguments, which is the
+    /// currency of the locale's region by default, e.g. `EUR` for `de-AT`.
+    /// Panics if `code` is not an ISO 4217 code like `CHF`.
+    pub fn with_default_currency(mut self, code: &str) -> Self {
+        self.default_currency = Some(parse_currency_code(code).expect("invalid currency code"));
+        self
+    }
+
+    /// Returns the currency of `{N, number, currency}` arguments, `XXX` (no
+    /// currency) if the locale has no region with a known currency.
+    pub(crate) fn default_currency(&self) -> TinyAsciiStr<3> {
+        self.default_currency.unwrap_or_else(|| {
+            self.locale
+                .id
+                .region
+                .and_then(|region| region_currency(region.as_str()))
+                .and_then(parse_currency_code)
+                .unwrap_or(tinystr!(3, "XXX"))
+        })
+    }
+
+    /// Parses a number formatted in the locale, e.g. `1.234,5` in German, so
+    /// that a localized value can be edited and formatted again. Grouping
+    /// separators are optional, and ASCII digits are accepted in every locale.
+    pub fn parse_number(&self, input: &str) -> Option<FixedDecimal> {
+        self.number_symbols
+            .get_or_init(|| NumberSymbols::new(self.fixed_decimal_formatter()))
+            .parse(input)
+    }
+
+    pub(crate) fn fixed_decimal_formatter(&self) -> &FixedDecimalFormatter {
+        self.fdf.get_or_init(|| {
+            try_new!(
+                &self.data,
+                FixedDecimalFormatter::{
+                    try_new,
+                    try_new_with_any_provider,
+                    try_new_with_buffer_provider
+                }(&(&self.locale).into(), Default::default())
+            )
+            .expect("missing locale")
+        })
+    }
+
+    /// Returns the formatter of a number style with a numbering system, e.g.
+    /// `::numbering-system/arab`, or with grouping options.
+    pub(crate) fn decimal_formatter(
+        &self,
+        numbering_system: Option<&Value>,
+        grouping: GroupingStrategy,
+    ) -> Arc<FixedDecimalFormatter> {
+        let mut formatters = self
+            .decimal_formatters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let key = (numbering_system.cloned(), grouping);
+        let formatter = formatters.entry(key).or_insert_with(|| {
+            let locale = match numbering_system {
+                Some(numbering_system) => with_numbering_system(&self.locale, numbering_system),
+                None => self.locale.clone(),
+            };
+            let formatter = try_new!(
+                &self.data,
+                FixedDecimalFormatter::{
+                    try_new,
+                    try_new_with_any_provider,
+                    try_new_with_buffer_provider
+                }(&(&locale).into(), grouping.into())
+            )
+            .expect("missing locale");
+            Arc::new(formatter)
+        });
+        Arc::clone(formatter)
+    }
+
+    /// Returns the formatter of a compact number style, e.g. of
+    /// `::compact-long group-off`.
+    pub(crate) fn compact_decimal_formatter(
+        &self,
+        notation: Notation,
+        numbering_system: Option<&Value>,
+        grouping: GroupingStrategy,
+    ) -> Arc<CompactDecimalFormatter> {
+        let mut formatters = self
+            .compact_decimal_formatters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let key = (notation, numbering_system.cloned(), grouping);
+        let formatter = formatters.entry(key).or_insert_with(|| {
+            let locale = match numbering_system {
+                Some(numbering_system) => with_numbering_system(&self.locale, numbering_system),
+                None => self.locale.clone(),
+            };
+            let locale = &(&locale).into();
+            let formatter = if notation == Notation::CompactLong {
+                try_new!(
+                    &self.data,
+                    CompactDecimalFormatter::{
+                        try_new_long,
+                        try_new_long_with_any_provider,
+                        try_new_long_with_buffer_provider
+                    }(locale, grouping.into())
+                )
+            } else {
+                try_new!(
+                    &self.data,
+                    CompactDecimalFormatter::{
+                        try_new_short,
+                        try_new_short_with_any_provider,
+                        try_new_short_with_buffer_provider
+                    }(locale, grouping.into())
+                )
+            };
+            Arc::new(formatter.expect("missing locale"))
+        });
+        Arc::clone(formatter)
+    }
+
+    /// Returns the formatter of the currency patterns of the locale with a
+    /// numbering system, e.g. of `::currency/EUR numbering-system/arab`.
+    pub(crate) fn currency_formatter(
+        &self,
+        numbering_system: Option<&Value>,
+    ) -> Arc<CurrencyFormatter> {
+        let mut formatters = self
+            .currency_formatters
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let formatter = formatters
+            .entry(numbering_system.cloned())
+            .or_insert_with(|| {
+                let locale = match numbering_system {
+                    Some(numbering_system) => with_numbering_system(&self.locale, numbering_system),
+                    None => self.locale.clone(),
+                };
+                let formatter = try_new!(
+                    &self.data,
+                    CurrencyFormatter::{
+                        try_new,
+                        try_new_with_any_provider,
+                        try_new_with_buffer_provider
+                    }(&(&locale).into(), Default::default())
+                )
+                .expect("missing locale");
+                Arc::new(formatter)
+            });
+        Arc::clone(formatter)
+    }
+
+    /// Returns the percent pattern of the locale with a numbering system, e.g.
+    /// of `::%x100 numbering-system/arab`.
+    pub(crate) fn percent_pattern(&self, numbering_system: Option<&Value>) -> Arc<PercentPattern> {
+        let mut patterns = self
+            .percent_patterns
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let pattern = patterns
+            .entry(numbering_system.cloned())
+            .or_insert_with(|| {
+                let locale = match numbering_system {
+                    Some(numbering_system) => with_numbering_system(&self.locale, numbering_system),
+                    None => self.locale.clone(),
+                };
+                Arc::new(PercentPattern::load(&self.data, &locale).expect("missing locale"))
+            });
+        Arc::clone(pattern)
+    }
+
+    /// Returns the formatter of dates of a length, e.g. of `{D, date, long}`.
+    pub(crate) fn date_formatter(&self, length: length::Date) -> Arc<DateFormatter> {
+        let mut formatters = self
+            .date_formatters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let formatter = formatters.entry(length).or_insert_with(|| {
+            let formatter = try_new!(
+                &self.data,
+                DateFormatter::{
+                    try_new_with_length,
+                    try_new_with_length_with_any_provider,
+                    try_new_with_length_with_buffer_provider
+                }(&(&self.locale).into(), length)
+            )
+            .expect("missing locale");
+            Arc::new(formatter)
+        });
+        Arc::clone(formatter)
+    }
+
+    /// Returns the formatter of times of a length, e.g. of `{T, time, short}`.
+    pub(crate) fn time_formatter(&self, length: length::Time) -> Arc<TimeFormatter> {
+        let mut formatters = self
+            .time_formatters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let formatter = formatters.entry(length).or_insert_with(|| {
+            let formatter = try_new!(
+                &self.data,
+                TimeFormatter::{
+                    try_new_with_length,
+                    try_new_with_length_with_any_provider,
+                    try_new_with_length_with_buffer_provider
+                }(&(&self.locale).into(), length)
+            )
+            .expect("missing locale");
+            Arc::new(formatter)
+        });
+        Arc::clone(formatter)
+    }
+
+    /// Returns the formatter of date-time parameters of simple arguments,
+    /// i.e. with short date and time.
+    pub(crate) fn date_time_formatter(&self) -> &DateTimeFormatter {
+        self.date_time_formatter.get_or_init(|| {
+            let options =
+                length::Bag::from_date_time_style(length::Date::Short, length::Time::Short);
+            try_new!(
+                &self.data,
+                DateTimeFormatter::{
+                    try_new,
+                    try_new_with_any_provider,
+                    try_new_with_buffer_provider
+                }(&(&self.locale).into(), options.into())
+            )
+            .expect("missing locale")
+        })
+    }
+
+    fn cardinal_rules(&self) -> &PluralRulesWithRanges<PluralRules> {
+        self.cardinal.get_or_init(|| {
+            try_new!(
+                &self.data,
+                PluralRulesWithRanges::{
+                    try_new_cardinal,
+                    try_new_cardinal_with_any_provider,
+                    try_new_cardinal_with_buffer_provider
+                }(&(&self.locale).into())
+            )
+            .expect("missing locale")
+        })
+    }
+
+    pub(crate) fn plural_category(&self, n: PluralOperands) -> &'static str {
+        category_name(self.cardinal_rules().rules().category_for(n))
+    }
+
+    pub(crate) fn plural_range_category(
+        &self,
+        start: PluralOperands,
+        end: PluralOperands,
+    ) -> &'static str {
+        category_name(self.cardinal_rules().category_for_range(start, end))
+    }
+
+    pub(crate) fn ordinal_category(&self, n: PluralOperands) -> &'static str {
+        let rules = self.ordinal.get_or_init(|| {
+            try_new!(
+                &self.data,
+                PluralRules::{try_new, try_new_with_any_provider, try_new_with_buffer_provider}(
+                    &(&self.locale).into(),
+                    PluralRuleType::Ordinal
+                )
+            )
+            .expect("missing locale")
+        });
+        category_name(rules.category_for(n))
+    }
+
+    pub(crate) fn ordinal_range_category(
+        &self,
+        _start: PluralOperands,
+        end: PluralOperands,
+    ) -> &'static str {
+        // There are no ordinal range rules, the end determines the suffix
+        self.ordinal_category(end)
+    }
+}
@@ -0,0 +1,62 @@
+use icu::datetime::options::length;
+
+use crate::{ParamValue, RenderContext};
+
+/// Type and style of `date` and `time` arguments, e.g. `{D, date, short}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DateTimeStyle {
+    Date(length::Date),
+    Time(length::Time),
+}
+
+impl DateTimeStyle {
+    /// Parses the ICU argument type and style, which is `medium` by default.
+    pub(crate) fn parse(typ: &str, style: &str) -> Option<Self> {
+        match (typ, style) {
+            ("date", "" | "medium") => Some(Self::Date(length::Date::Medium)),
+            ("date", "short") => Some(Self::Date(length::Date::Short)),
+            ("date", "long") => Some(Self::Date(length::Date::Long)),
+            ("date", "full") => Some(Self::Date(length::Date::Full)),
+            ("time", "" | "medium") => Some(Self::Time(length::Time::Medium)),
+            ("time", "short") => Some(Self::Time(length::Time::Short)),
+            ("time", "long") => Some(Self::Time(length::Time::Long)),
+            ("time", "full") => Some(Self::Time(length::Time::Full)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn typ(&self) -> &'static str {
+        match self {
+            Self::Date(_) => "date",
+            Self::Time(_) => "time",
+        }
+    }
+
+    pub(crate) fn style(&self) -> &'static str {
+        match self {
+            Self::Date(length::Date::Short) | Self::Time(length::Time::Short) => "short",
+            Self::Date(length::Date::Long) | Self::Time(length::Time::Long) => "long",
+            Self::Date(length::Date::Full) | Self::Time(length::Time::Full) => "full",
+            _ => "medium",
+        }
+    }
+
+    /// Formats dates and date-times; times require a date-time. Returns `None`
+    /// for other parameters.
+    pub(crate) fn format(&self, value: &ParamValue, context: &RenderContext) -> Option<String> {
+        match *self {
+            Self::Date(length) => {
+                let date = value.as_iso_date()?;
+                let formatted = context
+                    .date_formatter(length)
+                    .format_to_string(&date.to_any())
+                    .expect("ISO date");
+                Some(formatted)
+            }
+            Self::Time(length) => {
+                let date_time = value.as_iso_date_time()?;
+                Some(context.time_formatter(length).format_to_string(date_time))
+            }
+        }
+    }
+}
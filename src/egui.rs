@@ -0,0 +1,40 @@
+use egui::{text::LayoutJob, TextFormat};
+
+use crate::MessageRenderer;
+
+/// Renders a message into an egui [`LayoutJob`], in which arguments are
+/// sections with the format returned by `style`, e.g. to highlight them.
+pub struct LayoutJobRenderer<F> {
+    job: LayoutJob,
+    format: TextFormat,
+    style: F,
+}
+
+impl<F: FnMut(&str, &TextFormat) -> TextFormat> LayoutJobRenderer<F> {
+    /// `style` gets the name of an argument and the format of the literal
+    /// text, and returns the format of the argument.
+    pub fn new(format: TextFormat, style: F) -> Self {
+        Self {
+            job: LayoutJob::default(),
+            format,
+            style,
+        }
+    }
+}
+
+impl<F: FnMut(&str, &TextFormat) -> TextFormat> MessageRenderer for LayoutJobRenderer<F> {
+    type Output = LayoutJob;
+
+    fn literal(&mut self, text: &str) {
+        self.job.append(text, 0.0, self.format.clone());
+    }
+
+    fn argument(&mut self, name: &str, value: &str) {
+        let format = (self.style)(name, &self.format);
+        self.job.append(value, 0.0, format);
+    }
+
+    fn finish(self) -> LayoutJob {
+        self.job
+    }
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, env};
+
+use fixed_decimal::FixedDecimal;
+
+use crate::{MessageFormat, ParamValue};
+
+/// Mapping of argument names to variable names, see
+/// [`ParamSource::with_case`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameCase {
+    /// Reads the argument `count` from the variable `count`.
+    #[default]
+    Unchanged,
+    /// Reads the argument `count` from the variable `COUNT`.
+    Upper,
+    /// Reads the argument `COUNT` from the variable `count`.
+    Lower,
+}
+
+/// Parameters read from environment variables or another map of variables,
+/// e.g. for shell-driven tools: with the prefix `APP_` and upper case names,
+/// the argument `count` is read from `APP_COUNT`.
+///
+/// Values which are written like numbers, e.g. `3` or `1.50`, are passed as
+/// numbers, so that they select plural branches and exact matches.
+#[derive(Debug, Clone, Default)]
+pub struct ParamSource {
+    vars: HashMap<String, String>,
+    prefix: String,
+    case: NameCase,
+}
+
+impl ParamSource {
+    /// Reads the environment variables of the process, skipping variables
+    /// which are not valid Unicode.
+    pub fn env() -> Self {
+        Self::from_vars(env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }))
+    }
+
+    /// Reads the given variables instead of the environment, e.g. for tests.
+    pub fn from_vars(
+        vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        Self {
+            vars: vars
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the prefix of the variable names, e.g. `APP_`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sets how argument names are mapped to variable names.
+    pub fn with_case(mut self, case: NameCase) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Returns the value of the argument `name`, if its variable is set.
+    pub fn get(&self, name: &str) -> Option<ParamValue> {
+        let name = match self.case {
+            NameCase::Unchanged => format!("{}{name}", self.prefix),
+            NameCase::Upper => format!("{}{}", self.prefix, name.to_uppercase()),
+            NameCase::Lower => format!("{}{}", self.prefix, name.to_lowercase()),
+        };
+        self.vars.get(&name).map(|value| parse_value(value))
+    }
+
+    /// Returns the parameters of all arguments of `message` whose variables
+    /// are set.
+    pub fn params(&self, message: &MessageFormat<'_>) -> Vec<(String, ParamValue)> {
+        message
+            .arguments()
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), self.get(&name)?)))
+            .collect()
+    }
+
+    /// Formats `message` with the parameters of [`ParamSource::params`].
+    pub fn format(&self, message: &MessageFormat<'_>) -> String {
+        let params = self.params(message);
+        message.format_with_params(params)
+    }
+}
+
+/// Values are numbers only if they are written like the number, so that e.g.
+/// the zip code `01234` stays text
+fn parse_value(value: &str) -> ParamValue {
+    if let Some(n) = value.parse::<i64>().ok().filter(|n| n.to_string() == value) {
+        return n.into();
+    }
+    match value.parse::<FixedDecimal>() {
+        Ok(n) if n.clone().trimmed_start().to_string() == value => n.into(),
+        _ => value.to_owned().into(),
+    }
+}
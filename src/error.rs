@@ -0,0 +1,81 @@
+use std::{error, fmt};
+
+/// Pattern rejected by [`crate::MessageFormat::parse_strict`] or
+/// [`crate::MessageFormat::try_new`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Block which is not an argument, e.g. `{-}`
+    UnknownBlock(String),
+    /// Argument type outside the supported ICU grammar, e.g. `date` in
+    /// `{D, date}`
+    UnknownArgumentType(String),
+    /// Plural or ordinal key which is neither a plural category nor an exact
+    /// match like `=1`
+    InvalidPluralKey(String),
+    /// Numeric select key, e.g. `1` in `{N, select, 1 {…} other {…}}`
+    NumericSelectKey(String),
+    /// Braces without an argument, i.e. `{}`
+    EmptyBlock,
+    /// Offset of a plural or ordinal block which is not `offset:N`, e.g.
+    /// `offset,1`
+    InvalidOffset(String),
+    /// `#` outside of plural and ordinal blocks, e.g. `Order #{ID}`
+    StrayPound,
+    /// `{` without a matching `}` or the other way around, at the given byte
+    /// position, see [`crate::extract_parts`]
+    UnbalancedBraces(usize),
+    /// `}` without a matching `{`
+    UnmatchedClosingBrace,
+    /// `{` without a matching `}`
+    UnclosedBrace,
+    /// Style of a number argument which is neither a known style nor a
+    /// skeleton, e.g. `@#@`
+    UnknownNumberStyle(String),
+    /// Style of a date or time argument other than `short`, `medium`, `long`
+    /// and `full`
+    UnknownDateTimeStyle(String),
+    /// Select, plural or ordinal block without an `other` branch
+    MissingOther(String),
+    /// Key of a select, plural or ordinal block without a branch, e.g. `one`
+    /// in `{N, plural, one # other {#}}`
+    MissingBranch(String),
+    /// Key of a joint select block with more parts than arguments, see
+    /// [`crate::MessageFormat::with_joint_select`]
+    TooManyKeys(String),
+    /// Tag without a closing tag in the same branch, see
+    /// [`crate::MessageFormat::with_tag_handler`]
+    UnclosedTag(String),
+    /// Closing tag without a matching opening tag
+    UnmatchedClosingTag(String),
+    /// Syntax error at the given byte position of a MessageFormat 2 message,
+    /// see [`crate::MessageFormat::try_new_mf2`]
+    InvalidMf2(usize, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownBlock(pattern) => write!(f, "unknown block type for pattern {pattern}"),
+            Self::UnknownArgumentType(typ) => write!(f, "unknown argument type {typ}"),
+            Self::InvalidPluralKey(key) => write!(f, "invalid plural key {key}"),
+            Self::NumericSelectKey(key) => write!(f, "numeric select key {key}"),
+            Self::EmptyBlock => f.write_str("empty block {}"),
+            Self::InvalidOffset(offset) => write!(f, "invalid offset {offset}"),
+            Self::StrayPound => f.write_str("# outside of plural and ordinal blocks"),
+            Self::UnbalancedBraces(pos) => write!(f, "unbalanced brace at {pos}"),
+            Self::UnmatchedClosingBrace => f.write_str("No matching { for }"),
+            Self::UnclosedBrace => f.write_str("There are mismatched { or } in the pattern"),
+            Self::UnknownNumberStyle(style) => write!(f, "unknown number style {style}"),
+            Self::UnknownDateTimeStyle(style) => write!(f, "unknown date or time style {style}"),
+            Self::MissingOther(typ) => write!(f, "missing other key in {typ} statement"),
+            Self::MissingBranch(key) => write!(f, "missing branch of key {key}"),
+            Self::UnclosedTag(name) => write!(f, "unclosed tag <{name}>"),
+            Self::UnmatchedClosingTag(name) => write!(f, "no matching <{name}> for </{name}>"),
+            Self::TooManyKeys(key) => write!(f, "too many keys in joint select branch {key}"),
+            Self::InvalidMf2(pos, message) => write!(f, "{message} at {pos}"),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
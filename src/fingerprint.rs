@@ -0,0 +1,38 @@
+use std::hash::Hasher;
+
+/// Version of the ICU4X data compiled into the crate, i.e. CLDR 45 as
+/// shipped with `icu` 1.5. Must be updated with the `icu` dependency, so
+/// that fingerprints change when plural rules or number symbols may have.
+pub(crate) const DATA_VERSION: &str = "icu-1.5/cldr-45";
+
+/// 64-bit FNV-1a, whose output unlike the one of `DefaultHasher` is the same
+/// on every platform and in every release
+pub(crate) struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv64 {
+    /// Writes `field` followed by a separator, so that e.g. `("ab", "c")` and
+    /// `("a", "bc")` differ
+    pub(crate) fn field(&mut self, field: &str) {
+        self.write(field.as_bytes());
+        self.write(&[0xff]);
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, fmt::Write};
+
+use fluent_syntax::{
+    ast::{Entry, Expression, InlineExpression, Pattern, PatternElement, VariantKey},
+    parser,
+};
+
+use crate::MessageFormat;
+
+const CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// Message or attribute of a Fluent resource, converted to a pattern, see
+/// [`crate::MessageBundle::load_ftl`].
+pub(crate) struct FtlMessage {
+    /// Message ID, or `message.attribute` for attributes
+    pub(crate) key: String,
+    /// The pattern, or why the message cannot be converted
+    pub(crate) pattern: Result<String, String>,
+    pub(crate) comment: Option<String>,
+}
+
+/// Converts the messages of a Fluent resource, failing on syntax errors.
+pub(crate) fn convert_resource(ftl: &str) -> Result<Vec<FtlMessage>, String> {
+    let resource = parser::parse(ftl).map_err(|(_, errors)| {
+        let err = &errors[0];
+        format!("error at {}..{}: {err}", err.pos.start, err.pos.end)
+    })?;
+
+    let terms: HashMap<&str, &Pattern<&str>> = resource
+        .body
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Term(term) => Some((term.id.name, &term.value)),
+            _ => None,
+        })
+        .collect();
+    let converter = Converter { terms: &terms };
+
+    let mut messages = Vec::new();
+    for entry in &resource.body {
+        let Entry::Message(message) = entry else {
+            continue;
+        };
+        let comment = message
+            .comment
+            .as_ref()
+            .map(|comment| comment.content.join("\n"));
+        if let Some(value) = &message.value {
+            messages.push(FtlMessage {
+                key: message.id.name.to_owned(),
+                pattern: converter.pattern(value, &mut Vec::new()),
+                comment: comment.clone(),
+            });
+        }
+        for attribute in &message.attributes {
+            messages.push(FtlMessage {
+                key: format!("{}.{}", message.id.name, attribute.id.name),
+                pattern: converter.pattern(&attribute.value, &mut Vec::new()),
+                comment: comment.clone(),
+            });
+        }
+    }
+    Ok(messages)
+}
+
+struct Converter<'r> {
+    /// Terms are inlined, e.g. `-brand = Firefox`
+    terms: &'r HashMap<&'r str, &'r Pattern<&'r str>>,
+}
+
+impl<'r> Converter<'r> {
+    /// `terms` are the terms being inlined, to reject cyclic references.
+    fn pattern(
+        &self,
+        pattern: &Pattern<&'r str>,
+        terms: &mut Vec<&'r str>,
+    ) -> Result<String, String> {
+        let mut out = String::new();
+        for element in &pattern.elements {
+            match element {
+                PatternElement::TextElement { value } => {
+                    out.push_str(&MessageFormat::escape(value));
+                }
+                PatternElement::Placeable { expression } => {
+                    self.expression(expression, terms, &mut out)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn expression(
+        &self,
+        expression: &Expression<&'r str>,
+        terms: &mut Vec<&'r str>,
+        out: &mut String,
+    ) -> Result<(), String> {
+        let (selector, variants) = match expression {
+            Expression::Inline(inline) => return self.inline(inline, terms, out),
+            Expression::Select { selector, variants } => (selector, variants),
+        };
+
+        let (name, typ) = match selector {
+            InlineExpression::VariableReference { id } => {
+                // numeric keys and plural categories select by number
+                let is_plural = variants.iter().any(|variant| match &variant.key {
+                    VariantKey::Identifier { name } => CATEGORIES.contains(name),
+                    VariantKey::NumberLiteral { .. } => true,
+                });
+                (id.name, if is_plural { "plural" } else { "select" })
+            }
+            InlineExpression::FunctionReference { id, arguments } if id.name == "NUMBER" => {
+                let [InlineExpression::VariableReference { id: variable }] =
+                    arguments.positional.as_slice()
+                else {
+                    return Err("NUMBER selectors require a variable".to_owned());
+                };
+                let typ = match arguments.named.as_slice() {
+                    [] => "plural",
+                    [argument]
+                        if argument.name.name == "type"
+                            && matches!(
+                                argument.value,
+                                InlineExpression::StringLiteral { value: "ordinal" }
+                            ) =>
+                    {
+                        "selectordinal"
+                    }
+                    _ => return Err("options of NUMBER selectors are not supported".to_owned()),
+                };
+                (variable.name, typ)
+            }
+            _ => return Err("selectors other than variables are not supported".to_owned()),
+        };
+
+        write!(out, "{{{}, {typ},", argument_name(name)).unwrap();
+        let mut default = None;
+        for variant in variants {
+            let key = match &variant.key {
+                VariantKey::Identifier { name } => (*name).to_owned(),
+                VariantKey::NumberLiteral { value } if typ == "select" => (*value).to_owned(),
+                VariantKey::NumberLiteral { value } => format!("={value}"),
+            };
+            let branch = self.pattern(&variant.value, terms)?;
+            write!(out, " {key} {{{branch}}}").unwrap();
+            if variant.default && key != "other" {
+                default = Some(branch);
+            }
+        }
+        // the default variant is the `other` branch of ICU
+        if let Some(branch) = default {
+            write!(out, " other {{{branch}}}").unwrap();
+        }
+        out.push('}');
+        Ok(())
+    }
+
+    fn inline(
+        &self,
+        inline: &InlineExpression<&'r str>,
+        terms: &mut Vec<&'r str>,
+        out: &mut String,
+    ) -> Result<(), String> {
+        match inline {
+            InlineExpression::StringLiteral { value } => {
+                let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+                out.push_str(&MessageFormat::escape(&value));
+            }
+            InlineExpression::NumberLiteral { value } => out.push_str(value),
+            InlineExpression::VariableReference { id } => {
+                write!(out, "{{{}}}", argument_name(id.name)).unwrap();
+            }
+            InlineExpression::FunctionReference { id, arguments } if id.name == "NUMBER" => {
+                match (arguments.positional.as_slice(), arguments.named.is_empty()) {
+                    ([InlineExpression::VariableReference { id: variable }], true) => {
+                        write!(out, "{{{}, number}}", argument_name(variable.name)).unwrap();
+                    }
+                    _ => return Err("options of NUMBER are not supported".to_owned()),
+                }
+            }
+            InlineExpression::FunctionReference { id, .. } => {
+                return Err(format!("function {} is not supported", id.name));
+            }
+            InlineExpression::MessageReference { id, .. } => {
+                return Err(format!("message reference {} is not supported", id.name));
+            }
+            InlineExpression::TermReference {
+                id,
+                attribute,
+                arguments,
+            } => {
+                if attribute.is_some() || arguments.is_some() {
+                    return Err(format!(
+                        "term -{} with attribute or arguments is not supported",
+                        id.name
+                    ));
+                }
+                let Some(term) = self.terms.get(id.name) else {
+                    return Err(format!("unknown term -{}", id.name));
+                };
+                if terms.contains(&id.name) {
+                    return Err(format!("cyclic term -{}", id.name));
+                }
+                terms.push(id.name);
+                out.push_str(&self.pattern(term, terms)?);
+                terms.pop();
+            }
+            InlineExpression::Placeable { expression } => {
+                self.expression(expression, terms, out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Argument names are words, so e.g. `$user-name` becomes `user_name`.
+fn argument_name(variable: &str) -> String {
+    variable.replace('-', "_")
+}
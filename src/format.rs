@@ -1,239 +1,585 @@
 use std::collections::HashMap;
 
-use icu::{
-    locid::Locale,
-    plurals::{PluralCategory, PluralOperands, PluralRules},
+use fixed_decimal::FixedDecimal;
+use icu::plurals::{PluralCategory, PluralOperands};
+use indexmap::IndexMap;
+
+use crate::{
+    casing::{capitalize_first, lowercase_first, Casing},
+    contains_pound,
+    datetime::DateTimeStyle,
+    number::NumberOptions,
+    param::{subtract_offset, ARGUMENT_NAME, ARGUMENT_OFFSET},
+    ArgumentFormatter, Block, BranchObserver, BranchPath, Callback, Compatibility, MessageFormat,
+    MessagePart, ParamKind, ParamValue, RenderContext, StrayPoundHandler, TagHandler, OTHER,
 };
-use icu_decimal::FixedDecimalFormatter;
 
-use crate::{placeholder, Block, ParamValue, OTHER};
+type CategorySelector = fn(&RenderContext, PluralOperands) -> &'static str;
+type RangeCategorySelector = fn(&RenderContext, PluralOperands, PluralOperands) -> &'static str;
+type PluralRules = (CategorySelector, RangeCategorySelector);
+
+const CARDINAL_RULES: PluralRules = (
+    RenderContext::plural_category,
+    RenderContext::plural_range_category,
+);
+
+/// Branch of a plural or ordinal block selected by a parameter
+struct PluralBranch<'b> {
+    argument_name: &'b str,
+    /// Key as reported to branch observers, e.g. `=0` for exact matches
+    key: String,
+    option: &'b Vec<Block>,
+    /// Number for `#`, i.e. the parameter minus the offset
+    diff: FixedDecimal,
+    /// End of range parameters
+    diff_end: Option<FixedDecimal>,
+}
 
 #[derive(Debug)]
 pub(crate) struct Formatter<'a> {
-    locale: &'a Locale,
-    initial_literals: &'a Vec<String>,
+    context: &'a RenderContext,
     parsed_pattern: &'a Vec<Block>,
     ignore_pound: bool,
-    fdf: Option<FixedDecimalFormatter>,
+    casing: Casing,
+    compatibility: Compatibility,
+    branch_observer: Option<&'a Callback<BranchObserver>>,
+    stray_pound_handler: Option<&'a Callback<StrayPoundHandler>>,
+    tag_handler: Option<&'a Callback<TagHandler>>,
+    select_aliases: &'a HashMap<String, ParamValue>,
+    argument_formatters: &'a HashMap<String, Callback<ArgumentFormatter>>,
+    parts: Vec<MessagePart>,
+    /// Parts before the open tags, with their names
+    open_tags: Vec<(String, Vec<MessagePart>)>,
+    /// Argument name, formatted number and kind of the `#` of the innermost
+    /// plural or ordinal block
+    pound: Option<(String, String, ParamKind)>,
+    stray_pound: bool,
 }
 
 impl<'a> Formatter<'a> {
     pub(crate) fn new(
-        locale: &'a Locale,
-        initial_literals: &'a Vec<String>,
+        context: &'a RenderContext,
+        message: &'a MessageFormat<'a>,
         parsed_pattern: &'a Vec<Block>,
         ignore_pound: bool,
     ) -> Self {
         Self {
-            locale,
+            context,
             parsed_pattern,
-            initial_literals,
             ignore_pound,
-            fdf: Default::default(),
+            casing: message.casing,
+            compatibility: message.compatibility,
+            branch_observer: message.branch_observer.as_ref(),
+            stray_pound_handler: message.stray_pound_handler.as_ref(),
+            tag_handler: message.tag_handler.as_ref(),
+            select_aliases: &message.select_aliases,
+            argument_formatters: &message.argument_formatters,
+            parts: Vec::new(),
+            open_tags: Vec::new(),
+            pound: None,
+            stray_pound: false,
         }
     }
 
-    fn fixed_decimal_formatter(&mut self) -> &FixedDecimalFormatter {
-        self.fdf.get_or_insert_with(|| {
-            FixedDecimalFormatter::try_new(&self.locale.into(), Default::default())
-                .expect("missing locale")
-        })
-    }
-
     pub(crate) fn format(
         &mut self,
-        named_parameters: Option<HashMap<String, ParamValue>>,
-    ) -> String {
+        named_parameters: Option<&HashMap<String, ParamValue>>,
+    ) -> Vec<MessagePart> {
         if self.parsed_pattern.is_empty() {
-            return String::new();
+            return Vec::new();
         }
 
-        let mut literals = self.initial_literals.clone();
-
-        let mut message_parts = Vec::new();
         self.format_block(
             self.parsed_pattern,
-            named_parameters.as_ref().unwrap_or(&HashMap::new()),
-            &mut literals,
-            &mut message_parts,
+            named_parameters.unwrap_or(&HashMap::new()),
         );
-        let mut message = message_parts.join("");
+        let mut parts = std::mem::take(&mut self.parts);
 
-        if !self.ignore_pound {
-            assert!(!message.contains('#'), "not all # were replaced");
+        if self.casing != Casing::Unchanged {
+            if let Some(part) = parts
+                .iter_mut()
+                .find(|part| part.text().chars().any(char::is_alphabetic))
+            {
+                let text = part.text_mut();
+                *text = capitalize_first(text, self.context.locale());
+            }
         }
 
-        while let Some(literal) = literals.pop() {
-            let placeholder = placeholder(literals.len());
-            message = message.replacen(&placeholder, &literal, 1);
+        if let Some(handler) = self.stray_pound_handler.filter(|_| self.stray_pound) {
+            (handler.0)(&parts.iter().map(MessagePart::text).collect::<String>());
         }
 
-        message
+        parts
+    }
+
+    fn push_argument(&mut self, name: &str, value: String, kind: ParamKind) {
+        self.parts.push(MessagePart::Argument {
+            name: name.to_owned(),
+            value,
+            kind,
+        });
+    }
+
+    /// Pushes the text replacing a block, e.g. for an undefined parameter.
+    fn push_error(&mut self, text: String) {
+        push_literal(&mut self.parts, &text);
+    }
+
+    /// Pushes text of the pattern, whose `#` are the number of the innermost
+    /// plural or ordinal block.
+    fn push_text(&mut self, text: &str) {
+        if self.ignore_pound || !text.contains('#') {
+            push_literal(&mut self.parts, text);
+            return;
+        }
+        let Some((name, value, kind)) = self.pound.clone() else {
+            self.stray_pound = true;
+            push_literal(&mut self.parts, text);
+            return;
+        };
+        let mut segments = text.split('#');
+        push_literal(&mut self.parts, segments.next().unwrap_or_default());
+        for segment in segments {
+            self.push_argument(&name, value.clone(), kind);
+            push_literal(&mut self.parts, segment);
+        }
+    }
+
+    /// Renders the children of the innermost open tag with the tag handler.
+    fn close_tag(&mut self) {
+        let (name, outer) = self.open_tags.pop().expect("logic error");
+        let children: String = std::mem::replace(&mut self.parts, outer)
+            .iter()
+            .map(MessagePart::text)
+            .collect();
+        let handler = self.tag_handler.expect("logic error");
+        push_literal(&mut self.parts, &(handler.0)(&name, &children));
     }
 
     fn format_block(
         &mut self,
         parsed_blocks: &[Block],
         named_parameters: &HashMap<String, ParamValue>,
-        literals: &mut Vec<String>,
-        result: &mut Vec<String>,
     ) {
         for current_pattern in parsed_blocks {
             match current_pattern {
-                Block::String(value) => {
-                    result.push(value.clone());
-                }
+                Block::String(text) => self.push_text(text),
+                Block::Literal(text) => push_literal(&mut self.parts, text),
                 Block::Simple(value) => {
-                    self.format_simple_placeholder(value, named_parameters, literals, result);
+                    self.format_simple_placeholder(value, named_parameters);
+                }
+                Block::Number(value, options) => {
+                    self.format_number_placeholder(value, options, named_parameters);
+                }
+                Block::DateTime(value, style) => {
+                    self.format_date_time_placeholder(value, style, named_parameters);
+                }
+                Block::TagOpen(name) => {
+                    let outer = std::mem::take(&mut self.parts);
+                    self.open_tags.push((name.clone(), outer));
+                }
+                Block::TagClose(_) => self.close_tag(),
+                Block::Custom(value, typ, style) => {
+                    self.format_custom_placeholder(value, typ, style, named_parameters);
                 }
                 Block::Select(map_pattern) => {
-                    self.format_select_block(map_pattern, named_parameters, literals, result);
+                    self.format_select_block(map_pattern, named_parameters);
                 }
-                Block::Plural(value) => {
+                Block::Plural(value, has_categories) => {
                     self.format_plural_ordinal_block(
                         value,
+                        *has_categories,
                         named_parameters,
-                        literals,
-                        plural_rules_select,
-                        result,
+                        CARDINAL_RULES,
                     );
                 }
-                Block::Ordinal(value) => {
+                Block::Ordinal(value, has_categories) => {
                     self.format_plural_ordinal_block(
                         value,
+                        *has_categories,
                         named_parameters,
-                        literals,
-                        ordinal_rules_select,
-                        result,
+                        self.ordinal_rules(),
                     );
                 }
             }
         }
     }
 
-    fn format_simple_placeholder(
+    /// Returns the branches selected by the parameters without formatting,
+    /// see [`MessageFormat::select_branches`]. Blocks whose parameter is
+    /// undefined or invalid are skipped.
+    pub(crate) fn select_branches(
         &self,
+        parsed_blocks: &[Block],
+        named_parameters: &HashMap<String, ParamValue>,
+        path: &mut BranchPath,
+    ) {
+        for block in parsed_blocks {
+            let branch = match block {
+                Block::String(_)
+                | Block::Literal(_)
+                | Block::Simple(_)
+                | Block::Number(..)
+                | Block::DateTime(..)
+                | Block::Custom(..)
+                | Block::TagOpen(_)
+                | Block::TagClose(_) => continue,
+                Block::Select(branches) => self.select_block_branch(branches, named_parameters),
+                Block::Plural(branches, has_categories)
+                | Block::Ordinal(branches, has_categories) => {
+                    let (plural_selector, plural_range_selector) = match block {
+                        Block::Ordinal(..) => self.ordinal_rules(),
+                        _ => CARDINAL_RULES,
+                    };
+                    self.plural_branch(
+                        branches,
+                        *has_categories,
+                        named_parameters,
+                        plural_selector,
+                        plural_range_selector,
+                    )
+                    .map(|branch| (branch.argument_name, branch.key, branch.option))
+                }
+            };
+            if let Ok((argument_name, key, option)) = branch {
+                path.push((argument_name.to_owned(), key));
+                self.select_branches(option, named_parameters, path);
+            }
+        }
+    }
+
+    fn ordinal_rules(&self) -> PluralRules {
+        match self.compatibility {
+            // Ordinals are not supported
+            // <https://github.com/dart-lang/i18n/blob/98e7b4aea2e6ff613ec273ca29f58938d9c5b23d/pkgs/intl/lib/message_format.dart#L771>
+            Compatibility::Dart => CARDINAL_RULES,
+            _ => (
+                RenderContext::ordinal_category,
+                RenderContext::ordinal_range_category,
+            ),
+        }
+    }
+
+    fn observe_branch(&self, argument: &str, key: String) {
+        if let Some(branch_observer) = self.branch_observer {
+            (branch_observer.0)(argument, &key);
+        }
+    }
+
+    fn undefined_parameter(&self, param: &str) -> String {
+        if self.compatibility == Compatibility::Icu {
+            format!("{{{param}}}")
+        } else {
+            format!("Undefined parameter - {param}")
+        }
+    }
+
+    fn format_simple_placeholder(
+        &mut self,
         param: &str,
         named_parameters: &HashMap<String, ParamValue>,
-        literals: &mut Vec<String>,
-        result: &mut Vec<String>,
     ) {
         let Some(value) = named_parameters.get(param) else {
-            result.push(format!("Undefined parameter - {param}"));
+            self.push_error(self.undefined_parameter(param));
             return;
         };
-        let value = value.format_with_locale(self.locale);
-        let placeholder = placeholder(literals.len());
-        literals.push(value);
-        result.push(placeholder);
+        let kind = value.kind();
+        let value = if let Some((pattern, params)) = value.as_message() {
+            let mut sub_parameters = named_parameters.clone();
+            // a message cannot contain itself
+            sub_parameters.remove(param);
+            sub_parameters.extend(params.iter().cloned());
+            MessageFormat::new(pattern, self.context.locale())
+                .with_compatibility(self.compatibility)
+                .format_with_context(self.context, sub_parameters)
+        } else {
+            match value.as_str() {
+                Some(value) if self.casing == Casing::SentenceCase => {
+                    lowercase_first(value, self.context.locale())
+                }
+                _ => value.format_with_context(self.context),
+            }
+        };
+        self.push_argument(param, value, kind);
+    }
+
+    fn format_number_placeholder(
+        &mut self,
+        param: &str,
+        options: &NumberOptions,
+        named_parameters: &HashMap<String, ParamValue>,
+    ) {
+        let Some(value) = named_parameters.get(param) else {
+            self.push_error(self.undefined_parameter(param));
+            return;
+        };
+        let overridden_options = value
+            .number_options()
+            .map(|overrides| options.overridden_by(overrides));
+        let options = overridden_options.as_ref().unwrap_or(options);
+        if let Some((start, end)) = value.as_fixed_decimal_range() {
+            let range = options.format_range(start, end, self.context);
+            self.push_argument(param, range, ParamKind::Range);
+            return;
+        }
+        let Some(value) = value.as_fixed_decimal() else {
+            self.push_error(format!("Invalid parameter - {param}"));
+            return;
+        };
+        let value = options.format(value, self.context);
+        self.push_argument(param, value, ParamKind::Number);
+    }
+
+    fn format_custom_placeholder(
+        &mut self,
+        param: &str,
+        typ: &str,
+        style: &str,
+        named_parameters: &HashMap<String, ParamValue>,
+    ) {
+        let Some(value) = named_parameters.get(param) else {
+            self.push_error(self.undefined_parameter(param));
+            return;
+        };
+        // e.g. precompiled messages without the formatter
+        let Some(formatter) = self.argument_formatters.get(typ) else {
+            self.push_error(format!("Unknown argument type - {typ}"));
+            return;
+        };
+        let text = (formatter.0)(value, style, self.context.locale());
+        self.push_argument(param, text, value.kind());
+    }
+
+    fn format_date_time_placeholder(
+        &mut self,
+        param: &str,
+        style: &DateTimeStyle,
+        named_parameters: &HashMap<String, ParamValue>,
+    ) {
+        let Some(value) = named_parameters.get(param) else {
+            self.push_error(self.undefined_parameter(param));
+            return;
+        };
+        let kind = value.kind();
+        let Some(value) = style.format(value, self.context) else {
+            self.push_error(format!("Invalid parameter - {param}"));
+            return;
+        };
+        self.push_argument(param, value, kind);
     }
 
     fn format_select_block(
         &mut self,
-        parsed_blocks: &HashMap<ParamValue, Vec<Block>>,
+        parsed_blocks: &IndexMap<ParamValue, Vec<Block>>,
         named_parameters: &HashMap<String, ParamValue>,
-        literals: &mut Vec<String>,
-        result: &mut Vec<String>,
     ) {
-        let Some(Block::String(argument_name)) = parsed_blocks
-            .get(&"argumentName".to_owned().into())
-            .and_then(|b| b.first())
+        match self.select_block_branch(parsed_blocks, named_parameters) {
+            Ok((argument_name, key, option)) => {
+                self.observe_branch(argument_name, key);
+                self.format_block(option, named_parameters);
+            }
+            Err(err) => self.push_error(err),
+        }
+    }
+
+    /// Returns the argument name, key and branch of a select block, or the
+    /// text replacing the block.
+    fn select_block_branch<'b>(
+        &self,
+        parsed_blocks: &'b IndexMap<ParamValue, Vec<Block>>,
+        named_parameters: &'a HashMap<String, ParamValue>,
+    ) -> Result<(&'b str, String, &'b Vec<Block>), String> {
+        let Some(Block::String(argument_name)) =
+            parsed_blocks.get(&ARGUMENT_NAME).and_then(|b| b.first())
         else {
             panic!("invalid argument name");
         };
 
         let Some(param) = named_parameters.get(argument_name) else {
-            result.push(format!("Undefined parameter - {argument_name}"));
-            return;
+            return Err(self.undefined_parameter(argument_name));
         };
 
-        let Some(option) = parsed_blocks
-            .get(param)
-            .or_else(|| parsed_blocks.get(&OTHER))
+        let Some((key, option)) = self
+            .select_branch(parsed_blocks, param)
+            .or_else(|| parsed_blocks.get_key_value(&OTHER))
         else {
             panic!("Invalid option or missing other option for select block");
         };
+        Ok((argument_name, key.to_string(), option))
+    }
 
-        self.format_block(option, named_parameters, literals, result);
+    /// Returns the branch of `key`, following the select aliases of keys
+    /// without a branch.
+    fn select_branch<'b>(
+        &self,
+        parsed_blocks: &'b IndexMap<ParamValue, Vec<Block>>,
+        mut key: &'a ParamValue,
+    ) -> Option<(&'b ParamValue, &'b Vec<Block>)> {
+        // a chain cannot be longer than the number of aliases, even if cyclic
+        for _ in 0..=self.select_aliases.len() {
+            if let Some(branch) = parsed_blocks.get_key_value(key) {
+                return Some(branch);
+            }
+            key = self.select_aliases.get(key.as_str()?)?;
+        }
+        None
     }
 
     fn format_plural_ordinal_block(
         &mut self,
-        parsed_blocks: &HashMap<ParamValue, Vec<Block>>,
+        parsed_blocks: &IndexMap<ParamValue, Vec<Block>>,
+        has_categories: bool,
         named_parameters: &HashMap<String, ParamValue>,
-        literals: &mut Vec<String>,
-        plural_selector: impl Fn(PluralOperands, &Locale) -> &'static str,
-        result: &mut Vec<String>,
+        (plural_selector, plural_range_selector): PluralRules,
     ) {
-        let Some(Block::String(argument_name)) = parsed_blocks
-            .get(&"argumentName".into())
-            .and_then(|b| b.first())
+        let PluralBranch {
+            argument_name,
+            key,
+            option,
+            diff,
+            diff_end,
+        } = match self.plural_branch(
+            parsed_blocks,
+            has_categories,
+            named_parameters,
+            plural_selector,
+            plural_range_selector,
+        ) {
+            Ok(branch) => branch,
+            Err(err) => {
+                self.push_error(err);
+                return;
+            }
+        };
+        self.observe_branch(argument_name, key);
+        let pound = (!self.ignore_pound && contains_pound(option)).then(|| {
+            let number_options = named_parameters[argument_name].number_options();
+            let fdf = self.context.fixed_decimal_formatter();
+            let format = |value: FixedDecimal| match number_options {
+                Some(number_options) => number_options.format(value, self.context),
+                None => fdf.format_to_string(&value),
+            };
+            match diff_end {
+                Some(diff_end) => {
+                    let options = number_options.cloned().unwrap_or_default();
+                    let range = options.format_range(diff, diff_end, self.context);
+                    (argument_name.to_owned(), range, ParamKind::Range)
+                }
+                None => (argument_name.to_owned(), format(diff), ParamKind::Number),
+            }
+        });
+        let outer = std::mem::replace(&mut self.pound, pound);
+        self.format_block(option, named_parameters);
+        self.pound = outer;
+    }
+
+    /// Selects the branch of a plural or ordinal block, or returns the text
+    /// replacing the block, e.g. for an undefined parameter. The plural rules
+    /// are only evaluated if the block `has_categories`.
+    fn plural_branch<'b>(
+        &self,
+        parsed_blocks: &'b IndexMap<ParamValue, Vec<Block>>,
+        has_categories: bool,
+        named_parameters: &HashMap<String, ParamValue>,
+        plural_selector: CategorySelector,
+        plural_range_selector: RangeCategorySelector,
+    ) -> Result<PluralBranch<'b>, String> {
+        let Some(Block::String(argument_name)) =
+            parsed_blocks.get(&ARGUMENT_NAME).and_then(|b| b.first())
         else {
             panic!("invalid argument name");
         };
-        let Some(Block::String(argument_offset)) = parsed_blocks
-            .get(&"argumentOffset".into())
-            .and_then(|b| b.first())
+        let Some(Block::String(argument_offset)) =
+            parsed_blocks.get(&ARGUMENT_OFFSET).and_then(|b| b.first())
         else {
             panic!("invalid argument offset");
         };
 
         let Some(plural_value) = named_parameters.get(argument_name) else {
-            result.push(format!("Undefined parameter - {argument_name}"));
-            return;
-        };
-
-        let Some(plural_value) = plural_value.as_decimal() else {
-            result.push(format!("Invalid parameter - {argument_name}"));
-            return;
+            return Err(self.undefined_parameter(argument_name));
         };
 
-        let Ok(argument_offset) = argument_offset.parse::<f64>() else {
-            result.push(format!("Invalid offset - {argument_offset}"));
-            return;
+        let Ok(argument_offset) = argument_offset.parse::<FixedDecimal>() else {
+            return Err(format!("Invalid offset - {argument_offset}"));
         };
 
-        let diff = plural_value - argument_offset;
+        let number_options = plural_value.number_options();
 
-        let option = match parsed_blocks.get(&named_parameters[argument_name]) {
-            Some(option) => option,
-            None => {
-                let Ok(diff_fixed_decimal) = diff.abs().to_string().parse() else {
-                    result.push(format!("Invalid parameter - {diff}"));
-                    return;
+        // `diff_end` is only set for range parameters
+        let (key, option, diff, diff_end) =
+            if let Some((start, end)) = plural_value.as_plural_operand_range() {
+                let (Some(start), Some(end)) = (
+                    subtract_offset(&start, &argument_offset),
+                    subtract_offset(&end, &argument_offset),
+                ) else {
+                    return Err(format!("Invalid parameter - {argument_name}"));
                 };
-                let item = plural_selector(diff_fixed_decimal, self.locale);
-                let Some(option) = parsed_blocks
-                    .get(&item.to_owned().into())
-                    .or_else(|| parsed_blocks.get(&OTHER))
+                let item = if has_categories {
+                    plural_range_selector(self.context, (&start).into(), (&end).into())
+                } else {
+                    "other"
+                };
+                let Some((key, option)) = parsed_blocks
+                    .get_key_value(&ParamValue::from(item))
+                    .or_else(|| parsed_blocks.get_key_value(&OTHER))
                 else {
                     panic!("Invalid option or missing other option for plural block");
                 };
-                option
-            }
-        };
-
-        let mut plural_result = Vec::new();
-        self.format_block(option, named_parameters, literals, &mut plural_result);
-        let plural = plural_result.join("");
-        if self.ignore_pound {
-            result.push(plural);
-        } else {
-            let diff_str = diff.to_string();
-            let diff_formatted = if let Ok(diff_fixed) = diff_str.parse() {
-                self.fixed_decimal_formatter().format_to_string(&diff_fixed)
+                (key.to_string(), option, start, Some(end))
             } else {
-                diff_str
+                let Some(mut diff) = plural_value
+                    .as_plural_operand()
+                    .and_then(|plural_value| subtract_offset(&plural_value, &argument_offset))
+                else {
+                    return Err(format!("Invalid parameter - {argument_name}"));
+                };
+                // select the branch of the number as it is displayed
+                if let Some(number_options) = number_options {
+                    number_options.apply_precision(&mut diff);
+                }
+
+                let (key, option) = match parsed_blocks.get_key_value(plural_value) {
+                    Some((key, option)) => (format!("={key}"), option),
+                    None => {
+                        let item = if has_categories {
+                            let operands = plural_value.plural_operands();
+                            plural_selector(self.context, operands.unwrap_or((&diff).into()))
+                        } else {
+                            "other"
+                        };
+                        let Some((key, option)) = parsed_blocks
+                            .get_key_value(&ParamValue::from(item))
+                            .or_else(|| parsed_blocks.get_key_value(&OTHER))
+                        else {
+                            panic!("Invalid option or missing other option for plural block");
+                        };
+                        (key.to_string(), option)
+                    }
+                };
+                (key, option, diff, None)
             };
-            result.push(plural.replace('#', &diff_formatted));
-        }
+        Ok(PluralBranch {
+            argument_name,
+            key,
+            option,
+            diff,
+            diff_end,
+        })
+    }
+}
+
+fn push_literal(parts: &mut Vec<MessagePart>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match parts.last_mut() {
+        Some(MessagePart::Literal(literal)) => literal.push_str(text),
+        _ => parts.push(MessagePart::Literal(text.to_owned())),
     }
 }
 
-fn plural_rules_select(n: PluralOperands, locale: &Locale) -> &'static str {
-    let rule = PluralRules::try_new(&locale.into(), icu::plurals::PluralRuleType::Cardinal)
-        .expect("missing locale");
-    match rule.category_for(n) {
+pub(crate) fn category_name(category: PluralCategory) -> &'static str {
+    match category {
         PluralCategory::Zero => "zero",
         PluralCategory::One => "one",
         PluralCategory::Two => "two",
@@ -242,9 +588,3 @@ fn plural_rules_select(n: PluralOperands, locale: &Locale) -> &'static str {
         PluralCategory::Other => "other",
     }
 }
-
-fn ordinal_rules_select(n: PluralOperands, locale: &Locale) -> &'static str {
-    // Ordinals are not supported
-    // <https://github.com/dart-lang/i18n/blob/98e7b4aea2e6ff613ec273ca29f58938d9c5b23d/pkgs/intl/lib/message_format.dart#L771>
-    plural_rules_select(n, locale)
-}
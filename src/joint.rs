@@ -0,0 +1,103 @@
+use indexmap::IndexMap;
+
+use crate::{
+    param::{ARGUMENT_NAME, ARGUMENT_OFFSET},
+    Block, ParamValue, ParseError, OTHER,
+};
+
+const CATEGORIES: [&str; 5] = ["zero", "one", "two", "few", "many"];
+
+/// Branch of a joint select block, with one key per argument
+pub(crate) type JointBranch = (Vec<String>, Vec<Block>);
+
+/// Turns a joint select block, e.g. `{GENDER|N, select, female|one {…}
+/// other {…}}`, into nested select and plural blocks, one level per
+/// argument. Arguments with a plural category or an exact match like `=1` in
+/// any key are plural arguments, the others select arguments.
+///
+/// `other` matches any value, and keys with fewer parts than arguments end
+/// with `other`. Of the branches matching the parameters, the one with the
+/// fewest `other` parts is selected, and the first of them on a tie.
+pub(crate) fn nest(
+    arguments: &[String],
+    mut branches: Vec<JointBranch>,
+) -> Result<Block, ParseError> {
+    for (keys, _) in &mut branches {
+        if keys.len() > arguments.len() {
+            return Err(ParseError::TooManyKeys(keys.join("|")));
+        }
+        keys.resize(arguments.len(), OTHER.to_string());
+    }
+    if !branches
+        .iter()
+        .any(|(keys, _)| keys.iter().all(|key| *key == OTHER.to_string()))
+    {
+        return Err(ParseError::MissingOther("select".to_owned()));
+    }
+    let plural: Vec<bool> = (0..arguments.len())
+        .map(|idx| {
+            branches.iter().any(|(keys, _)| {
+                keys[idx].starts_with('=') || CATEGORIES.contains(&keys[idx].as_str())
+            })
+        })
+        .collect();
+    let candidates: Vec<&JointBranch> = branches.iter().collect();
+    Ok(nest_level(arguments, &plural, 0, &candidates)
+        .pop()
+        .expect("joint select without arguments"))
+}
+
+fn nest_level(
+    arguments: &[String],
+    plural: &[bool],
+    idx: usize,
+    candidates: &[&JointBranch],
+) -> Vec<Block> {
+    let other = OTHER.to_string();
+    if idx == arguments.len() {
+        // the most specific branch, i.e. the first with the fewest `other`
+        let specificity = |keys: &[String]| keys.iter().filter(|key| **key != other).count();
+        let mut best = candidates[0];
+        for candidate in &candidates[1..] {
+            if specificity(&candidate.0) > specificity(&best.0) {
+                best = candidate;
+            }
+        }
+        return best.1.clone();
+    }
+
+    let mut values: Vec<&str> = Vec::new();
+    for (keys, _) in candidates {
+        if keys[idx] != other && !values.contains(&keys[idx].as_str()) {
+            values.push(&keys[idx]);
+        }
+    }
+
+    let mut result = IndexMap::new();
+    result.insert(ARGUMENT_NAME, vec![Block::String(arguments[idx].clone())]);
+    if plural[idx] {
+        result.insert(ARGUMENT_OFFSET, vec![Block::String("0".to_owned())]);
+    }
+    for value in values {
+        let matching: Vec<&JointBranch> = candidates
+            .iter()
+            .copied()
+            .filter(|(keys, _)| keys[idx] == value || keys[idx] == other)
+            .collect();
+        let key = value.strip_prefix('=').unwrap_or(value);
+        let key = ParamValue::parse_number(key).unwrap_or_else(|| key.to_owned().into());
+        result.insert(key, nest_level(arguments, plural, idx + 1, &matching));
+    }
+    let matching: Vec<&JointBranch> = candidates
+        .iter()
+        .copied()
+        .filter(|(keys, _)| keys[idx] == other)
+        .collect();
+    result.insert(OTHER, nest_level(arguments, plural, idx + 1, &matching));
+
+    if plural[idx] {
+        vec![Block::plural(result)]
+    } else {
+        vec![Block::Select(result)]
+    }
+}
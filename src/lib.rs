@@ -1,161 +1,1140 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt, hash::Hasher, sync::Arc};
 
+use branches::combinations;
+use datetime::DateTimeStyle;
+use fingerprint::Fnv64;
 use format::Formatter;
-use icu::locid::Locale;
-use once_cell::sync::Lazy;
+use icu::locid::{extensions::unicode::Value, Locale};
+use icu_provider::AnyProvider;
+#[cfg(feature = "buffer_provider")]
+use icu_provider::BufferProvider;
+use indexmap::IndexMap;
+use memchr::memchr2_iter;
+use number::{with_numbering_system, NumberOptions};
+use once_cell::sync::{Lazy, OnceCell};
 use param::{ARGUMENT_NAME, ARGUMENT_OFFSET, OTHER};
+use provider::DataSource;
 use regex::{Captures, Regex};
+use tokenize::find_quotes;
+use truncate::truncate;
 
-pub use param::ParamValue;
+pub use args::MessageArgs;
+pub use branches::BranchPath;
+pub use builder::{Branches, MessageBuilder};
+pub use bundle::MessageBundle;
+pub use cache::set_parse_cache_capacity;
+pub use casing::Casing;
+#[doc(hidden)]
+pub use compile::{compile_message, message_arguments, runtime as __private};
+pub use context::RenderContext;
+#[cfg(feature = "egui")]
+pub use egui::LayoutJobRenderer;
+pub use env::{NameCase, ParamSource};
+pub use error::ParseError;
+#[cfg(feature = "tokio")]
+pub use loader::FileFetcher;
+#[cfg(feature = "async")]
+pub use loader::ResourceFetcher;
+pub use metadata::Metadata;
+pub use param::{ParamKind, ParamValue};
+pub use parts::{AnsiText, Html, MessagePart, MessageRenderer, PlainText};
+pub use registry::{AuditIssue, Fallback, Registry};
+pub use report::{Diagnostic, Report, Severity};
+pub use samples::{plural_samples, PluralSamples};
+pub use structured::StructuredMessage;
+pub use tokenize::{extract_parts, PatternPart, PatternPartKind};
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+pub use web::{Localization, Messages};
 
+mod args;
+mod branches;
+pub mod build;
+mod builder;
+mod bundle;
+mod cache;
+mod casing;
+mod compile;
+mod context;
+mod datetime;
+#[cfg(feature = "egui")]
+mod egui;
+mod env;
+mod error;
+mod fingerprint;
+#[cfg(feature = "fluent")]
+mod fluent;
 mod format;
+mod joint;
+#[cfg(feature = "async")]
+mod loader;
+mod metadata;
+mod mf2;
+mod number;
 mod param;
+mod parts;
+#[cfg(feature = "precompiled")]
+mod precompiled;
+mod provider;
+mod registry;
+mod report;
+mod samples;
+mod structured;
+mod tokenize;
+mod tree;
+mod truncate;
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+mod web;
 
-static PLURAL_BLOCK_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^\s*(\w+)\s*,\s*plural\s*,(?:\s*offset:(\d+))?").unwrap());
-static ORDINAL_BLOCK_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^\s*(\w+)\s*,\s*selectordinal\s*,").unwrap());
+// offsets may be negative or fractional, e.g. `offset:-1` or `offset:0.5`; exact
+// matches like `=1` always compare the value before the offset is subtracted
+static PLURAL_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(\w+)\s*,\s*plural\s*,(?:\s*offset\s*:\s*(-?\d+(?:\.\d+)?))?").unwrap()
+});
+static ORDINAL_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(\w+)\s*,\s*selectordinal\s*,(?:\s*offset\s*:\s*(-?\d+(?:\.\d+)?))?").unwrap()
+});
 static SELECT_BLOCK_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^\s*(\w+)\s*,\s*select\s*,").unwrap());
+static JOINT_SELECT_BLOCK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(\w+(?:\s*\|\s*\w+)+)\s*,\s*select\s*,").unwrap());
+static NUMBER_BLOCK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(\w+)\s*,\s*number\s*(?:,(.*))?$").unwrap());
+static DATE_TIME_BLOCK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(\w+)\s*,\s*(date|time)\s*(?:,(.*))?$").unwrap());
 
+static ARGUMENT_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*\w+\s*$").unwrap());
 static KV_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*=?(\w+)\s*").unwrap());
 static WHITESPACES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
 
+/// MessageFormat implementation whose behavior is reproduced, see
+/// [`MessageFormat::with_compatibility`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Compatibility {
+    /// Behavior of this crate
+    #[default]
+    Native,
+    /// `goog.i18n.MessageFormat` of the Closure library
+    Closure,
+    /// `MessageFormat` of Dart's `package:intl`, which this crate was ported
+    /// from. It has no `number`, `date` and `time` argument types, so
+    /// `{N, number}` is a simple argument named `N, number`, and selects the branches of
+    /// `selectordinal` by the cardinal plural rules.
+    Dart,
+    /// ICU4C/ICU4J, following the ICU specification strictly: exact plural
+    /// matches require an `=` (`=1` instead of `1`), and missing arguments
+    /// are rendered as `{NAME}`.
+    Icu,
+}
+
+/// Rendering of `None` parameters, see [`MessageFormat::with_none`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoneParam {
+    /// Renders the given text, e.g. `""` or `"—"`.
+    Text(Cow<'static, str>),
+    /// Handles the parameter as if it was not passed at all.
+    Undefined,
+}
+
+impl Default for NoneParam {
+    fn default() -> Self {
+        Self::Text(Cow::Borrowed(""))
+    }
+}
+
+/// Type of the values an argument expects, inferred from its blocks, see
+/// [`MessageFormat::argument_types`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentType {
+    /// Any value, e.g. `{NAME}`
+    Any,
+    /// Number, e.g. of `{N, number}` or `{N, plural, ...}`
+    Number,
+    /// Date or date-time, e.g. of `{D, date}` or `{D, time, short}`
+    DateTime,
+    /// One of the keys of select blocks, or any value for `other`
+    Select(Vec<String>),
+}
+
+type UnknownArgumentHandler = dyn Fn(&str) -> String + Send + Sync;
+type BranchObserver = dyn Fn(&str, &str) + Send + Sync;
+type StrayPoundHandler = dyn Fn(&str) + Send + Sync;
+type TagHandler = dyn Fn(&str, &str) -> String + Send + Sync;
+type ArgumentFormatter = dyn Fn(&ParamValue, &str, &Locale) -> String + Send + Sync;
+
+/// Boxed callback, so that `MessageFormat` can derive `Debug`
+struct Callback<F: ?Sized>(Box<F>);
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
 #[derive(Debug)]
 pub struct MessageFormat<'l> {
-    pattern: Option<String>,
-    initial_literals: Vec<String>,
-    parsed_pattern: Vec<Block>,
-    locale: &'l Locale,
+    pattern: String,
+    /// Parsed on first use, so that options which change parsing can be set
+    /// after [`MessageFormat::new`]
+    parsed: OnceCell<Vec<Block>>,
+    locale: Cow<'l, Locale>,
+    data: DataSource,
+    /// Formatters of the locale, created on first use and reused unless a
+    /// context is passed, see [`MessageFormat::format_with_context`]
+    context: OnceCell<RenderContext>,
+    numbering_system: Option<Value>,
+    none: NoneParam,
+    casing: Casing,
+    max_len: Option<usize>,
+    compatibility: Compatibility,
+    strict: bool,
+    unknown_argument_handler: Option<Callback<UnknownArgumentHandler>>,
+    branch_observer: Option<Callback<BranchObserver>>,
+    stray_pound_handler: Option<Callback<StrayPoundHandler>>,
+    tag_handler: Option<Callback<TagHandler>>,
+    /// Formatters of custom argument types by type name
+    argument_formatters: HashMap<String, Callback<ArgumentFormatter>>,
+    /// Keys of select branches by the keys they are aliases of
+    select_aliases: HashMap<String, ParamValue>,
+    joint_select: bool,
 }
 
 impl<'l> MessageFormat<'l> {
     pub fn new(pattern: impl Into<String>, locale: &'l Locale) -> Self {
+        Self::with_locale(pattern.into(), Cow::Borrowed(locale))
+    }
+
+    /// Creates a message which owns its locale, e.g. to store it in a struct
+    /// or a `static` without keeping the locale alive elsewhere.
+    pub fn new_owned(pattern: impl Into<String>, locale: Locale) -> MessageFormat<'static> {
+        MessageFormat::with_locale(pattern.into(), Cow::Owned(locale))
+    }
+
+    /// Creates a message whose formatters load their data from `provider`
+    /// instead of the data compiled into the binary, e.g. to ship the data of
+    /// fewer locales. [`MessageFormat::format_with_context`] uses the provider
+    /// of the context.
+    pub fn new_with_any_provider(
+        pattern: impl Into<String>,
+        locale: &'l Locale,
+        provider: Arc<dyn AnyProvider + Send + Sync>,
+    ) -> Self {
+        let mut message_format = Self::new(pattern, locale);
+        message_format.data = DataSource::Any(provider);
+        message_format
+    }
+
+    /// Creates a message whose formatters deserialize their data from
+    /// `provider`, e.g. a postcard blob exported with `icu4x-datagen`, see
+    /// [`MessageFormat::new_with_any_provider`].
+    #[cfg(feature = "buffer_provider")]
+    pub fn new_with_buffer_provider(
+        pattern: impl Into<String>,
+        locale: &'l Locale,
+        provider: Arc<dyn BufferProvider + Send + Sync>,
+    ) -> Self {
+        let mut message_format = Self::new(pattern, locale);
+        message_format.data = DataSource::Buffer(provider);
+        message_format
+    }
+
+    fn with_locale(pattern: String, locale: Cow<'l, Locale>) -> Self {
         Self {
-            pattern: Some(pattern.into()),
-            initial_literals: Default::default(),
-            parsed_pattern: Default::default(),
+            pattern,
+            parsed: OnceCell::new(),
             locale,
+            data: DataSource::Compiled,
+            context: OnceCell::new(),
+            numbering_system: None,
+            none: Default::default(),
+            casing: Default::default(),
+            max_len: None,
+            compatibility: Default::default(),
+            strict: false,
+            unknown_argument_handler: None,
+            branch_observer: None,
+            stray_pound_handler: None,
+            tag_handler: None,
+            argument_formatters: HashMap::new(),
+            select_aliases: HashMap::new(),
+            joint_select: false,
+        }
+    }
+
+    /// Escapes `text` for use in a pattern, so that it is formatted verbatim,
+    /// e.g. `"{x}"` becomes `"'{x}'"`.
+    pub fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        // quoted literals end at line breaks
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                escaped.push('\n');
+            }
+            let line = line.replace('\'', "''");
+            match line.find(['{', '}', '#']) {
+                Some(pos) => {
+                    escaped.push_str(&line[..pos]);
+                    escaped.push('\'');
+                    escaped.push_str(&line[pos..]);
+                    escaped.push('\'');
+                }
+                None => escaped.push_str(&line),
+            }
+        }
+        escaped
+    }
+
+    /// Parses the pattern immediately, rejecting anything outside the core ICU
+    /// grammar, so that the pattern stays portable to other MessageFormat
+    /// implementations: unknown argument types, plural keys other than plural
+    /// categories and `=N`, and numeric select keys.
+    ///
+    /// Malformed patterns are rejected as well, see
+    /// [`MessageFormat::try_new`].
+    pub fn parse_strict(
+        pattern: impl Into<String>,
+        locale: &'l Locale,
+    ) -> Result<Self, ParseError> {
+        let mut message_format = Self::new(pattern, locale);
+        message_format.strict = true;
+        message_format.try_parse()
+    }
+
+    /// Parses the pattern immediately and returns an error if it is malformed,
+    /// e.g. with mismatched braces or without an `other` branch, instead of
+    /// panicking on first use, e.g. for patterns provided by translators.
+    pub fn try_new(pattern: impl Into<String>, locale: &'l Locale) -> Result<Self, ParseError> {
+        Self::new(pattern, locale).try_parse()
+    }
+
+    /// Parses a message in the syntax of MessageFormat 2, e.g.
+    /// `.input {$n :number} .match $n one {{{$n} file}} * {{{$n} files}}`,
+    /// by converting it to a pattern, which is returned by
+    /// [`MessageFormat::source`].
+    ///
+    /// Selectors annotated with `:number` or `:integer` become plural blocks,
+    /// or ordinal blocks with `select=ordinal`, and the others select blocks;
+    /// several selectors become nested blocks. Placeholders support the
+    /// functions `:string`, `:number` and `:integer` with the fraction digits,
+    /// grouping and notation options, and `:date`, `:time` and `:datetime`
+    /// with styles. Hyphens and dots in variable names become underscores.
+    /// Markup and other functions are rejected.
+    pub fn try_new_mf2(source: &str, locale: &'l Locale) -> Result<Self, ParseError> {
+        Self::try_new(mf2::to_pattern(source)?, locale)
+    }
+
+    /// Parses the pattern as [`MessageFormat::try_new`], after options which
+    /// change parsing, e.g. [`MessageFormat::with_compatibility`], are set.
+    pub fn try_parse(self) -> Result<Self, ParseError> {
+        let (parsed, error) = self.parse(true);
+        if let Some(err) = error {
+            return Err(err);
+        }
+        // the message may have been parsed already, with the same result
+        let _ = self.parsed.set(parsed);
+        Ok(self)
+    }
+
+    /// Returns the parsed pattern as JSON, so that messages can be parsed in a
+    /// build step and loaded with [`MessageFormat::from_precompiled`] without
+    /// parsing them at startup, e.g. for `{N, plural, one {# file} other {# files}}`:
+    ///
+    /// ```json
+    /// {"version": 1, "pattern": "{N, plural, …}", "blocks": [{"type": "plural",
+    /// "name": "N", "offset": "0", "branches": [{"key": "one", "blocks": [{"type":
+    /// "pound"}, {"type": "text", "value": " file"}]}, …]}]}
+    /// ```
+    ///
+    /// Options like the casing are not stored, and tags of
+    /// [`MessageFormat::with_tag_handler`] are stored as text.
+    #[cfg(feature = "precompiled")]
+    pub fn to_precompiled(&self) -> String {
+        precompiled::to_json(self).to_string()
+    }
+
+    /// Loads a message written by [`MessageFormat::to_precompiled`], or
+    /// returns why the JSON is invalid. Custom argument types are formatted
+    /// once they are registered again with [`MessageFormat::with_argument_type`].
+    #[cfg(feature = "precompiled")]
+    pub fn from_precompiled(json: &str, locale: &'l Locale) -> Result<Self, String> {
+        let value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        precompiled::from_json(&value, Cow::Borrowed(locale))
+    }
+
+    /// Returns the pattern the message was created from, e.g. to log which
+    /// pattern produced a formatted message. It is also the `Display` output.
+    pub fn source(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Overrides the numbering system of the locale for this message, e.g.
+    /// `value!("latn")` to always use Latin digits.
+    pub fn with_numbering_system(mut self, numbering_system: Value) -> Self {
+        self.numbering_system = Some(numbering_system);
+        self
+    }
+
+    /// Sets how `None` parameters are rendered; they are rendered as empty
+    /// text by default.
+    pub fn with_none(mut self, none: NoneParam) -> Self {
+        self.none = none;
+        self
+    }
+
+    /// Sets the case adjustments applied to the formatted message.
+    pub fn with_casing(mut self, casing: Casing) -> Self {
+        self.casing = casing;
+        self
+    }
+
+    /// Truncates formatted messages longer than `max_len` user-perceived
+    /// characters (grapheme clusters), ending them with an ellipsis.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Routes blocks of unknown type, e.g. `{D, duration}` or `{-}`, to `handler`
+    /// instead of failing. It receives the raw text of the block and returns
+    /// the text replacing it.
+    pub fn with_unknown_argument_handler(
+        mut self,
+        handler: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.unknown_argument_handler = Some(Callback(Box::new(handler)));
+        self
+    }
+
+    /// Calls `observer` with the argument name and the key (e.g. `female`,
+    /// `one` or `=0`) of every select, plural and ordinal branch taken while
+    /// formatting, e.g. to report untested branches of translations.
+    pub fn with_branch_observer(
+        mut self,
+        observer: impl Fn(&str, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.branch_observer = Some(Callback(Box::new(observer)));
+        self
+    }
+
+    /// Calls `handler` with the formatted message if it contains `#` outside
+    /// of plural and ordinal blocks, e.g. to log a translator's stray pound
+    /// sign. The `#` is kept as text; [`MessageFormat::parse_strict`] rejects
+    /// such patterns.
+    pub fn with_stray_pound_handler(
+        mut self,
+        handler: impl Fn(&str) + Send + Sync + 'static,
+    ) -> Self {
+        self.stray_pound_handler = Some(Callback(Box::new(handler)));
+        self
+    }
+
+    /// Parses tags like `<b>…</b>` and `<br/>` in the text of the pattern and
+    /// renders them with `handler`, which receives the tag name and the
+    /// formatted children, e.g. to wrap them in styled elements:
+    ///
+    /// ```
+    /// use icu::locid::locale;
+    /// use message_format::MessageFormat;
+    ///
+    /// let en = locale!("en");
+    /// let fmt = MessageFormat::new("<b>{N, plural, one {# file} other {# files}}</b>", &en)
+    ///     .with_tag_handler(|tag, children| format!("[{tag}]{children}[/{tag}]"));
+    /// assert_eq!(fmt.format_with_params([("N", 3.into())]), "[b]3 files[/b]");
+    /// ```
+    ///
+    /// Tags are closed in the branch they are opened in. Without a handler,
+    /// tags are text. [`MessageFormat::format_to_parts`] returns the output of
+    /// the handler as a literal.
+    pub fn with_tag_handler(
+        mut self,
+        handler: impl Fn(&str, &str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.tag_handler = Some(Callback(Box::new(handler)));
+        self
+    }
+
+    /// Formats arguments of the custom type `typ`, e.g. `filesize` in
+    /// `{SIZE, filesize, short}`, with `formatter`, which receives the value,
+    /// the style (empty if there is none) and the locale:
+    ///
+    /// ```
+    /// use icu::locid::locale;
+    /// use message_format::MessageFormat;
+    ///
+    /// let en = locale!("en");
+    /// let fmt = MessageFormat::new("Size: {SIZE, filesize, short}", &en)
+    ///     .with_argument_type("filesize", |value, style, _locale| {
+    ///         let kb = value.to_string().parse::<f64>().unwrap_or_default() / 1000.0;
+    ///         match style {
+    ///             "short" => format!("{kb} kB"),
+    ///             _ => format!("{kb} kilobytes"),
+    ///         }
+    ///     });
+    /// assert_eq!(fmt.format_with_params([("SIZE", 1500.into())]), "Size: 1.5 kB");
+    /// ```
+    pub fn with_argument_type(
+        mut self,
+        typ: impl Into<String>,
+        formatter: impl Fn(&ParamValue, &str, &Locale) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.argument_formatters
+            .insert(typ.into(), Callback(Box::new(formatter)));
+        self
+    }
+
+    /// Selects the branch `to` of select blocks without a branch `from`, e.g.
+    /// `("nonbinary", "other")` or `("f", "female")`, so that the values of
+    /// parameters can change without editing every pattern. Aliases are
+    /// followed in chains, e.g. `f → female → other`.
+    pub fn with_select_alias(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.select_aliases.insert(from.into(), to.into().into());
+        self
+    }
+
+    /// Enables select blocks on several arguments at once, with one key part
+    /// per argument, instead of nesting plural blocks in select blocks:
+    ///
+    /// ```text
+    /// {GENDER|N, select,
+    ///     female|one {She has # file}
+    ///     female|other {She has # files}
+    ///     other|one {They have # file}
+    ///     other {They have # files}}
+    /// ```
+    ///
+    /// `other` matches any value, and the branch with the fewest `other`
+    /// parts is selected. Arguments with a plural category or an exact match
+    /// like `=0` in a key select by the plural rules and replace `#`.
+    pub fn with_joint_select(mut self) -> Self {
+        self.joint_select = true;
+        self
+    }
+
+    /// Reproduces the behavior of another MessageFormat implementation, e.g.
+    /// to compare outputs byte-for-byte while migrating from it.
+    pub fn with_compatibility(mut self, compatibility: Compatibility) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+
+    /// Whether a plural or ordinal branch contains `#`, i.e. whether
+    /// [`MessageFormat::format_ignoring_pound`] formats differently.
+    pub fn uses_pound(&self) -> bool {
+        uses_pound(self.parsed())
+    }
+
+    /// Whether the message contains a plural or ordinal block, i.e. requires
+    /// a numeric parameter.
+    pub fn has_plural(&self) -> bool {
+        has_plural(self.parsed())
+    }
+
+    /// Whether the message has no arguments, so it can be formatted once and
+    /// cached as a constant.
+    pub fn is_static(&self) -> bool {
+        self.parsed()
+            .iter()
+            .all(|block| matches!(block, Block::String(_) | Block::Literal(_)))
+    }
+
+    /// Returns the text of a message without arguments and quoting, which is
+    /// formatted as is, e.g. `Cancel`.
+    pub fn as_static_text(&self) -> Option<&str> {
+        self.static_text()
+    }
+
+    /// Renders the parsed message as an indented tree of blocks, branch keys
+    /// and literal text, e.g. to see which branch a parameter selects:
+    ///
+    /// ```text
+    /// plural N offset:1
+    ///   =0
+    ///     text "nobody"
+    ///   other
+    ///     text "# others"
+    /// ```
+    pub fn debug_tree(&self) -> String {
+        let mut tree = String::new();
+        tree::write_tree(&mut tree, self.parsed(), 0);
+        tree
+    }
+
+    /// Returns a fingerprint of what the message renders, e.g. as part of
+    /// the keys of cached renderings. It is stable across processes and
+    /// releases, ignores how the pattern is written (whitespace in blocks,
+    /// quoting), and changes with the locale, the options of the message
+    /// and the version of the locale data.
+    ///
+    /// Handlers changing the output, custom argument types and data providers
+    /// are covered by whether they are set, not by what they return, so a cache key must
+    /// also change when their code or data does, e.g. with the version of the
+    /// application.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv64::default();
+        hasher.field(fingerprint::DATA_VERSION);
+        hasher.field(&self.locale.to_string());
+        hasher.field(&self.debug_tree());
+        let options = format!(
+            "{:?} {:?} {:?} {:?} {:?}",
+            self.numbering_system, self.none, self.casing, self.max_len, self.compatibility
+        );
+        hasher.field(&options);
+        let mut aliases: Vec<_> = self.select_aliases.iter().collect();
+        aliases.sort();
+        for (from, to) in aliases {
+            hasher.field(from);
+            hasher.field(&to.to_string());
+        }
+        // only options which are set, so that the fingerprints of messages
+        // without them are unchanged
+        if self.joint_select {
+            hasher.field("joint-select");
+        }
+        if !matches!(self.data, DataSource::Compiled) {
+            hasher.field(&format!("data {:?}", self.data));
+        }
+        if self.unknown_argument_handler.is_some() {
+            hasher.field("unknown-argument-handler");
+        }
+        if self.tag_handler.is_some() {
+            hasher.field("tag-handler");
+        }
+        let mut argument_types: Vec<_> = self.argument_formatters.keys().collect();
+        argument_types.sort();
+        for typ in argument_types {
+            hasher.field(&format!("argument-type {typ}"));
+        }
+        hasher.finish()
+    }
+
+    /// Formats the message as [`MessageFormat::format_with_params`], but
+    /// borrows the text of static messages, see
+    /// [`MessageFormat::as_static_text`].
+    pub fn format_cow(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Cow<'_, str> {
+        if let Some(text) = self.static_text() {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(self.format_with_params(named_parameters))
+        }
+    }
+
+    fn static_text(&self) -> Option<&str> {
+        if self.casing != Casing::Unchanged || self.max_len.is_some() {
+            return None;
         }
+        match self.parsed().as_slice() {
+            [] => Some(""),
+            // `#` outside of plural blocks is reported when formatting
+            [Block::String(text)] if !text.contains('#') => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of all arguments in the order of their first use.
+    pub fn arguments(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        arguments(self.parsed(), &mut names);
+        names
+    }
+
+    /// Returns the names of all arguments in the order of their first use,
+    /// with the type inferred from all blocks using them. Numbers and dates
+    /// win over select keys, which win over simple arguments; the keys of several
+    /// select blocks are merged.
+    pub fn argument_types(&self) -> Vec<(String, ArgumentType)> {
+        let mut types = Vec::new();
+        argument_types(self.parsed(), &mut types);
+        types
     }
 
-    pub fn format(&mut self) -> String {
-        self.format_impl(false, None)
+    pub fn format(&self) -> String {
+        self.format_impl(false, None, None)
     }
 
     pub fn format_with_params(
-        &mut self,
+        &self,
         named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
     ) -> String {
         self.format_impl(
             false,
-            Some(
+            Some(Cow::Owned(
                 named_parameters
                     .into_iter()
                     .map(|(k, v)| (k.into(), v))
                     .collect(),
-            ),
+            )),
+            None,
+        )
+    }
+
+    /// Formats a message with numeric argument names, e.g. `{0} of {1}`, with
+    /// the parameters in the order of their numbers.
+    pub fn format_with_positional_params(
+        &self,
+        params: impl IntoIterator<Item = ParamValue>,
+    ) -> String {
+        self.format_with_params(
+            params
+                .into_iter()
+                .enumerate()
+                .map(|(idx, value)| (idx.to_string(), value)),
+        )
+    }
+
+    /// Returns the select, plural and ordinal branches the parameters select,
+    /// in the order they would be formatted, without formatting the message,
+    /// e.g. `[("GENDER", "female"), ("N", "one")]`. Keys are reported as to
+    /// [`MessageFormat::with_branch_observer`], and blocks whose parameter is
+    /// missing or invalid are skipped.
+    pub fn select_branches(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> BranchPath {
+        let named_parameters = self.replace_none(Cow::Owned(
+            named_parameters
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect(),
+        ));
+        let mut path = Vec::new();
+        Formatter::new(self.context(), self, self.parsed(), false).select_branches(
+            self.parsed(),
+            &named_parameters,
+            &mut path,
+        );
+        path
+    }
+
+    /// Formats the message as [`MessageFormat::format_with_params`], but with
+    /// the parameters of a map, which is neither copied nor consumed.
+    pub fn format_with_map(&self, named_parameters: &HashMap<String, ParamValue>) -> String {
+        self.format_impl(false, Some(Cow::Borrowed(named_parameters)), None)
+    }
+
+    /// Formats the message as [`MessageFormat::format_with_params`], and
+    /// returns the parameters of its arguments along with the text, e.g. as
+    /// fields of a `tracing` event.
+    pub fn format_structured(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> StructuredMessage {
+        let mut params: HashMap<String, ParamValue> = named_parameters
+            .into_iter()
+            .map(|(k, v)| (k.into(), v))
+            .collect();
+        let text = self.format_with_map(&params);
+        let fields = self
+            .arguments()
+            .into_iter()
+            .filter_map(|name| {
+                let value = params.remove(&name)?;
+                Some((name, value))
+            })
+            .collect();
+        StructuredMessage { text, fields }
+    }
+
+    /// Formats the message with the locale and the cached formatters of
+    /// `context` instead of setting them up for this call.
+    pub fn format_with_context(
+        &self,
+        context: &RenderContext,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> String {
+        self.format_impl(
+            false,
+            Some(Cow::Owned(
+                named_parameters
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v))
+                    .collect(),
+            )),
+            Some(context),
         )
     }
 
     pub fn format_ignoring_pound(
-        &mut self,
+        &self,
         named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
     ) -> String {
         self.format_impl(
             true,
-            Some(
+            Some(Cow::Owned(
                 named_parameters
                     .into_iter()
                     .map(|(k, v)| (k.into(), v))
                     .collect(),
-            ),
+            )),
+            None,
+        )
+    }
+
+    /// Formats the message into literal text and argument values, e.g. to
+    /// wrap the arguments in styled elements. The length limit of
+    /// [`MessageFormat::with_max_len`] is not applied.
+    pub fn format_to_parts(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Vec<MessagePart> {
+        self.format_blocks_to_parts(
+            self.parsed(),
+            false,
+            Some(Cow::Owned(
+                named_parameters
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v))
+                    .collect(),
+            )),
+            None,
         )
     }
 
+    /// Renders the message with `renderer`, see [`MessageRenderer`].
+    pub fn render<R: MessageRenderer>(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+        renderer: R,
+    ) -> R::Output {
+        renderer.render(&self.format_to_parts(named_parameters))
+    }
+
+    /// Formats only the branch `key` (e.g. `one` or `=0`) of the select,
+    /// plural or ordinal block of `argument`, e.g. for segment previews.
+    ///
+    /// In a plural or ordinal branch, `#` is replaced by the number of
+    /// `argument` if it is passed, and kept otherwise. Returns `None` if there
+    /// is no such branch.
+    pub fn format_branch(
+        &self,
+        argument: &str,
+        key: &str,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Option<String> {
+        let named_parameters: HashMap<String, ParamValue> = named_parameters
+            .into_iter()
+            .map(|(k, v)| (k.into(), v))
+            .collect();
+        let key = key.trim().trim_start_matches('=');
+        let key = ParamValue::parse_number(key).unwrap_or_else(|| key.to_owned().into());
+        let (block, branch) = find_branch(self.parsed(), argument, &key)?;
+
+        let (blocks, ignore_pound) = match block {
+            Block::Plural(branches, _) | Block::Ordinal(branches, _)
+                if named_parameters.contains_key(argument) =>
+            {
+                // a plural block with the branch as its only option, so that `#`
+                // is replaced as usual
+                let mut plural = IndexMap::new();
+                plural.insert(ARGUMENT_NAME, branches[&ARGUMENT_NAME].clone());
+                plural.insert(ARGUMENT_OFFSET, branches[&ARGUMENT_OFFSET].clone());
+                plural.insert(OTHER, branch.clone());
+                (vec![Block::plural(plural)], false)
+            }
+            Block::Plural(..) | Block::Ordinal(..) => (branch.clone(), true),
+            _ => (branch.clone(), false),
+        };
+        Some(self.format_blocks(
+            &blocks,
+            ignore_pound,
+            Some(Cow::Owned(named_parameters)),
+            None,
+        ))
+    }
+
+    /// Formats every combination of select, plural and ordinal branches, e.g.
+    /// for exhaustive snapshot tests of a message.
+    ///
+    /// Plural branches are formatted with their exact value or a sample number
+    /// of their category, see [`plural_samples`]. The other arguments are
+    /// taken from `named_parameters`.
+    pub fn format_all_branches(
+        &self,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Vec<(BranchPath, String)> {
+        let named_parameters: HashMap<String, ParamValue> = named_parameters
+            .into_iter()
+            .map(|(k, v)| (k.into(), v))
+            .collect();
+        combinations(self.parsed(), &self.locale)
+            .into_iter()
+            .map(|combination| {
+                let mut params = named_parameters.clone();
+                params.extend(combination.params);
+                let message =
+                    self.format_blocks(&combination.blocks, false, Some(Cow::Owned(params)), None);
+                (combination.path, message)
+            })
+            .collect()
+    }
+
     fn format_impl(
-        &mut self,
+        &self,
         ignore_pound: bool,
-        named_parameters: Option<HashMap<String, ParamValue>>,
+        named_parameters: Option<Cow<HashMap<String, ParamValue>>>,
+        context: Option<&RenderContext>,
     ) -> String {
-        self.init();
-
-        Formatter::new(
-            self.locale,
-            &self.initial_literals,
-            &self.parsed_pattern,
-            ignore_pound,
-        )
-        .format(named_parameters)
+        self.format_blocks(self.parsed(), ignore_pound, named_parameters, context)
     }
 
-    fn init(&mut self) {
-        if let Some(pattern) = self.pattern.take() {
-            self.initial_literals = Default::default();
-            let pattern = self.insert_placeholders(pattern);
+    fn format_blocks(
+        &self,
+        blocks: &Vec<Block>,
+        ignore_pound: bool,
+        named_parameters: Option<Cow<HashMap<String, ParamValue>>>,
+        context: Option<&RenderContext>,
+    ) -> String {
+        let message: String = self
+            .format_blocks_to_parts(blocks, ignore_pound, named_parameters, context)
+            .iter()
+            .map(MessagePart::text)
+            .collect();
 
-            self.parsed_pattern = self.parse_block(pattern);
+        match self.max_len {
+            Some(max_len) => truncate(message, max_len),
+            None => message,
         }
     }
 
-    fn insert_placeholders(&mut self, pattern: String) -> String {
-        static DOUBLE_APOSTROPHE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"''").unwrap());
-        static LITERAL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"'([{}#].*?)'").unwrap());
+    fn format_blocks_to_parts(
+        &self,
+        blocks: &Vec<Block>,
+        ignore_pound: bool,
+        named_parameters: Option<Cow<HashMap<String, ParamValue>>>,
+        context: Option<&RenderContext>,
+    ) -> Vec<MessagePart> {
+        let named_parameters = named_parameters.map(|params| self.replace_none(params));
+
+        let owned_context;
+        let context = match (context, &self.numbering_system) {
+            (Some(context), None) => context,
+            (None, _) => self.context(),
+            (Some(context), Some(numbering_system)) => {
+                let locale = with_numbering_system(context.locale(), numbering_system);
+                owned_context = RenderContext::with_data(locale, context.data().clone());
+                &owned_context
+            }
+        };
 
-        let pattern = DOUBLE_APOSTROPHE_RE.replace_all(&pattern, |_caps: &Captures| {
-            Self::build_placeholder(&mut self.initial_literals, "'")
-        });
-        let pattern = LITERAL_RE.replace_all(&pattern, |caps: &Captures| {
-            Self::build_placeholder(&mut self.initial_literals, &caps[1])
-        });
+        Formatter::new(context, self, blocks, ignore_pound).format(named_parameters.as_deref())
+    }
+
+    fn context(&self) -> &RenderContext {
+        self.context.get_or_init(|| {
+            let locale = match &self.numbering_system {
+                Some(numbering_system) => with_numbering_system(&self.locale, numbering_system),
+                None => self.locale.clone().into_owned(),
+            };
+            RenderContext::with_data(locale, self.data.clone())
+        })
+    }
 
-        pattern.into_owned()
+    /// Applies [`MessageFormat::with_none`] to the parameters.
+    fn replace_none<'p>(
+        &self,
+        mut named_parameters: Cow<'p, HashMap<String, ParamValue>>,
+    ) -> Cow<'p, HashMap<String, ParamValue>> {
+        if !named_parameters.values().any(ParamValue::is_none) {
+            return named_parameters;
+        }
+        let named_parameters_mut = named_parameters.to_mut();
+        match &self.none {
+            NoneParam::Text(text) => {
+                for value in named_parameters_mut.values_mut().filter(|v| v.is_none()) {
+                    *value = text.clone().into_owned().into();
+                }
+            }
+            NoneParam::Undefined => named_parameters_mut.retain(|_, v| !v.is_none()),
+        }
+        named_parameters
     }
 
-    fn build_placeholder(literals: &mut Vec<String>, text: &str) -> String {
-        let idx = literals.len();
-        literals.push(text.to_owned());
-        placeholder(idx)
+    fn parsed(&self) -> &Vec<Block> {
+        self.parsed.get_or_init(|| self.parse(false).0)
+    }
+
+    /// Parses the pattern, and returns the first error of strict or fallible
+    /// parsing.
+    fn parse(&self, fallible: bool) -> (Vec<Block>, Option<ParseError>) {
+        let pattern = self.pattern.clone();
+        // parsing depends on the handlers, and strict parsing records errors
+        let cached = !self.strict
+            && !self.joint_select
+            && self.unknown_argument_handler.is_none()
+            && self.tag_handler.is_none()
+            && self.argument_formatters.is_empty()
+            && cache::is_enabled();
+        if cached {
+            if let Some(blocks) = cache::get(&pattern, self.compatibility) {
+                return (blocks, None);
+            }
+        }
+        let original_pattern = cached.then(|| pattern.clone());
+
+        let mut parser = Parser {
+            parse_error: None,
+            strict: self.strict,
+            fallible,
+            compatibility: self.compatibility,
+            joint_select: self.joint_select,
+            tags: self.tag_handler.is_some(),
+            unknown_argument_handler: self.unknown_argument_handler.as_ref(),
+            argument_formatters: &self.argument_formatters,
+        };
+        let blocks = parser.parse_block(pattern);
+        if has_stray_pound(&blocks) {
+            parser.reject_strict(ParseError::StrayPound);
+        }
+
+        if let Some(pattern) = original_pattern.filter(|_| parser.parse_error.is_none()) {
+            cache::insert(pattern, self.compatibility, blocks.clone());
+        }
+        (blocks, parser.parse_error)
     }
+}
+
+/// State of parsing a pattern with the options of a [`MessageFormat`]
+struct Parser<'m> {
+    /// First error of strict or fallible parsing
+    parse_error: Option<ParseError>,
+    strict: bool,
+    /// Records malformed patterns instead of panicking, see
+    /// [`MessageFormat::try_parse`]
+    fallible: bool,
+    compatibility: Compatibility,
+    joint_select: bool,
+    /// See [`MessageFormat::with_tag_handler`]
+    tags: bool,
+    unknown_argument_handler: Option<&'m Callback<UnknownArgumentHandler>>,
+    /// See [`MessageFormat::with_argument_type`]
+    argument_formatters: &'m HashMap<String, Callback<ArgumentFormatter>>,
+}
 
+impl Parser<'_> {
     fn parse_block(&mut self, pattern: String) -> Vec<Block> {
         let mut result = Vec::new();
         let parts = self.extract_parts(&pattern);
         for part in parts {
             let block = match part.typ {
-                ElementType::String => Block::String(part.value),
+                ElementType::String => {
+                    push_text(&mut result, &part.value);
+                    continue;
+                }
                 ElementType::Block => {
                     let block_type = self.parse_block_type(&part.value);
                     match block_type {
                         BlockType::Select => Block::Select(self.parse_select_block(&part.value)),
-                        BlockType::Plural => Block::Plural(self.parse_plural_block(&part.value)),
-                        BlockType::Ordinal => Block::Ordinal(self.parse_ordinal_block(&part.value)),
-                        BlockType::Simple => Block::Simple(part.value),
+                        BlockType::JointSelect => self.parse_joint_select_block(&part.value),
+                        BlockType::Plural => Block::plural(self.parse_plural_block(&part.value)),
+                        BlockType::Ordinal => Block::ordinal(self.parse_ordinal_block(&part.value)),
+                        BlockType::Number => {
+                            let (name, options) = self.parse_number_block(&part.value);
+                            Block::Number(name, options)
+                        }
+                        BlockType::DateTime => {
+                            let (name, style) = self.parse_date_time_block(&part.value);
+                            Block::DateTime(name, style)
+                        }
+                        BlockType::Simple if self.is_custom_block(&part.value) => {
+                            self.parse_custom_block(&part.value)
+                        }
+                        BlockType::Simple
+                            if self.unknown_argument_handler.is_some()
+                                && !ARGUMENT_NAME_RE.is_match(&part.value) =>
+                        {
+                            self.handle_unknown_block(&part.value)
+                        }
+                        BlockType::Simple => {
+                            self.check_simple_block(&part.value);
+                            Block::Simple(part.value)
+                        }
+                        BlockType::Empty => {
+                            // stray `{}` are common in translations, keep them as text
+                            self.reject_strict(ParseError::EmptyBlock);
+                            Block::Literal(format!("{{{}}}", part.value))
+                        }
+                        BlockType::Unknown if self.unknown_argument_handler.is_some() => {
+                            self.handle_unknown_block(&part.value)
+                        }
                         BlockType::Unknown => {
-                            panic!("unknown block type for pattern {}", part.value);
+                            self.fail(ParseError::UnknownBlock(part.value));
+                            Block::String(String::new())
                         }
                     }
                 }
             };
             result.push(block);
         }
+        if self.tags {
+            result = self.parse_tags(result);
+        }
         result
     }
 
-    fn extract_parts(&mut self, pattern: &str) -> Vec<ElementTypeAndVal> {
-        static BRACES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[{}]").unwrap());
+    /// Splits text at tags, see [`MessageFormat::with_tag_handler`]
+    fn parse_tags(&mut self, blocks: Vec<Block>) -> Vec<Block> {
+        static TAG_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"<(/?)([A-Za-z][\w-]*)\s*(/?)>").unwrap());
+
+        let mut result = Vec::new();
+        let mut open_tags = Vec::new();
+        for block in blocks {
+            let Block::String(text) = block else {
+                result.push(block);
+                continue;
+            };
+            let mut end = 0;
+            for caps in TAG_RE.captures_iter(&text) {
+                let tag = caps.get(0).unwrap();
+                if tag.start() > end {
+                    result.push(Block::String(text[end..tag.start()].to_owned()));
+                }
+                end = tag.end();
+
+                let name = caps[2].to_owned();
+                if !caps[1].is_empty() {
+                    if open_tags.last() == Some(&name) {
+                        open_tags.pop();
+                        result.push(Block::TagClose(name));
+                    } else {
+                        self.fail(ParseError::UnmatchedClosingTag(name));
+                    }
+                } else if !caps[3].is_empty() {
+                    result.push(Block::TagOpen(name.clone()));
+                    result.push(Block::TagClose(name));
+                } else {
+                    open_tags.push(name.clone());
+                    result.push(Block::TagOpen(name));
+                }
+            }
+            if end < text.len() {
+                result.push(Block::String(text[end..].to_owned()));
+            }
+        }
+        if let Some(name) = open_tags.pop() {
+            self.fail(ParseError::UnclosedTag(name));
+        }
+        result
+    }
 
+    /// Splits the pattern into text and blocks, skipping the braces of quoted
+    /// literals, which are kept in the text.
+    fn extract_parts(&mut self, pattern: &str) -> Vec<ElementTypeAndVal> {
         let mut prev_pos = 0;
         let mut brace_stack: Vec<char> = Vec::new();
         let mut results: Vec<ElementTypeAndVal> = Vec::new();
 
-        for m in BRACES_RE.find_iter(pattern) {
-            let pos = m.start();
-            if m.as_str() == "}" {
-                if let Some(brace) = brace_stack.pop() {
-                    assert_eq!(brace, '{', "No matching }} for {{");
-                } else {
-                    panic!("No matching {{ for }}");
+        let quotes = find_quotes(pattern);
+        for pos in memchr2_iter(b'{', b'}', pattern.as_bytes()) {
+            if quotes.is_quoted(pos) {
+                continue;
+            }
+            if pattern.as_bytes()[pos] == b'}' {
+                if brace_stack.pop().is_none() {
+                    // kept as text
+                    self.fail(ParseError::UnmatchedClosingBrace);
+                    continue;
                 }
                 if brace_stack.is_empty() {
                     // end of block
@@ -175,10 +1154,9 @@ impl<'l> MessageFormat<'l> {
             }
         }
 
-        assert!(
-            brace_stack.is_empty(),
-            "There are mismatched {{ or }} in the pattern"
-        );
+        if !brace_stack.is_empty() {
+            self.fail(ParseError::UnclosedBrace);
+        }
 
         let substr = &pattern[prev_pos..];
         if !substr.is_empty() {
@@ -191,12 +1169,20 @@ impl<'l> MessageFormat<'l> {
     fn parse_block_type(&self, value: &str) -> BlockType {
         static SIMPLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*\w").unwrap());
 
-        if PLURAL_BLOCK_RE.is_match(value) {
+        if value.trim().is_empty() {
+            BlockType::Empty
+        } else if PLURAL_BLOCK_RE.is_match(value) {
             BlockType::Plural
         } else if ORDINAL_BLOCK_RE.is_match(value) {
             BlockType::Ordinal
         } else if SELECT_BLOCK_RE.is_match(value) {
             BlockType::Select
+        } else if self.joint_select && JOINT_SELECT_BLOCK_RE.is_match(value) {
+            BlockType::JointSelect
+        } else if NUMBER_BLOCK_RE.is_match(value) && self.compatibility != Compatibility::Dart {
+            BlockType::Number
+        } else if DATE_TIME_BLOCK_RE.is_match(value) && self.compatibility != Compatibility::Dart {
+            BlockType::DateTime
         } else if SIMPLE_RE.is_match(value) {
             BlockType::Simple
         } else {
@@ -204,7 +1190,34 @@ impl<'l> MessageFormat<'l> {
         }
     }
 
-    fn parse_select_block(&mut self, pattern: &str) -> HashMap<ParamValue, Vec<Block>> {
+    fn parse_number_block(&mut self, pattern: &str) -> (String, NumberOptions) {
+        let caps = NUMBER_BLOCK_RE.captures(pattern).expect("logic error");
+        let style = caps.get(2).map_or("", |m| m.as_str().trim());
+        let options = NumberOptions::parse_style(style).unwrap_or_else(|| {
+            // otherwise formatted as a plain number, like ICU does with
+            // styles it cannot apply
+            if self.strict || self.fallible {
+                self.fail(ParseError::UnknownNumberStyle(style.to_owned()));
+            }
+            NumberOptions::default()
+        });
+        (caps[1].to_owned(), options)
+    }
+
+    fn parse_date_time_block(&mut self, pattern: &str) -> (String, DateTimeStyle) {
+        let caps = DATE_TIME_BLOCK_RE.captures(pattern).expect("logic error");
+        let style = caps.get(3).map_or("", |m| m.as_str().trim());
+        let style = DateTimeStyle::parse(&caps[2], style).unwrap_or_else(|| {
+            // otherwise formatted in the medium style
+            if self.strict || self.fallible {
+                self.fail(ParseError::UnknownDateTimeStyle(style.to_owned()));
+            }
+            DateTimeStyle::parse(&caps[2], "").expect("logic error")
+        });
+        (caps[1].to_owned(), style)
+    }
+
+    fn parse_select_block(&mut self, pattern: &str) -> IndexMap<ParamValue, Vec<Block>> {
         let mut argument_name = None;
         let pattern = SELECT_BLOCK_RE.replace(pattern, |caps: &Captures| {
             // string, name
@@ -212,7 +1225,7 @@ impl<'l> MessageFormat<'l> {
             ""
         });
 
-        let mut result = HashMap::new();
+        let mut result = IndexMap::new();
         result.insert(
             ARGUMENT_NAME,
             vec![Block::String(argument_name.expect("logic error"))],
@@ -227,91 +1240,165 @@ impl<'l> MessageFormat<'l> {
             let key = &part.value;
 
             pos += 1;
-            assert!(pos < parts.len(), "missing or invalid select value element");
-            let part = &parts[pos];
-
-            let value = match part.typ {
-                ElementType::Block => self.parse_block(part.value.clone()),
-                ElementType::String => panic!("assert_eqed block type"),
+            let Some(value) = self.parse_branch(key, parts.get(pos)) else {
+                break;
             };
 
             let key = WHITESPACES_RE.replace_all(key, "");
+            if ParamValue::parse_number(&key).is_some() {
+                self.reject_strict(ParseError::NumericSelectKey(key.clone().into_owned()));
+            }
             let key = ParamValue::parse_number(&key).unwrap_or_else(|| key.into_owned().into());
             result.insert(key, value);
 
             pos += 1;
         }
 
-        assert!(
-            result.contains_key(&OTHER),
-            "missing other key in select statement"
-        );
+        if !result.contains_key(&OTHER) {
+            self.fail(ParseError::MissingOther("select".to_owned()));
+        }
 
         result
     }
 
-    fn parse_plural_block(&mut self, pattern: &str) -> HashMap<ParamValue, Vec<Block>> {
-        let mut argument_name = None;
-        let mut argument_offset = 0;
-        let pattern = PLURAL_BLOCK_RE.replace(pattern, |caps: &Captures| {
-            argument_name = Some(caps[1].to_owned());
-            if let Some(offset) = caps.get(2) {
-                argument_offset = offset.as_str().parse().unwrap();
-            }
-            ""
+    fn check_plural_key(&mut self, key: &str) {
+        static ICU_PLURAL_KEY_RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"^\s*(?:=\d+(?:\.\d+)?|zero|one|two|few|many|other)\s*$").unwrap()
         });
 
-        let mut result = HashMap::new();
-        result.insert(ARGUMENT_NAME, vec![Block::String(argument_name.unwrap())]);
-        result.insert(
-            ARGUMENT_OFFSET,
-            vec![Block::String(argument_offset.to_string())],
-        );
+        if !ICU_PLURAL_KEY_RE.is_match(key) {
+            let err = ParseError::InvalidPluralKey(key.trim().to_owned());
+            if self.compatibility == Compatibility::Icu {
+                self.fail(err);
+            } else {
+                self.reject_strict(err);
+            }
+        }
+    }
 
-        let parts = self.extract_parts(&pattern);
+    /// Whether the block has a type of [`MessageFormat::with_argument_type`]
+    fn is_custom_block(&self, value: &str) -> bool {
+        let mut parts = value.splitn(3, ',');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(typ)) => {
+                ARGUMENT_NAME_RE.is_match(name) && self.argument_formatters.contains_key(typ.trim())
+            }
+            _ => false,
+        }
+    }
 
-        // looking for (key block)+ sequence
-        let mut pos = 0;
-        while pos < parts.len() {
-            let part = &parts[pos];
-            let key = &part.value;
+    fn parse_custom_block(&self, value: &str) -> Block {
+        let mut parts = value.splitn(3, ',').map(str::trim);
+        let name = parts.next().unwrap_or_default().to_owned();
+        let typ = parts.next().unwrap_or_default().to_owned();
+        let style = parts.next().unwrap_or_default();
+        let style = find_quotes(style)
+            .unquote(style, 0..style.len())
+            .into_owned();
+        Block::Custom(name, typ, style)
+    }
 
-            pos += 1;
-            assert!(pos < parts.len(), "missing or invalid plural element");
-            let part = &parts[pos];
+    fn handle_unknown_block(&mut self, value: &str) -> Block {
+        let handler = self.unknown_argument_handler.expect("logic error");
+        let text = (handler.0)(value);
+        // the text is inserted verbatim, e.g. `#` is not replaced
+        Block::Literal(text)
+    }
 
-            let value = match part.typ {
-                ElementType::Block => self.parse_block(part.value.clone()),
-                ElementType::String => panic!("assert_eqed block type"),
-            };
+    /// `{N, date}` is parsed as a simple argument named `N, date`
+    fn check_simple_block(&mut self, value: &str) {
+        if !ARGUMENT_NAME_RE.is_match(value) {
+            let typ = value.split(',').nth(1).unwrap_or(value).trim();
+            self.reject_strict(ParseError::UnknownArgumentType(typ.to_owned()));
+        }
+    }
 
-            let key = KV_RE.replace_all(key, |caps: &Captures| caps[1].to_owned());
-            let key = ParamValue::parse_number(&key).unwrap_or_else(|| key.into_owned().into());
-            result.insert(key, value);
+    /// Records the first violation of the strict grammar, see
+    /// [`MessageFormat::parse_strict`].
+    fn reject_strict(&mut self, err: ParseError) {
+        if self.strict && self.parse_error.is_none() {
+            self.parse_error = Some(err);
+        }
+    }
 
-            pos += 1;
+    /// Records a malformed pattern for [`MessageFormat::try_parse`] and
+    /// [`MessageFormat::parse_strict`], and panics otherwise.
+    fn fail(&mut self, err: ParseError) {
+        assert!(self.strict || self.fallible, "{err}");
+        if self.parse_error.is_none() {
+            self.parse_error = Some(err);
         }
+    }
 
-        assert!(
-            result.contains_key(&OTHER),
-            "missing other key in plural statement"
-        );
+    /// Skips an offset which is not matched by the block regex, e.g.
+    /// `offset,1`
+    fn skip_invalid_offset<'p>(&mut self, pattern: &'p str) -> &'p str {
+        static OFFSET_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^\s*(offset\b[\s:,]*\S*)").unwrap());
 
-        result
+        let Some(caps) = OFFSET_RE.captures(pattern) else {
+            return pattern;
+        };
+        self.fail(ParseError::InvalidOffset(caps[1].to_owned()));
+        &pattern[caps.get(0).unwrap().end()..]
+    }
+
+    /// See [`MessageFormat::with_joint_select`]
+    fn parse_joint_select_block(&mut self, pattern: &str) -> Block {
+        let mut arguments = Vec::new();
+        let pattern = JOINT_SELECT_BLOCK_RE.replace(pattern, |caps: &Captures| {
+            arguments = caps[1]
+                .split('|')
+                .map(|name| name.trim().to_owned())
+                .collect();
+            ""
+        });
+
+        let parts = self.extract_parts(&pattern);
+        let mut branches = Vec::new();
+        for pair in parts.chunks(2) {
+            let Some(value) = self.parse_branch(&pair[0].value, pair.get(1)) else {
+                break;
+            };
+            let key = WHITESPACES_RE.replace_all(&pair[0].value, "");
+            branches.push((key.split('|').map(str::to_owned).collect(), value));
+        }
+        joint::nest(&arguments, branches).unwrap_or_else(|err| {
+            self.fail(err);
+            Block::String(String::new())
+        })
+    }
+
+    /// Parses the branch of `key`, which has to be a block.
+    fn parse_branch(&mut self, key: &str, part: Option<&ElementTypeAndVal>) -> Option<Vec<Block>> {
+        match part {
+            Some(part) if matches!(part.typ, ElementType::Block) => {
+                Some(self.parse_block(part.value.clone()))
+            }
+            _ => {
+                self.fail(ParseError::MissingBranch(key.trim().to_owned()));
+                None
+            }
+        }
     }
 
-    fn parse_ordinal_block(&mut self, pattern: &str) -> HashMap<ParamValue, Vec<Block>> {
+    fn parse_plural_block(&mut self, pattern: &str) -> IndexMap<ParamValue, Vec<Block>> {
         let mut argument_name = None;
-        let pattern = ORDINAL_BLOCK_RE.replace(pattern, |caps: &Captures| {
+        let mut argument_offset = "0".to_owned();
+        let pattern = PLURAL_BLOCK_RE.replace(pattern, |caps: &Captures| {
             argument_name = Some(caps[1].to_owned());
+            if let Some(offset) = caps.get(2) {
+                argument_offset = offset.as_str().to_owned();
+            }
             ""
         });
+        let pattern = self.skip_invalid_offset(&pattern);
 
-        let mut result = HashMap::new();
+        let mut result = IndexMap::new();
         result.insert(ARGUMENT_NAME, vec![Block::String(argument_name.unwrap())]);
-        result.insert(ARGUMENT_OFFSET, vec![Block::String("0".to_owned())]);
+        result.insert(ARGUMENT_OFFSET, vec![Block::String(argument_offset)]);
 
-        let parts = self.extract_parts(&pattern);
+        let parts = self.extract_parts(pattern);
 
         // looking for (key block)+ sequence
         let mut pos = 0;
@@ -320,17 +1407,55 @@ impl<'l> MessageFormat<'l> {
             let key = &part.value;
 
             pos += 1;
-            assert!(
-                pos < parts.len(),
-                "missing or invalid ordinal value element"
-            );
+            let Some(value) = self.parse_branch(key, parts.get(pos)) else {
+                break;
+            };
+
+            self.check_plural_key(key);
+            let key = KV_RE.replace_all(key, |caps: &Captures| caps[1].to_owned());
+            let key = ParamValue::parse_number(&key).unwrap_or_else(|| key.into_owned().into());
+            result.insert(key, value);
+
+            pos += 1;
+        }
+
+        if !result.contains_key(&OTHER) {
+            self.fail(ParseError::MissingOther("plural".to_owned()));
+        }
+
+        result
+    }
+
+    fn parse_ordinal_block(&mut self, pattern: &str) -> IndexMap<ParamValue, Vec<Block>> {
+        let mut argument_name = None;
+        let mut argument_offset = "0".to_owned();
+        let pattern = ORDINAL_BLOCK_RE.replace(pattern, |caps: &Captures| {
+            argument_name = Some(caps[1].to_owned());
+            if let Some(offset) = caps.get(2) {
+                argument_offset = offset.as_str().to_owned();
+            }
+            ""
+        });
+        let pattern = self.skip_invalid_offset(&pattern);
+
+        let mut result = IndexMap::new();
+        result.insert(ARGUMENT_NAME, vec![Block::String(argument_name.unwrap())]);
+        result.insert(ARGUMENT_OFFSET, vec![Block::String(argument_offset)]);
+
+        let parts = self.extract_parts(pattern);
+
+        // looking for (key block)+ sequence
+        let mut pos = 0;
+        while pos < parts.len() {
             let part = &parts[pos];
+            let key = &part.value;
 
-            let value = match part.typ {
-                ElementType::Block => self.parse_block(part.value.clone()),
-                ElementType::String => panic!("assert_eqed block type"),
+            pos += 1;
+            let Some(value) = self.parse_branch(key, parts.get(pos)) else {
+                break;
             };
 
+            self.check_plural_key(key);
             let key = KV_RE.replace_all(key, |caps: &Captures| caps[1].to_owned());
             let key = ParamValue::parse_number(&key).unwrap_or_else(|| key.into_owned().into());
             result.insert(key, value);
@@ -338,27 +1463,254 @@ impl<'l> MessageFormat<'l> {
             pos += 1;
         }
 
-        assert!(
-            result.contains_key(&OTHER),
-            "missing other key in ordinal statement"
-        );
+        if !result.contains_key(&OTHER) {
+            self.fail(ParseError::MissingOther("ordinal".to_owned()));
+        }
 
         result
     }
 }
 
-fn placeholder(idx: usize) -> String {
-    const LITERAL_PLACEHOLDER: &str = "\u{FDDF}_";
-    format!("_{LITERAL_PLACEHOLDER}{idx}_")
+impl fmt::Display for MessageFormat<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
 }
 
-#[derive(Debug)]
+/// Appends text to `blocks`, with quoted literals and escaped apostrophes as
+/// literal blocks, so that `#` in text blocks are the numbers of plural and
+/// ordinal blocks.
+fn push_text(blocks: &mut Vec<Block>, text: &str) {
+    let quotes = find_quotes(text);
+    let push_literal = |blocks: &mut Vec<Block>, literal: &str| match blocks.last_mut() {
+        Some(Block::Literal(text)) => text.push_str(literal),
+        _ => blocks.push(Block::Literal(literal.to_owned())),
+    };
+    let push_unquoted = |blocks: &mut Vec<Block>, span: std::ops::Range<usize>| {
+        let mut end = span.start;
+        for pos in quotes.apostrophes_in(span.clone()) {
+            if pos > end {
+                blocks.push(Block::String(text[end..pos].to_owned()));
+            }
+            push_literal(blocks, "'");
+            end = pos + 2;
+        }
+        if end < span.end {
+            blocks.push(Block::String(text[end..span.end].to_owned()));
+        }
+    };
+
+    let mut end = 0;
+    for literal in &quotes.literals {
+        push_unquoted(blocks, end..literal.start);
+        end = literal.end;
+
+        let mut quoted = String::new();
+        quotes.unescape(&mut quoted, text, literal.start + 1..literal.end - 1);
+        push_literal(blocks, &quoted);
+    }
+    push_unquoted(blocks, end..text.len());
+}
+
+/// Returns the block of `argument` containing the branch `key`, and the
+/// branch.
+fn find_branch<'b>(
+    blocks: &'b [Block],
+    argument: &str,
+    key: &ParamValue,
+) -> Option<(&'b Block, &'b Vec<Block>)> {
+    blocks.iter().find_map(|block| {
+        let (Block::Select(branches) | Block::Plural(branches, _) | Block::Ordinal(branches, _)) = block
+        else {
+            return None;
+        };
+        if let Some(branch) = branches.get(key) {
+            if matches!(branches[&ARGUMENT_NAME].first(), Some(Block::String(name)) if name == argument)
+            {
+                return Some((block, branch));
+            }
+        }
+        branches
+            .values()
+            .find_map(|branch| find_branch(branch, argument, key))
+    })
+}
+
+/// Branches are kept in declaration order.
+#[derive(Debug, Clone)]
 enum Block {
-    Select(HashMap<ParamValue, Vec<Block>>),
-    Plural(HashMap<ParamValue, Vec<Block>>),
-    Ordinal(HashMap<ParamValue, Vec<Block>>),
+    Select(IndexMap<ParamValue, Vec<Block>>),
+    /// Branches, and whether a branch has a category other than `other`,
+    /// i.e. whether the plural rules are evaluated to select one
+    Plural(IndexMap<ParamValue, Vec<Block>>, bool),
+    Ordinal(IndexMap<ParamValue, Vec<Block>>, bool),
+    /// Text of the pattern, whose `#` are the numbers of plural and ordinal
+    /// blocks
     String(String),
+    /// Text which is formatted verbatim, e.g. quoted in the pattern
+    Literal(String),
     Simple(String),
+    Number(String, NumberOptions),
+    DateTime(String, DateTimeStyle),
+    /// Start of a tag, which is closed in the same list of blocks, see
+    /// [`MessageFormat::with_tag_handler`]
+    TagOpen(String),
+    TagClose(String),
+    /// Argument of a type of [`MessageFormat::with_argument_type`]: name,
+    /// type and style
+    Custom(String, String, String),
+}
+
+impl Block {
+    fn plural(branches: IndexMap<ParamValue, Vec<Block>>) -> Self {
+        let has_categories = has_categories(&branches);
+        Self::Plural(branches, has_categories)
+    }
+
+    fn ordinal(branches: IndexMap<ParamValue, Vec<Block>>) -> Self {
+        let has_categories = has_categories(&branches);
+        Self::Ordinal(branches, has_categories)
+    }
+}
+
+/// Whether a plural or ordinal block has a branch for a category except
+/// `other`, e.g. `one`
+fn has_categories(branches: &IndexMap<ParamValue, Vec<Block>>) -> bool {
+    ["zero", "one", "two", "few", "many"]
+        .into_iter()
+        .any(|category| branches.contains_key(&ParamValue::from(category)))
+}
+
+fn uses_pound(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| match block {
+        Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+            branches.values().any(|branch| contains_pound(branch))
+        }
+        Block::Select(branches) => branches.values().any(|branch| uses_pound(branch)),
+        _ => false,
+    })
+}
+
+fn contains_pound(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| match block {
+        Block::String(value) => value.contains('#'),
+        Block::Select(branches) | Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+            branches.values().any(|branch| contains_pound(branch))
+        }
+        _ => false,
+    })
+}
+
+/// Whether there is a `#` outside of plural and ordinal blocks.
+fn has_stray_pound(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| match block {
+        Block::String(text) => text.contains('#'),
+        Block::Select(branches) => branches
+            .iter()
+            .any(|(key, branch)| *key != ARGUMENT_NAME && has_stray_pound(branch)),
+        Block::Literal(_)
+        | Block::Simple(_)
+        | Block::Number(..)
+        | Block::DateTime(..)
+        | Block::Custom(..)
+        | Block::TagOpen(_)
+        | Block::TagClose(_)
+        | Block::Plural(..)
+        | Block::Ordinal(..) => false,
+    })
+}
+
+fn arguments(blocks: &[Block], names: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Simple(name)
+            | Block::Number(name, _)
+            | Block::DateTime(name, _)
+            | Block::Custom(name, ..) => {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            Block::Select(branches) | Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+                for (key, branch) in branches {
+                    if *key == ARGUMENT_OFFSET {
+                        continue;
+                    }
+                    match branch.first() {
+                        Some(Block::String(name)) if *key == ARGUMENT_NAME => {
+                            if !names.contains(name) {
+                                names.push(name.clone());
+                            }
+                        }
+                        _ => arguments(branch, names),
+                    }
+                }
+            }
+            Block::String(_) | Block::Literal(_) | Block::TagOpen(_) | Block::TagClose(_) => {}
+        }
+    }
+}
+
+fn argument_types(blocks: &[Block], types: &mut Vec<(String, ArgumentType)>) {
+    for block in blocks {
+        let (name, typ, branches) = match block {
+            Block::String(_) | Block::Literal(_) | Block::TagOpen(_) | Block::TagClose(_) => {
+                continue
+            }
+            Block::Simple(name) | Block::Custom(name, ..) => (name, ArgumentType::Any, None),
+            Block::Number(name, _) => (name, ArgumentType::Number, None),
+            Block::DateTime(name, _) => (name, ArgumentType::DateTime, None),
+            Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+                let Some(Block::String(name)) = branches[&ARGUMENT_NAME].first() else {
+                    continue;
+                };
+                (name, ArgumentType::Number, Some(branches))
+            }
+            Block::Select(branches) => {
+                let Some(Block::String(name)) = branches[&ARGUMENT_NAME].first() else {
+                    continue;
+                };
+                let keys = branches
+                    .keys()
+                    .filter(|key| **key != ARGUMENT_NAME && **key != OTHER)
+                    .map(ToString::to_string)
+                    .collect();
+                (name, ArgumentType::Select(keys), Some(branches))
+            }
+        };
+        infer_type(types, name, typ);
+        for (key, branch) in branches.into_iter().flatten() {
+            if *key != ARGUMENT_NAME && *key != ARGUMENT_OFFSET {
+                argument_types(branch, types);
+            }
+        }
+    }
+}
+
+fn infer_type(types: &mut Vec<(String, ArgumentType)>, name: &str, typ: ArgumentType) {
+    let Some(idx) = types.iter().position(|(n, _)| n == name) else {
+        types.push((name.to_owned(), typ));
+        return;
+    };
+    match (&mut types[idx].1, typ) {
+        (ArgumentType::Select(keys), ArgumentType::Select(new_keys)) => {
+            for key in new_keys {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        (ArgumentType::Number | ArgumentType::DateTime, _) | (_, ArgumentType::Any) => {}
+        (existing, typ) => *existing = typ,
+    }
+}
+
+fn has_plural(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| match block {
+        Block::Plural(..) | Block::Ordinal(..) => true,
+        Block::Select(branches) => branches.values().any(|branch| has_plural(branch)),
+        _ => false,
+    })
 }
 
 #[derive(Debug)]
@@ -366,6 +1718,10 @@ enum BlockType {
     Plural,
     Ordinal,
     Select,
+    JointSelect,
+    Number,
+    DateTime,
+    Empty,
     Simple,
     Unknown,
 }
@@ -394,13 +1750,14 @@ enum ElementType {
 #[cfg(test)]
 mod tests {
     use icu::locid::locale;
+    use icu_decimal::options::GroupingStrategy;
 
     use super::*;
 
     #[test]
     fn test_empty_pattern() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("", &locale);
+        let fmt = MessageFormat::new("", &locale);
         assert_eq!(fmt.format(), "");
     }
 
@@ -408,7 +1765,7 @@ mod tests {
     #[should_panic(expected = "No matching { for }")]
     fn test_missing_left_curly_brace() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("\'\'{}}", &locale);
+        let fmt = MessageFormat::new("\'\'{}}", &locale);
         fmt.format();
     }
 
@@ -416,14 +1773,14 @@ mod tests {
     #[should_panic(expected = "There are mismatched { or } in the pattern")]
     fn test_too_many_left_curly_braces() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{} {", &locale);
+        let fmt = MessageFormat::new("{} {", &locale);
         fmt.format();
     }
 
     #[test]
     fn test_simple_replacement() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("New York in {SEASON} is nice.", &locale);
+        let fmt = MessageFormat::new("New York in {SEASON} is nice.", &locale);
         assert_eq!(
             fmt.format_with_params([("SEASON", "the Summer".into())]),
             "New York in the Summer is nice."
@@ -433,7 +1790,7 @@ mod tests {
     #[test]
     fn test_simple_select() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{GENDER, select,\
             male {His}\
             female {Her}\
@@ -462,7 +1819,7 @@ mod tests {
     #[test]
     fn test_simple_plural() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "I see {NUM_PEOPLE, plural, offset:1 \
             =0 {no one at all in {PLACE}.} \
             =1 {{PERSON} in {PLACE}.} \
@@ -503,7 +1860,7 @@ mod tests {
     #[test]
     fn test_select_nested_in_plural() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{CIRCLES, plural, \
         one {{GENDER, select, \
           female {{WHO} added you to her circle} \
@@ -535,12 +1892,12 @@ mod tests {
     fn test_plural_nested_in_select() {
         // Added offset just for testing purposes. It doesn't make sense to have it otherwise.
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{GENDER, select, \
         female {{NUM_GROUPS, plural, \
           one {{WHO} added you to her group} \
           other {{WHO} added you to her # groups}}} \
-        other {{NUM_GROUPS, plural, offset,1\
+        other {{NUM_GROUPS, plural, offset:1\
           one {{WHO} added you to his group} \
           other {{WHO} added you to his # groups}}}}",
             &locale,
@@ -560,14 +1917,14 @@ mod tests {
                 ("WHO", "Milan".into()),
                 ("NUM_GROUPS", 1234.into())
             ]),
-            "Milan added you to his 1,234 groups",
+            "Milan added you to his 1,233 groups",
         );
     }
 
     #[test]
     fn test_literal_open_curly_brace() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "Anna's house has '{0} and # in the roof' and {NUM_COWS} cows.",
             &locale,
         );
@@ -580,7 +1937,7 @@ mod tests {
     #[test]
     fn test_literal_closed_curly_brace() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "Anna's house has '{'0'} and # in the roof' and {NUM_COWS} cows.",
             &locale,
         );
@@ -597,7 +1954,7 @@ mod tests {
     #[test]
     fn test_literal_pound_sign() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "Anna's house has '{0}' and '# in the roof' and {NUM_COWS} cows.",
             &locale,
         );
@@ -614,7 +1971,7 @@ mod tests {
     #[test]
     fn test_no_literals_for_single_quotes() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("Anna's house 'has {NUM_COWS} cows'.", &locale);
+        let fmt = MessageFormat::new("Anna's house 'has {NUM_COWS} cows'.", &locale);
         assert_eq!(
             fmt.format_with_params([("NUM_COWS", 5.into())]),
             "Anna's house 'has 5 cows'."
@@ -624,21 +1981,31 @@ mod tests {
     #[test]
     fn test_consecutive_single_quotes_are_replaced_with_one_single_quote() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("Anna''s house a'{''''b'", &locale);
+        let fmt = MessageFormat::new("Anna''s house a'{''''b'", &locale);
         assert_eq!(fmt.format(), "Anna's house a{''b");
     }
 
     #[test]
     fn test_consecutive_single_quotes_before_special_char_dont_create_literal() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("a''{NUM_COWS}'b", &locale);
+        let fmt = MessageFormat::new("a''{NUM_COWS}'b", &locale);
         assert_eq!(fmt.format_with_params([("NUM_COWS", 5.into())]), "a'5'b");
     }
 
+    #[test]
+    fn test_quoted_literals_end_on_the_same_line() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("'{a}' {N, plural, other {'#\n'x}} '{b''}' '#'", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 5.into())]),
+            "{a} '5\n'x {b'} #"
+        );
+    }
+
     #[test]
     fn test_serbian_simple_select() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{GENDER, select, female {Njen} other {Njegov}} bicikl je \
              {GENDER, select, female {crven} other {plav}}.",
             &locale,
@@ -657,7 +2024,7 @@ mod tests {
     #[test]
     fn test_serbian_simple_plural() {
         let locale = locale!("sr");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "Ja {NUM_PEOPLE, plural, offset:1 \
             =0 {ne vidim nikoga} \
             =1 {vidim {PERSON}} \
@@ -710,7 +2077,7 @@ mod tests {
     #[test]
     fn test_serbian_simple_plural_no_offset() {
         let locale = locale!("sr");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "Ja {NUM_PEOPLE, plural, \
             =0 {ne vidim nikoga} \
             =1 {vidim {PERSON}} \
@@ -763,7 +2130,7 @@ mod tests {
     #[test]
     fn test_serbian_select_nested_in_plural() {
         let locale = locale!("sr");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{CIRCLES, plural, \
             one {{GENDER, select, \
               female {{WHO} vas je dodala u njen # kruzok} \
@@ -820,7 +2187,7 @@ mod tests {
         // Only locale and numbers matter, the actual language of the message
         // does not.
         let locale = locale!("ar-DZ");
-        let mut fmt = MessageFormat::new("{NUM_MINUTES, plural, other {# minutes}}", &locale);
+        let fmt = MessageFormat::new("{NUM_MINUTES, plural, other {# minutes}}", &locale);
 
         // These numbers exercise all cases for the arabic plural rules.
         assert_eq!(
@@ -852,7 +2219,7 @@ mod tests {
     #[test]
     fn test_pound_shows_number_minus_offset_in_all_cases() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{SOME_NUM, plural, offset:1 =0 {#} =1 {#} =2 {#} one {#} other {#}}",
             &locale,
         );
@@ -866,7 +2233,7 @@ mod tests {
     #[test]
     fn test_special_characters_in_paramater_dont_change_format() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{SOME_NUM, plural, other {# {GROUP}}}", &locale);
+        let fmt = MessageFormat::new("{SOME_NUM, plural, other {# {GROUP}}}", &locale);
 
         // Test pound sign.
         assert_eq!(
@@ -883,7 +2250,7 @@ mod tests {
     #[test]
     fn test_missing_or_invalid_plural_parameter() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{SOME_NUM, plural, other {result}}", &locale);
+        let fmt = MessageFormat::new("{SOME_NUM, plural, other {result}}", &locale);
 
         // Key name doesn"t match A != SOME_NUM.
         assert_eq!(
@@ -901,7 +2268,7 @@ mod tests {
     #[test]
     fn test_missing_select_parameter() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{GENDER, select, other {result}}", &locale);
+        let fmt = MessageFormat::new("{GENDER, select, other {result}}", &locale);
 
         // Key name doesn"t match A != GENDER.
         assert_eq!(
@@ -913,7 +2280,7 @@ mod tests {
     #[test]
     fn test_missing_simple_placeholder() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{result}", &locale);
+        let fmt = MessageFormat::new("{result}", &locale);
 
         // Key name doesn"t match A != result.
         assert_eq!(
@@ -925,7 +2292,7 @@ mod tests {
     #[test]
     fn test_plural() {
         let locale = locale!("ru");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{SOME_NUM, plural,\
             =0 {none}\
             =1 {exactly one}\
@@ -966,7 +2333,7 @@ mod tests {
     #[test]
     fn test_plural_with_ignore_pound() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{SOME_NUM, plural, other {# {GROUP}}}", &locale);
+        let fmt = MessageFormat::new("{SOME_NUM, plural, other {# {GROUP}}}", &locale);
 
         // Test pound sign.
         assert_eq!(
@@ -983,7 +2350,7 @@ mod tests {
     #[test]
     fn test_simple_plural_with_ignore_pound() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "I see {NUM_PEOPLE, plural, offset:1 \
           =0 {no one at all in {PLACE}.} \
           =1 {{PERSON} in {PLACE}.} \
@@ -1005,7 +2372,7 @@ mod tests {
     #[test]
     fn test_romanian_offset_with_negative_value() {
         let locale = locale!("ro");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{NUM_FLOOR, plural, offset:2 \
           one {One #}\
           few {Few #}\
@@ -1034,12 +2401,10 @@ mod tests {
         );
     }
 
-    #[ignore = "ordinals are not supported"]
     #[test]
     fn test_simple_ordinal() {
-        // TOFIX. Ordinal not supported in Dart
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{NUM_FLOOR, selectordinal, \
           one {Take the elevator to the #st floor.}\
           two {Take the elevator to the #nd floor.}\
@@ -1075,12 +2440,10 @@ mod tests {
         );
     }
 
-    #[ignore = "ordinals are not supported"]
     #[test]
     fn test_ordinal_with_negative_value() {
-        // TOFIX. Ordinal not supported in Dart
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{NUM_FLOOR, selectordinal, \
           one {Take the elevator to the #st floor.}\
           two {Take the elevator to the #nd floor.}\
@@ -1110,7 +2473,7 @@ mod tests {
     #[test]
     fn test_simple_ordinal_with_ignore_pound() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{NUM_FLOOR, selectordinal, \
           one {Take the elevator to the #st floor.}\
           two {Take the elevator to the #nd floor.}\
@@ -1125,29 +2488,28 @@ mod tests {
         );
     }
 
-    #[ignore = "ordinals are not supported"]
     #[test]
     fn test_missing_or_invalid_ordinal_parameter() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new("{SOME_NUM, selectordinal, other {result}}", &locale);
+        let fmt = MessageFormat::new("{SOME_NUM, selectordinal, other {result}}", &locale);
 
         // Key name doesn"t match A != SOME_NUM.
         assert_eq!(
             fmt.format_with_params([("A", 10.into())]),
-            "Undefined or invalid parameter - SOME_NUM"
+            "Undefined parameter - SOME_NUM"
         );
 
         // Value is not a number.
         assert_eq!(
             fmt.format_with_params([("SOME_NUM", "Value".into())]),
-            "Undefined or invalid parameter - SOME_NUM"
+            "Invalid parameter - SOME_NUM"
         );
     }
 
     #[test]
     fn test_int_as_float_singular_plural_or_zero() {
         let locale = locale!("en");
-        let mut fmt = MessageFormat::new(
+        let fmt = MessageFormat::new(
             "{n, plural, =0 {No more messages} =1 {1 more message} other {{n} more messages}}",
             &locale,
         );
@@ -1164,4 +2526,3377 @@ mod tests {
             "10 more messages"
         );
     }
+
+    #[test]
+    fn test_plural_range() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{DAYS, plural, one {# day} other {# days}}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(1, 3))]),
+            "1–3 days"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(0.5, 1))]),
+            "0.5–1 days"
+        );
+        assert_eq!(
+            fmt.format_ignoring_pound([("DAYS", ParamValue::range(1, 3))]),
+            "# days"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range("a", 3))]),
+            "Invalid parameter - DAYS"
+        );
+    }
+
+    #[test]
+    fn test_russian_plural_range() {
+        let locale = locale!("ru");
+        let fmt = MessageFormat::new(
+            "{DAYS, plural, offset:1 one {# one} few {# few} many {# many} other {# other}}",
+            &locale,
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(2, 3))]),
+            "1–2 few"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(3, 6))]),
+            "2–5 many"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(6, 22))]),
+            "5–21 one"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DAYS", ParamValue::range(1, 2.5))]),
+            "0–1,5 other"
+        );
+    }
+
+    #[test]
+    fn test_simple_range() {
+        let locale = locale!("de");
+        let fmt = MessageFormat::new("{DISTANCE} km", &locale);
+        assert_eq!(
+            fmt.format_with_params([("DISTANCE", ParamValue::range(3, 5))]),
+            "3–5 km"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DISTANCE", ParamValue::range(1000, 2500.5))]),
+            "1.000–2.500,5 km"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DISTANCE", ParamValue::range(5, 5.0))]),
+            "5 km"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DISTANCE", ParamValue::range(-5, -3))]),
+            "-5 – -3 km"
+        );
+        let ja = locale!("ja");
+        assert_eq!(
+            MessageFormat::new("{D}km", &ja).format_with_params([("D", ParamValue::range(3, 5))]),
+            "3～5km"
+        );
+    }
+
+    #[test]
+    fn test_number_range() {
+        let range = || [("R", ParamValue::range(10, 20))];
+        let format = |pattern, locale: &Locale| {
+            MessageFormat::new(pattern, locale).format_with_params(range())
+        };
+        let en_us = locale!("en-US");
+        let de_de = locale!("de-DE");
+        // one-character currency symbols are repeated, longer ones shared
+        assert_eq!(format("{R, number, currency}", &en_us), "$10.00 – $20.00");
+        assert_eq!(
+            format("{R, number, currency}", &de_de),
+            "10,00–20,00\u{a0}€"
+        );
+        assert_eq!(
+            format("{R, number, ::currency/CHF precision-integer}", &en_us),
+            "CHF\u{a0}10–20"
+        );
+        assert_eq!(
+            format("{R, number, ::currency/JPY}", &locale!("ja")),
+            "￥10 ～ ￥20"
+        );
+        assert_eq!(format("{R, number, percent}", &en_us), "1,000% – 2,000%");
+        assert_eq!(format("{R, number, percent}", &de_de), "1.000–2.000\u{a0}%");
+        assert_eq!(format("{R, number, ::.0}", &en_us), "10.0–20.0");
+
+        let fmt = MessageFormat::new("{R, number, ::compact-long}", &en_us);
+        let params = [("R", ParamValue::range(3000, 5000))];
+        assert_eq!(fmt.format_with_params(params), "3–5 thousand");
+        let params = [("R", ParamValue::range(3000, 5000000))];
+        assert_eq!(fmt.format_with_params(params), "3 thousand – 5 million");
+        let fmt = MessageFormat::new("{R, number, ::compact-short}", &en_us);
+        let params = [("R", ParamValue::range(3000, 5000))];
+        assert_eq!(fmt.format_with_params(params), "3K – 5K");
+
+        let fmt = MessageFormat::new("{R, number, currency}", &en_us);
+        let params = [("R", ParamValue::range(-20, -10))];
+        assert_eq!(fmt.format_with_params(params), "-$20.00 – -$10.00");
+        let params = [("R", ParamValue::range(5, 5.0))];
+        assert_eq!(fmt.format_with_params(params), "$5.00");
+        let params = [(
+            "R",
+            ParamValue::number(ParamValue::range(1, 2)).max_fraction_digits(0),
+        )];
+        assert_eq!(fmt.format_with_params(params), "$1 – $2");
+
+        let fmt = MessageFormat::new("{R, plural, one {# item} other {# items}}", &en_us);
+        let params = [(
+            "R",
+            ParamValue::number(ParamValue::range(1, 2)).min_fraction_digits(1),
+        )];
+        assert_eq!(fmt.format_with_params(params), "1.0–2.0 items");
+    }
+
+    #[test]
+    fn test_numbering_system_extension() {
+        let pattern = "{NUM} / {COUNT, plural, offset:1 one {# file} other {# files}}";
+        let params = || [("NUM", 1234.5.into()), ("COUNT", 13.into())];
+
+        let locale: Locale = "ar-u-nu-latn".parse().unwrap();
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(fmt.format_with_params(params()), "1,234.5 / 12 files");
+
+        let locale: Locale = "ar-u-nu-arab".parse().unwrap();
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(fmt.format_with_params(params()), "١٬٢٣٤٫٥ / ١٢ files");
+
+        let locale: Locale = "hi-u-nu-deva".parse().unwrap();
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(fmt.format_with_params(params()), "१,२३४.५ / १२ files");
+    }
+
+    #[test]
+    fn test_numbering_system_override() {
+        use icu::locid::extensions::unicode::value;
+
+        let pattern = "{NUM} / {COUNT, plural, one {# file} other {# files}}";
+        let params = || [("NUM", 1234.5.into()), ("COUNT", 12.into())];
+
+        let locale: Locale = "ar-u-nu-arab".parse().unwrap();
+        let fmt = MessageFormat::new(pattern, &locale).with_numbering_system(value!("latn"));
+        assert_eq!(fmt.format_with_params(params()), "1,234.5 / 12 files");
+
+        let locale = locale!("hi");
+        let fmt = MessageFormat::new(pattern, &locale).with_numbering_system(value!("deva"));
+        assert_eq!(fmt.format_with_params(params()), "१,२३४.५ / १२ files");
+    }
+
+    #[test]
+    fn test_number_significant_digits() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, number, @@#} rows", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 1234567.into())]),
+            "1,230,000 rows"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", 0.012345.into())]),
+            "0.0123 rows"
+        );
+        assert_eq!(fmt.format_with_params([("N", 1.5.into())]), "1.5 rows");
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "2.0 rows");
+        assert_eq!(fmt.format_with_params([("N", "9.999".into())]), "10.0 rows");
+
+        let fmt = MessageFormat::new("{N, number, @@@}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 0.into())]), "0.00");
+        assert_eq!(fmt.format_with_params([("N", (-12_i64).into())]), "-12.0");
+    }
+
+    #[test]
+    fn test_number_without_style() {
+        let locale = locale!("de");
+        let fmt = MessageFormat::new("{N, number} / {N,number,}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 1234.5.into())]),
+            "1.234,5 / 1.234,5"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", "many".into())]),
+            "Invalid parameter - N / Invalid parameter - N"
+        );
+        assert_eq!(
+            fmt.format_with_params([("M", 1.into())]),
+            "Undefined parameter - N / Undefined parameter - N"
+        );
+    }
+
+    #[test]
+    fn test_unknown_number_style() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, number, @#@}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1234.5.into())]), "1,234.5");
+    }
+
+    #[test]
+    fn test_number_skeleton() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, number, ::compact-short} rows", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1234567.into())]), "1.2M rows");
+        assert_eq!(fmt.format_with_params([("N", 999.into())]), "999 rows");
+
+        let fmt = MessageFormat::new("{N, number, ::KK}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 25000.into())]), "25 thousand");
+
+        let fmt = MessageFormat::new("{N, number, :: .00 group-off}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 12345.678.into())]),
+            "12345.68"
+        );
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "2.00");
+
+        let fmt = MessageFormat::new("{N, number, ::.0#}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "2.0");
+        assert_eq!(fmt.format_with_params([("N", 2.125.into())]), "2.12");
+
+        let fmt = MessageFormat::new("{N, number, ::@@+ ,?}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 3.into())]), "3.0");
+        assert_eq!(
+            fmt.format_with_params([("N", 1234.5678.into())]),
+            "1234.5678"
+        );
+        assert_eq!(fmt.format_with_params([("N", 12345.into())]), "12,345");
+
+        let fmt = MessageFormat::new("{N, number, ::precision-integer}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 2.5.into())]), "2");
+        assert_eq!(fmt.format_with_params([("N", 3.5.into())]), "4");
+    }
+
+    #[test]
+    fn test_number_skeleton_numbering_system() {
+        let locale = locale!("ar-EG");
+        let fmt = MessageFormat::new("{N} / {N, number, ::latin}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 12.into())]), "١٢ / 12");
+
+        let locale = locale!("hi");
+        let fmt = MessageFormat::new("{N, number, ::numbering-system/deva}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 12.into())]), "१२");
+    }
+
+    #[test]
+    fn test_unsupported_number_skeleton() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, number, ::compact-short currency/EUR}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1234567.into())]), "1,234,567");
+        assert_eq!(
+            MessageFormat::try_new("{N, number, ::compact-short currency/EUR}", &locale)
+                .unwrap_err(),
+            ParseError::UnknownNumberStyle("::compact-short currency/EUR".to_owned())
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_params() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("Due {DATE}", &locale);
+
+        let date = NaiveDate::from_ymd_opt(2025, 1, 3).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("DATE", date.into())]),
+            "Due 1/3/25"
+        );
+
+        let date_time = date.and_hms_opt(14, 30, 0).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Due 1/3/25, 2:30\u{202f}PM"
+        );
+
+        let date_time = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2025, 1, 3, 23, 5, 0)
+            .unwrap();
+        let locale = locale!("de");
+        let fmt = MessageFormat::new("Fällig {DATE}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Fällig 03.01.25, 23:05"
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_params() {
+        use time::{Date, Month, Time, UtcOffset};
+
+        let date = Date::from_calendar_date(2025, Month::January, 3).unwrap();
+        let date_time = date.with_time(Time::from_hms(14, 30, 0).unwrap());
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("Due {DATE}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("DATE", date.into())]),
+            "Due 1/3/25"
+        );
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Due 1/3/25, 2:30\u{202f}PM"
+        );
+
+        let date_time = date
+            .with_time(Time::from_hms(23, 5, 0).unwrap())
+            .assume_offset(UtcOffset::from_hms(1, 0, 0).unwrap());
+        let locale = locale!("de");
+        let fmt = MessageFormat::new("Fällig {DATE}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Fällig 03.01.25, 23:05"
+        );
+    }
+
+    #[test]
+    fn test_system_time_param() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let locale = locale!("de");
+        let fmt = MessageFormat::new("Stand: {DATE}", &locale);
+        let date_time = UNIX_EPOCH + Duration::from_secs(1_735_914_300);
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Stand: 03.01.25, 14:25"
+        );
+        let date_time = UNIX_EPOCH - Duration::from_millis(500);
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Stand: 31.12.69, 23:59"
+        );
+    }
+
+    #[test]
+    fn test_icu_calendar_params() {
+        use icu::calendar::{japanese::Japanese, Date, DateTime};
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("Due {DATE}", &locale);
+
+        let date = Date::try_new_iso_date(2025, 1, 3).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("DATE", date.into())]),
+            "Due 1/3/25"
+        );
+
+        let date = date.to_calendar(Japanese::new());
+        assert_eq!(
+            fmt.format_with_params([("DATE", date.into())]),
+            "Due 1/3/25"
+        );
+
+        let date_time = DateTime::try_new_gregorian_datetime(2025, 1, 3, 14, 30, 0).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("DATE", date_time.into())]),
+            "Due 1/3/25, 2:30\u{202f}PM"
+        );
+
+        // formatted in the calendar of the locale
+        let locale = locale!("th");
+        let fmt = MessageFormat::new("{DATE}", &locale);
+        let date = Date::try_new_iso_date(2025, 1, 3).unwrap();
+        assert_eq!(fmt.format_with_params([("DATE", date.into())]), "3/1/68");
+    }
+
+    #[test]
+    fn test_exact_decimal_params() {
+        use fixed_decimal::FixedDecimal;
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, =1 {exactly one item} one {# item} other {# items}}",
+            &locale,
+        );
+        let n: FixedDecimal = "1.0".parse().unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "exactly one item"
+        );
+
+        let fmt = MessageFormat::new("{N, plural, one {# item} other {# items}}", &locale);
+        let n: FixedDecimal = "1.0".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1.0 items");
+        let n: FixedDecimal = "1".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1 item");
+        let n: FixedDecimal = "12345678901234567890.25".parse().unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "12,345,678,901,234,567,890.25 items"
+        );
+
+        let fmt = MessageFormat::new("Total: {N}", &locale);
+        let n: FixedDecimal = "1234.50".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "Total: 1,234.50");
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal_params() {
+        use rust_decimal::Decimal;
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, one {# euro} other {# euros}}", &locale);
+        let n = Decimal::new(100, 2);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1.00 euros");
+        let n = Decimal::new(-1, 0);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "-1 euro");
+        assert_eq!(
+            fmt.format_with_params([("N", Decimal::MAX.into())]),
+            "79,228,162,514,264,337,593,543,950,335 euros"
+        );
+    }
+
+    #[cfg(feature = "bigdecimal")]
+    #[test]
+    fn test_bigdecimal_params() {
+        use bigdecimal::BigDecimal;
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, one {# euro} other {# euros}}", &locale);
+        let n: BigDecimal = "1.00".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1.00 euros");
+        let n: BigDecimal = "1e3".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1,000 euros");
+        let n: BigDecimal = "0.1000000000000000000000000000001".parse().unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "0.1000000000000000000000000000001 euros"
+        );
+
+        // beyond the range of `FixedDecimal`
+        let fmt = MessageFormat::new("{N}", &locale);
+        let n: BigDecimal = "1e40000".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "inf");
+        let n: BigDecimal = "-1e-40000".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "-0");
+        let n = BigDecimal::new(1.into(), -32768);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "inf");
+        let n = BigDecimal::new(25.into(), -32767);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "inf");
+        let n = BigDecimal::new(25.into(), 40000).with_scale(1);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "0.0");
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_bigint_params() {
+        use num_bigint::{BigInt, BigUint};
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, one {# block} other {# blocks}}", &locale);
+        let n: BigInt = "-123456789012345678901234567890".parse().unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "-123,456,789,012,345,678,901,234,567,890 blocks"
+        );
+        let n = BigUint::from(1u8);
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "1 block");
+
+        let locale = locale!("ru");
+        let fmt = MessageFormat::new(
+            "{N, plural, one {# блок} few {# блока} many {# блоков} other {# блока}}",
+            &locale,
+        );
+        let n = BigUint::from(u64::MAX) * 10u8 + 1u8;
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "184\u{a0}467\u{a0}440\u{a0}737\u{a0}095\u{a0}516\u{a0}151 блок"
+        );
+    }
+
+    #[test]
+    fn test_option_params() {
+        let locale = locale!("en");
+        let pattern = "Name: {NAME}, {GENDER, select, female {she} male {he} other {they}}";
+
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(
+            fmt.format_with_params([
+                ("NAME", Some("Ann").into()),
+                ("GENDER", Some("female").into())
+            ]),
+            "Name: Ann, she"
+        );
+        assert_eq!(
+            fmt.format_with_params([
+                ("NAME", None::<&str>.into()),
+                ("GENDER", None::<&str>.into())
+            ]),
+            "Name: , they"
+        );
+
+        let fmt = MessageFormat::new(pattern, &locale).with_none(NoneParam::Text("—".into()));
+        assert_eq!(
+            fmt.format_with_params([
+                ("NAME", None::<&str>.into()),
+                ("GENDER", None::<&str>.into())
+            ]),
+            "Name: —, they"
+        );
+
+        let fmt = MessageFormat::new(pattern, &locale).with_none(NoneParam::Undefined);
+        assert_eq!(
+            fmt.format_with_params([("NAME", None::<&str>.into()), ("GENDER", "male".into())]),
+            "Name: Undefined parameter - NAME, he"
+        );
+    }
+
+    #[test]
+    fn test_number_param_overrides() {
+        let locale = locale!("en");
+
+        let fmt = MessageFormat::new("{N}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::number(1234.5678).max_fraction_digits(1))]),
+            "1,234.6"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::from(5).min_fraction_digits(2))]),
+            "5.00"
+        );
+
+        let fmt = MessageFormat::new("{N, number, ::.000 group-min2}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 1234.5678.into())]),
+            "1234.568"
+        );
+        assert_eq!(
+            fmt.format_with_params([(
+                "N",
+                ParamValue::number(1234.5678).max_significant_digits(2)
+            )]),
+            "1200"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::number(12345).without_grouping())]),
+            "12345.000"
+        );
+
+        let fmt = MessageFormat::new("{N, plural, one {# star} other {# stars}}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::number(1.4).max_fraction_digits(0))]),
+            "1 star"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::number(1.04).max_fraction_digits(1))]),
+            "1.0 stars"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", ParamValue::number(1).min_fraction_digits(1))]),
+            "1.0 stars"
+        );
+    }
+
+    #[test]
+    fn test_casing() {
+        let locale = locale!("en");
+        let pattern = "{FRUIT} is on sale, buy {FRUIT} now!";
+
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(
+            fmt.format_with_params([("FRUIT", "Apple".into())]),
+            "Apple is on sale, buy Apple now!"
+        );
+
+        let fmt = MessageFormat::new(pattern, &locale).with_casing(Casing::SentenceCase);
+        assert_eq!(
+            fmt.format_with_params([("FRUIT", "Apple".into())]),
+            "Apple is on sale, buy apple now!"
+        );
+
+        let fmt = MessageFormat::new("«{FRUIT}» is on sale", &locale)
+            .with_casing(Casing::CapitalizeFirst);
+        assert_eq!(
+            fmt.format_with_params([("FRUIT", "apple".into())]),
+            "«Apple» is on sale"
+        );
+
+        let locale = locale!("tr");
+        let fmt =
+            MessageFormat::new("{CITY} veya {CITY}", &locale).with_casing(Casing::SentenceCase);
+        assert_eq!(
+            fmt.format_with_params([("CITY", "İstanbul".into())]),
+            "İstanbul veya istanbul"
+        );
+    }
+
+    #[test]
+    fn test_max_len() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{NAME} sent you a message", &locale).with_max_len(12);
+        assert_eq!(
+            fmt.format_with_params([("NAME", "Ann".into())]),
+            "Ann sent yo…"
+        );
+        assert_eq!(
+            fmt.format_with_params([("NAME", "👩‍👩‍👧‍👦👍🏽".into())]),
+            "👩‍👩‍👧‍👦👍🏽 sent you…"
+        );
+
+        let fmt = MessageFormat::new("{NAME} waves", &locale).with_max_len(12);
+        assert_eq!(
+            fmt.format_with_params([("NAME", "Zoë".into())]),
+            "Zoë waves"
+        );
+    }
+
+    #[test]
+    fn test_plural_samples() {
+        let samples = plural_samples(&locale!("ru"), "few");
+        assert_eq!(samples.integers, [2, 3, 4, 22, 23, 24, 32, 33, 34, 42]);
+        assert!(samples.decimals.is_empty());
+
+        let samples = plural_samples(&locale!("ru"), "other");
+        assert!(samples.integers.is_empty());
+        assert_eq!(samples.decimals[..4], ["0.0", "0.00", "0.1", "0.01"]);
+
+        let samples = plural_samples(&locale!("fr"), "many");
+        assert_eq!(samples.integers, [1_000_000, 10_000_000]);
+
+        let samples = plural_samples(&locale!("en"), "few");
+        assert_eq!(samples, PluralSamples::default());
+    }
+
+    #[test]
+    fn test_closure_ordinal() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{NUM_FLOOR, selectordinal, \
+          one {Take the elevator to the #st floor.}\
+          two {Take the elevator to the #nd floor.}\
+          few {Take the elevator to the #rd floor.}\
+          other {Take the elevator to the #th floor.}}",
+            &locale,
+        )
+        .with_compatibility(Compatibility::Closure);
+
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", 1.into())]),
+            "Take the elevator to the 1st floor."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", 2.into())]),
+            "Take the elevator to the 2nd floor."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", 23.into())]),
+            "Take the elevator to the 23rd floor."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", 0.into())]),
+            "Take the elevator to the 0th floor."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", (-3_i64).into())]),
+            "Take the elevator to the -3rd floor."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NUM_FLOOR", ParamValue::range(1, 3))]),
+            "Take the elevator to the 1–3rd floor."
+        );
+    }
+
+    #[test]
+    fn test_dart_compatibility() {
+        let locale = locale!("en");
+        let pattern = "{N, plural, one {# file} other {# files}}, {N, number, ::.00}";
+
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 1200.into())]),
+            "1,200 files, 1,200.00"
+        );
+
+        let fmt = MessageFormat::new(pattern, &locale).with_compatibility(Compatibility::Dart);
+        assert_eq!(
+            fmt.format_with_params([("N", 1200.into())]),
+            "1,200 files, Undefined parameter - N, number, ::.00"
+        );
+    }
+
+    #[test]
+    fn test_icu_compatibility() {
+        // examples of the ICU user guide
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{num_files, plural, \
+            =0 {There are no files on disk \"{disk_name}\".}\
+            =1 {There is one file on disk \"{disk_name}\".}\
+            other {There are # files on disk \"{disk_name}\".}}",
+            &locale,
+        )
+        .with_compatibility(Compatibility::Icu);
+        assert_eq!(
+            fmt.format_with_params([("num_files", 0.into()), ("disk_name", "MyDisk".into())]),
+            "There are no files on disk \"MyDisk\"."
+        );
+        assert_eq!(
+            fmt.format_with_params([("num_files", 1273.into()), ("disk_name", "MyDisk".into())]),
+            "There are 1,273 files on disk \"MyDisk\"."
+        );
+        assert_eq!(
+            fmt.format_with_params([("num_files", 1.into())]),
+            "There is one file on disk \"{disk_name}\"."
+        );
+        assert_eq!(fmt.format(), "{num_files}");
+
+        let fmt = MessageFormat::new(
+            "{gender_of_host, select, \
+            female {\
+              {num_guests, plural, offset:1 \
+                =0 {{host} does not give a party.}\
+                =1 {{host} invites {guest} to her party.}\
+                =2 {{host} invites {guest} and one other person to her party.}\
+                other {{host} invites {guest} and # other people to her party.}}}\
+            other {\
+              {num_guests, plural, offset:1 \
+                =0 {{host} does not give a party.}\
+                =1 {{host} invites {guest} to their party.}\
+                =2 {{host} invites {guest} and one other person to their party.}\
+                other {{host} invites {guest} and # other people to their party.}}}}",
+            &locale,
+        )
+        .with_compatibility(Compatibility::Icu);
+        let params = |gender: &'static str, num_guests: i64| {
+            [
+                ("gender_of_host", gender.into()),
+                ("num_guests", num_guests.into()),
+                ("host", "Ann".into()),
+                ("guest", "Bob".into()),
+            ]
+        };
+        assert_eq!(
+            fmt.format_with_params(params("female", 0)),
+            "Ann does not give a party."
+        );
+        assert_eq!(
+            fmt.format_with_params(params("female", 2)),
+            "Ann invites Bob and one other person to her party."
+        );
+        assert_eq!(
+            fmt.format_with_params(params("other", 5)),
+            "Ann invites Bob and 4 other people to their party."
+        );
+
+        let fmt = MessageFormat::new(
+            "{N, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}",
+            &locale,
+        )
+        .with_compatibility(Compatibility::Icu);
+        assert_eq!(fmt.format_with_params([("N", 22.into())]), "22nd");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid plural key 1")]
+    fn test_icu_compatibility_exact_match_without_equals() {
+        let locale = locale!("en");
+        MessageFormat::new("{N, plural, 1 {one} other {#}}", &locale)
+            .with_compatibility(Compatibility::Icu)
+            .format();
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::parse_strict(
+            "{N, plural, =0 {none} one {# file} other {# files}} in {DIR}",
+            &locale,
+        )
+        .unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", 2.into()), ("DIR", "/tmp".into())]),
+            "2 files in /tmp"
+        );
+
+        assert_eq!(
+            MessageFormat::parse_strict("Due {D, duration, short}", &locale).unwrap_err(),
+            ParseError::UnknownArgumentType("duration".into())
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{N, plural, 0 {none} other {#}}", &locale).unwrap_err(),
+            ParseError::InvalidPluralKey("0".into())
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{N, selectordinal, first {#st} other {#th}}", &locale)
+                .unwrap_err(),
+            ParseError::InvalidPluralKey("first".into())
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{N, select, 1 {one} other {more}}", &locale).unwrap_err(),
+            ParseError::NumericSelectKey("1".into())
+        );
+    }
+
+    #[test]
+    fn test_introspection() {
+        let locale = locale!("en");
+
+        let fmt = MessageFormat::new("Hello '{world}' #1", &locale);
+        assert!(fmt.is_static());
+        assert!(!fmt.has_plural());
+        assert!(!fmt.uses_pound());
+
+        let fmt = MessageFormat::new("Hello {NAME}", &locale);
+        assert!(!fmt.is_static());
+        assert!(!fmt.has_plural());
+
+        let fmt = MessageFormat::new(
+            "{G, select, female {{N, plural, one {her file} other {her '#'{N} files}}} other {#}}",
+            &locale,
+        );
+        assert!(!fmt.is_static());
+        assert!(fmt.has_plural());
+        assert!(!fmt.uses_pound());
+
+        let fmt = MessageFormat::new(
+            "{N, plural, one {a file} other {{G, select, female {her # files} other {# files}}}}",
+            &locale,
+        );
+        assert!(fmt.has_plural());
+        assert!(fmt.uses_pound());
+    }
+
+    #[test]
+    fn test_plural_categories_are_parsed_once() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, =0 {none} other {#}}", &locale);
+        assert!(matches!(fmt.parsed()[0], Block::Plural(_, false)));
+        assert_eq!(fmt.format_with_params([("N", 1.into())]), "1");
+        let fmt = MessageFormat::new("{N, selectordinal, one {#st} other {#th}}", &locale);
+        assert!(matches!(fmt.parsed()[0], Block::Ordinal(_, true)));
+    }
+
+    #[test]
+    fn test_branch_order() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, offset:1 =0 {a} other {b} one {c} few {d} =1 {e}}",
+            &locale,
+        );
+        let Block::Plural(branches, _) = &fmt.parsed()[0] else {
+            panic!("plural block expected");
+        };
+        let keys: Vec<_> = branches.keys().map(|key| key.to_string()).collect();
+        assert_eq!(
+            keys,
+            [
+                "argumentName",
+                "argumentOffset",
+                "0",
+                "other",
+                "one",
+                "few",
+                "1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_argument_handler() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, one {# file} other {# files}} since {D, duration, short}{-}",
+            &locale,
+        )
+        .with_unknown_argument_handler(|block| format!("[{}#]", block.trim()));
+        assert_eq!(
+            fmt.format_with_params([("N", 3.into())]),
+            "3 files since [D, duration, short#][-#]"
+        );
+
+        assert_eq!(
+            MessageFormat::parse_strict("{-}", &locale).unwrap_err(),
+            ParseError::UnknownBlock("-".into())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown block type for pattern -")]
+    fn test_unknown_block() {
+        let locale = locale!("en");
+        MessageFormat::new("{-}", &locale).format();
+    }
+
+    #[test]
+    fn test_escape() {
+        let locale = locale!("en");
+        assert_eq!(MessageFormat::escape("{x}"), "'{x}'");
+        assert_eq!(MessageFormat::escape("it's #1's"), "it''s '#1''s'");
+
+        let pattern = format!(
+            "{{N, plural, one {{{0} # file}} other {{{0} # files}}}}",
+            MessageFormat::escape("{DIR}'s #")
+        );
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(
+            fmt.format_with_params([("N", 2.into())]),
+            "{DIR}'s # 2 files"
+        );
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn prop_escape(text: String) -> quickcheck::TestResult {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(MessageFormat::escape(&text), &locale);
+        quickcheck::TestResult::from_bool(fmt.format() == text)
+    }
+
+    #[test]
+    fn test_message_builder() {
+        let locale = locale!("en");
+        let message = MessageBuilder::new()
+            .text("Hi ")
+            .arg("NAME")
+            .text(", you have ")
+            .plural("N", |p| {
+                p.exact(0, MessageBuilder::new().text("no {new} messages"))
+                    .branch("one", MessageBuilder::new().text("one new message"))
+                    .other(MessageBuilder::new().pound().text(" new messages #"))
+            })
+            .text(" from ")
+            .select("G", |s| {
+                s.branch("female", MessageBuilder::new().text("her"))
+                    .other(MessageBuilder::new().text("their"))
+            })
+            .text(" friends.");
+        assert_eq!(
+            message.to_pattern(),
+            "Hi {NAME}, you have {N, plural, =0 {no '{new} messages'} \
+            one {one new message} other {# new messages '#'}} \
+            from {G, select, female {her} other {their}} friends."
+        );
+
+        let fmt = message.build(&locale);
+        assert_eq!(
+            fmt.format_with_params([
+                ("NAME", "Ann".into()),
+                ("N", 1200.into()),
+                ("G", "female".into())
+            ]),
+            "Hi Ann, you have 1,200 new messages # from her friends."
+        );
+        assert_eq!(
+            fmt.format_with_params([("NAME", "Bob".into()), ("N", 0.into()), ("G", "x".into())]),
+            "Hi Bob, you have no {new} messages from their friends."
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing other branch in plural block N")]
+    fn test_message_builder_missing_other() {
+        MessageBuilder::new().plural("N", |p| p.branch("one", MessageBuilder::new()));
+    }
+
+    #[test]
+    fn test_message_param() {
+        let locale = locale!("de");
+        let fmt = MessageFormat::new(
+            "{N, plural, one {# Datei} other {# Dateien}} in {FOLDER}",
+            &locale,
+        );
+        let folder = ParamValue::message(
+            "{NAME} ({N, plural, one {# Eintrag} other {# Einträge}}, Rang '#'{RANK})",
+            [("NAME", "Projekte".into()), ("RANK", 1.into())],
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", 1200.into()), ("FOLDER", folder.clone())]),
+            "1.200 Dateien in Projekte (1.200 Einträge, Rang #1)"
+        );
+        assert_eq!(
+            folder.to_string(),
+            "{NAME} ({N, plural, one {# Eintrag} other {# Einträge}}, Rang '#'{RANK})"
+        );
+
+        // a message parameter does not see itself
+        let fmt = MessageFormat::new("{LINK}", &locale);
+        assert_eq!(
+            fmt.format_with_params([(
+                "LINK",
+                ParamValue::message("<{LINK}>", [] as [(&str, _); 0])
+            )]),
+            "<Undefined parameter - LINK>"
+        );
+    }
+
+    #[test]
+    fn test_format_branch() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{G, select, \
+              female {{N, plural, offset:1 =0 {nobody} one {her and # friend} other {her and # friends}}} \
+              other {{N, plural, =0 {nobody} one {them} other {them and # friends}}}}",
+            &locale,
+        );
+        assert_eq!(
+            fmt.format_branch("N", "one", [] as [(&str, _); 0])
+                .as_deref(),
+            Some("her and # friend")
+        );
+        assert_eq!(
+            fmt.format_branch("N", "other", [("N", 1001.into())])
+                .as_deref(),
+            Some("her and 1,000 friends")
+        );
+        assert_eq!(
+            fmt.format_branch("N", "=0", [] as [(&str, _); 0])
+                .as_deref(),
+            Some("nobody")
+        );
+        assert_eq!(
+            fmt.format_branch("G", "other", [("N", 3.into())])
+                .as_deref(),
+            Some("them and 3 friends")
+        );
+        assert_eq!(fmt.format_branch("N", "few", [] as [(&str, _); 0]), None);
+        assert_eq!(fmt.format_branch("X", "one", [] as [(&str, _); 0]), None);
+    }
+
+    #[test]
+    fn test_format_all_branches() {
+        let locale = locale!("ru");
+        let fmt = MessageFormat::new(
+            "{NAME} {G, select, female {добавила} other {добавил}} \
+            {N, plural, =0 {ничего} one {# файл} few {# файла} many {# файлов} other {# файла}}",
+            &locale,
+        );
+        let examples = fmt.format_all_branches([("NAME", "Саша".into())]);
+        let path = |g: &str, n: &str| {
+            vec![
+                ("G".to_owned(), g.to_owned()),
+                ("N".to_owned(), n.to_owned()),
+            ]
+        };
+        assert_eq!(
+            examples,
+            [
+                (path("female", "=0"), "Саша добавила ничего".to_owned()),
+                (path("female", "one"), "Саша добавила 1 файл".to_owned()),
+                (path("female", "few"), "Саша добавила 2 файла".to_owned()),
+                (path("female", "many"), "Саша добавила 0 файлов".to_owned()),
+                (
+                    path("female", "other"),
+                    "Саша добавила 0,0 файла".to_owned()
+                ),
+                (path("other", "=0"), "Саша добавил ничего".to_owned()),
+                (path("other", "one"), "Саша добавил 1 файл".to_owned()),
+                (path("other", "few"), "Саша добавил 2 файла".to_owned()),
+                (path("other", "many"), "Саша добавил 0 файлов".to_owned()),
+                (path("other", "other"), "Саша добавил 0,0 файла".to_owned()),
+            ]
+        );
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, offset:1 =0 {nobody} =1 {{HOST}} one {{HOST} and # guest} other {{HOST} and # guests}}",
+            &locale,
+        );
+        let examples: Vec<_> = fmt
+            .format_all_branches([("HOST", "Ann".into())])
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(
+            examples,
+            ["nobody", "Ann", "Ann and 1 guest", "Ann and 0 guests"]
+        );
+    }
+
+    #[test]
+    fn test_branch_observer() {
+        use std::sync::{Arc, Mutex};
+
+        let locale = locale!("en");
+        let taken = Arc::new(Mutex::new(Vec::new()));
+        let observer = {
+            let taken = taken.clone();
+            move |argument: &str, key: &str| {
+                taken.lock().unwrap().push(format!("{argument}:{key}"));
+            }
+        };
+        let fmt = MessageFormat::new(
+            "{G, select, female {{N, plural, =0 {none} one {one} other {#}}} other {{N, plural, other {#}}}}",
+            &locale,
+        )
+        .with_branch_observer(observer);
+
+        fmt.format_with_params([("G", "female".into()), ("N", 0.into())]);
+        fmt.format_with_params([("G", "female".into()), ("N", 1.into())]);
+        fmt.format_with_params([("G", "male".into()), ("N", 1.into())]);
+        fmt.format_with_params([("G", "male".into()), ("N", ParamValue::range(1, 2))]);
+        assert_eq!(
+            *taken.lock().unwrap(),
+            ["G:female", "N:=0", "G:female", "N:one", "G:other", "N:other", "G:other", "N:other"]
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        registry! {
+            static MESSAGES = {
+                "greeting" => "Hello {NAME}!",
+                "items" => "{N, plural, one {# item} other {# items}}",
+            };
+        }
+
+        let en = locale!("en");
+        let de = locale!("de");
+        assert_eq!(MESSAGES.keys().collect::<Vec<_>>(), ["greeting", "items"]);
+        assert_eq!(
+            MESSAGES.format("items", &en, [("N", 1.into())]),
+            Some("1 item".to_owned())
+        );
+        assert_eq!(MESSAGES.format("missing", &en, [("N", 1.into())]), None);
+
+        MESSAGES
+            .set_pattern("greeting", &de, "Hallo {NAME}!")
+            .unwrap();
+        assert_eq!(
+            MESSAGES.format("greeting", &de, [("NAME", "Welt".into())]),
+            Some("Hallo Welt!".to_owned())
+        );
+        assert_eq!(
+            MESSAGES.format("greeting", &en, [("NAME", "World".into())]),
+            Some("Hello World!".to_owned())
+        );
+        assert_eq!(
+            MESSAGES.pattern("greeting", &de),
+            Some("Hallo {NAME}!".to_owned())
+        );
+
+        let metadata = Metadata {
+            description: Some("Greeting on the start page".to_owned()),
+            ..Metadata::default()
+        };
+        MESSAGES.set_metadata("greeting", metadata.clone());
+        assert_eq!(MESSAGES.metadata("greeting"), Some(metadata));
+        assert_eq!(MESSAGES.metadata("items"), None);
+    }
+
+    #[test]
+    fn test_registry_static_messages() {
+        registry! {
+            static MESSAGES = {
+                "cancel" => "Cancel",
+                "greeting" => "Hello {NAME}!",
+            };
+        }
+
+        let de = locale!("de");
+        MESSAGES.set_pattern("cancel", &de, "Abbrechen").unwrap();
+        let text = MESSAGES
+            .format_shared("cancel", &de, [] as [(&str, ParamValue); 0])
+            .unwrap();
+        assert_eq!(&*text, "Abbrechen");
+        // the text formatted when the pattern was set is shared
+        let again = MESSAGES
+            .format_shared("cancel", &de, [] as [(&str, ParamValue); 0])
+            .unwrap();
+        assert!(Arc::ptr_eq(&text, &again));
+
+        MESSAGES.set_select_alias("m", "male");
+        let again = MESSAGES
+            .format_shared("cancel", &de, [] as [(&str, ParamValue); 0])
+            .unwrap();
+        assert!(Arc::ptr_eq(&text, &again));
+        assert_eq!(
+            MESSAGES.format("cancel", &de, [] as [(&str, ParamValue); 0]),
+            Some("Abbrechen".to_owned())
+        );
+        assert_eq!(
+            MESSAGES
+                .format_shared("greeting", &de, [("NAME", "Welt".into())])
+                .as_deref(),
+            Some("Hello Welt!")
+        );
+    }
+
+    #[cfg(any(feature = "axum", feature = "actix-web"))]
+    #[test]
+    fn test_localization() {
+        registry! {
+            static MESSAGES = {
+                "cart.empty" => "Your cart is empty",
+            };
+        }
+
+        let localization = Localization::new(&MESSAGES, [locale!("en"), locale!("de")]);
+        MESSAGES
+            .set_pattern("cart.empty", &locale!("de"), "Ihr Warenkorb ist leer")
+            .unwrap();
+
+        let messages = localization.messages(Some("fr;q=0.9, de-CH;q=0.8, en;q=0.5"));
+        assert_eq!(messages.locale(), &locale!("de"));
+        assert_eq!(
+            messages.format("cart.empty", [("N", 0.into())]),
+            "Ihr Warenkorb ist leer"
+        );
+        assert_eq!(messages.format("cart.full", [("N", 0.into())]), "cart.full");
+        assert_eq!(
+            localization.messages(Some("de;q=0, en")).locale(),
+            &locale!("en")
+        );
+        assert_eq!(localization.messages(None).locale(), &locale!("en"));
+    }
+
+    #[test]
+    fn test_format_to_parts() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "'{'{NAME}'}' has {N, plural, one {# <item>} other {# items}} for {PRICE, number}",
+            &locale,
+        );
+        let params = || {
+            [
+                ("NAME", "Tom & Jerry".into()),
+                ("N", 2.into()),
+                ("PRICE", 1234.into()),
+            ]
+        };
+        assert_eq!(
+            fmt.format_to_parts(params()),
+            [
+                MessagePart::Literal("{".to_owned()),
+                MessagePart::Argument {
+                    name: "NAME".to_owned(),
+                    value: "Tom & Jerry".to_owned(),
+                    kind: ParamKind::Text,
+                },
+                MessagePart::Literal("} has ".to_owned()),
+                MessagePart::Argument {
+                    name: "N".to_owned(),
+                    value: "2".to_owned(),
+                    kind: ParamKind::Number,
+                },
+                MessagePart::Literal(" items for ".to_owned()),
+                MessagePart::Argument {
+                    name: "PRICE".to_owned(),
+                    value: "1,234".to_owned(),
+                    kind: ParamKind::Number,
+                },
+            ]
+        );
+        assert_eq!(
+            fmt.render(params(), PlainText::default()),
+            fmt.format_with_params(params())
+        );
+        assert_eq!(
+            fmt.render([("NAME", "<b>".into()), ("N", 1.into()), ("PRICE", 1.into())], Html::default()),
+            "{<span data-arg=\"NAME\">&lt;b&gt;</span>} has <span data-arg=\"N\">1</span> &lt;item&gt; for <span data-arg=\"PRICE\">1</span>"
+        );
+
+        let kinds = |pattern: &str, params: Vec<(&str, ParamValue)>| {
+            MessageFormat::new(pattern, &locale)
+                .format_to_parts(params)
+                .into_iter()
+                .filter_map(|part| match part {
+                    MessagePart::Argument { kind, .. } => Some(kind),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let date = icu::calendar::Date::try_new_iso_date(2025, 1, 3).unwrap();
+        assert_eq!(
+            kinds(
+                "{N, plural, other {# days}} {N} {D, date} {D}",
+                vec![("N", ParamValue::range(1, 3)), ("D", date.into())]
+            ),
+            [
+                ParamKind::Range,
+                ParamKind::Range,
+                ParamKind::Date,
+                ParamKind::Date
+            ]
+        );
+    }
+
+    #[cfg(feature = "egui")]
+    #[test]
+    fn test_layout_job_renderer() {
+        use ::egui::{Color32, TextFormat};
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{NAME} has {N, plural, other {# items}}", &locale);
+        let renderer = LayoutJobRenderer::new(TextFormat::default(), |name, format| TextFormat {
+            color: if name == "N" {
+                Color32::RED
+            } else {
+                Color32::BLUE
+            },
+            ..format.clone()
+        });
+        let job = fmt.render([("NAME", "Anna".into()), ("N", 3.into())], renderer);
+        assert_eq!(job.text, "Anna has 3 items");
+        let sections: Vec<_> = job
+            .sections
+            .iter()
+            .map(|section| (&job.text[section.byte_range.clone()], section.format.color))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                ("Anna", Color32::BLUE),
+                (" has ", TextFormat::default().color),
+                ("3", Color32::RED),
+                (" items", TextFormat::default().color),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ansi_text() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{FILE}: {N, plural, one {# error} other {# errors}}",
+            &locale,
+        );
+        let style = |name: &str| (name == "N").then_some("1;31");
+        let params = || [("FILE", "lib.rs".into()), ("N", 2.into())];
+        assert_eq!(
+            fmt.render(params(), AnsiText::new(style)),
+            "lib.rs: \x1b[1;31m2\x1b[0m errors"
+        );
+        assert_eq!(
+            fmt.render(params(), AnsiText::new(style).colored(false)),
+            "lib.rs: 2 errors"
+        );
+    }
+
+    #[test]
+    fn test_arguments() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{NAME} {G, select, female {{N, plural, offset:1 other {# {NAME}}}} other {{TYPE, number}}}",
+            &locale,
+        );
+        assert_eq!(fmt.arguments(), ["NAME", "G", "N", "TYPE"]);
+    }
+
+    #[test]
+    fn test_generate_module() {
+        let module = build::generate_module([
+            ("cart.empty", "Your cart is empty"),
+            (
+                "cart.items",
+                "{N, plural, one {# item} other {# items}} of {TYPE}",
+            ),
+        ]);
+        assert_eq!(
+            module,
+            r#"// Generated by message_format::build::generate_module, do not edit.
+
+use message_format::ParamValue;
+
+/// `Your cart is empty`
+pub const CART_EMPTY: &str = "cart.empty";
+
+pub fn cart_empty() -> (&'static str, [(&'static str, ParamValue); 0]) {
+    (CART_EMPTY, [])
+}
+
+/// `{N, plural, one {# item} other {# items}} of {TYPE}`
+pub const CART_ITEMS: &str = "cart.items";
+
+pub fn cart_items(n: impl Into<ParamValue>, type_: impl Into<ParamValue>) -> (&'static str, [(&'static str, ParamValue); 2]) {
+    (CART_ITEMS, [("N", n.into()), ("TYPE", type_.into())])
+}
+"#
+        );
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    #[should_panic(expected = "2 invalid messages")]
+    fn test_validate_dir() {
+        let dir = std::env::temp_dir().join("message-format-test-validate-dir");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.arb"),
+            r#"{"greeting": "Hello {NAME}", "@greeting": {}, "items": "{N, plural, one {# item} other {# items}}"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("de.json"),
+            r#"{"greeting": "Hallo {NAME, duration}", "items": "{N, plural, eins {# Artikel} other {# Artikel}}"}"#,
+        )
+        .unwrap();
+        build::validate_dir(&dir);
+    }
+
+    #[test]
+    fn test_registry_audit() {
+        registry! {
+            static MESSAGES = {
+                "greeting" => "Hello {NAME}!",
+                "items" => "{N, plural, one {# item} other {# items}}",
+            };
+        }
+
+        let de = locale!("de");
+        let fr = locale!("fr");
+        MESSAGES
+            .set_pattern("greeting", &de, "Hallo {NAME}!")
+            .unwrap();
+        MESSAGES
+            .set_pattern(
+                "items",
+                &de,
+                "{N, plural, one {ein Artikel} other {{COUNT} Artikel}}",
+            )
+            .unwrap();
+        MESSAGES
+            .set_pattern("greeting", &fr, "Bonjour {NAME} !")
+            .unwrap();
+        MESSAGES.set_pattern("farewell", &fr, "Au revoir").unwrap();
+
+        let issues = MESSAGES.audit();
+        assert_eq!(
+            issues,
+            [
+                AuditIssue::ArgumentMismatch {
+                    key: "items".to_owned(),
+                    locale: de.clone(),
+                    missing: Vec::new(),
+                    unknown: vec!["COUNT".to_owned()],
+                },
+                AuditIssue::MissingTranslation {
+                    key: "items".to_owned(),
+                    locale: fr.clone(),
+                },
+                AuditIssue::MissingTranslation {
+                    key: "farewell".to_owned(),
+                    locale: de.clone(),
+                },
+            ]
+        );
+        assert_eq!(issues[1].to_string(), "fr: items: missing translation");
+
+        MESSAGES
+            .set_pattern(
+                "items",
+                &de,
+                "{N, plural, one {ein Artikel} other {# Artikel}}",
+            )
+            .unwrap();
+        MESSAGES
+            .set_pattern(
+                "items",
+                &fr,
+                "{N, plural, one {# article} other {# articles}}",
+            )
+            .unwrap();
+        // without a default pattern, the first locale is the reference
+        MESSAGES
+            .set_pattern("farewell", &de, "Tschüss {NAME}")
+            .unwrap();
+        assert_eq!(
+            MESSAGES
+                .audit()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["fr: farewell: missing arguments [NAME], unknown arguments []"]
+        );
+    }
+
+    #[test]
+    fn test_registry_fallback() {
+        registry! {
+            static MESSAGES = {
+                "cart.empty" => "Your cart is empty",
+                "cart.items" => "{N, plural, one {# item} other {# items}}",
+            };
+        }
+
+        let en = locale!("en");
+        let de = locale!("de");
+        MESSAGES.set_source_locale(&en);
+        MESSAGES
+            .set_pattern("cart.empty", &de, "Ihr Warenkorb ist leer")
+            .unwrap();
+        let format = |key, locale| MESSAGES.format(key, locale, [("N", 2.into())]);
+
+        assert_eq!(format("cart.items", &de), Some("2 items".to_owned()));
+        assert_eq!(
+            format("cart.empty", &en),
+            Some("Your cart is empty".to_owned())
+        );
+        MESSAGES.set_fallback(Fallback::Key);
+        assert_eq!(format("cart.items", &de), Some("cart.items".to_owned()));
+        assert_eq!(format("cart.items", &en), Some("2 items".to_owned()));
+        assert_eq!(
+            format("cart.empty", &de),
+            Some("Ihr Warenkorb ist leer".to_owned())
+        );
+        MESSAGES.set_fallback(Fallback::Error);
+        assert_eq!(format("cart.items", &de), None);
+        assert_eq!(
+            MESSAGES.format_with_fallback("cart.items", &de, [("N", 2.into())], Fallback::Pseudo),
+            Some("[2 íţéɱš]".to_owned())
+        );
+        assert_eq!(
+            MESSAGES.format_with_fallback(
+                "cart.empty",
+                &locale!("fr"),
+                [("N", 2.into())],
+                Fallback::Pseudo
+            ),
+            Some("[Ýóúŕ çáŕţ íš éɱƥţý]".to_owned())
+        );
+        assert_eq!(format("cart.full", &en), None);
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn test_read_locale_file() {
+        let dir = std::env::temp_dir().join("message-format-test-read-locale-file");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("en.arb");
+        std::fs::write(
+            &path,
+            r#"{
+                "order": "Order",
+                "@order": {"description": "Button to place the order", "meaning": "verb"},
+                "title": "Cart"
+            }"#,
+        )
+        .unwrap();
+
+        let entries = build::read_locale_file(&path).unwrap();
+        assert_eq!(
+            entries,
+            [
+                build::Entry {
+                    key: "order".to_owned(),
+                    pattern: "Order".to_owned(),
+                    metadata: Metadata {
+                        description: Some("Button to place the order".to_owned()),
+                        meaning: Some("verb".to_owned()),
+                        context: None,
+                        examples: Vec::new(),
+                    },
+                },
+                build::Entry {
+                    key: "title".to_owned(),
+                    pattern: "Cart".to_owned(),
+                    metadata: Metadata::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_previews() {
+        registry! {
+            static MESSAGES = {
+                "invite" => "{WHO} invited {N, plural, one {# guest} other {# guests}} to {PARTY}",
+            };
+        }
+
+        let en = locale!("en");
+        MESSAGES.set_metadata(
+            "invite",
+            Metadata {
+                examples: vec![
+                    ("WHO".to_owned(), "Alice".to_owned()),
+                    ("N".to_owned(), "3".to_owned()),
+                ],
+                ..Metadata::default()
+            },
+        );
+        let previews: Vec<_> = MESSAGES
+            .previews("invite", &en)
+            .into_iter()
+            .map(|(_, preview)| preview)
+            .collect();
+        assert_eq!(
+            previews,
+            [
+                "Alice invited 1 guest to {PARTY}",
+                "Alice invited 0 guests to {PARTY}"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_block() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("Hello {NAME}{}! { } {N, plural, other {# {}}}", &locale);
+        assert_eq!(
+            fmt.format_with_params([("NAME", "World".into()), ("N", 2.into())]),
+            "Hello World{}! { } 2 {}"
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("Hello {}", &locale).unwrap_err(),
+            ParseError::EmptyBlock
+        );
+    }
+
+    #[test]
+    fn test_offset_spacing() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, offset : 1 =0 {nobody} one {you and # other} other {you and # others}} \
+             {PLACE, selectordinal,offset: 1 one {#st} two {#nd} few {#rd} other {#th}}",
+            &locale,
+        )
+        .with_compatibility(Compatibility::Closure);
+        assert_eq!(
+            fmt.format_with_params([("N", 2.into()), ("PLACE", 3.into())]),
+            "you and 1 other 2nd"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid offset offset,1")]
+    fn test_invalid_offset() {
+        let locale = locale!("en");
+        MessageFormat::new("{N, plural, offset,1 other {#}}", &locale)
+            .format_with_params([("N", 1.into())]);
+    }
+
+    #[test]
+    fn test_invalid_offset_strict() {
+        let locale = locale!("en");
+        assert_eq!(
+            MessageFormat::parse_strict("{N, selectordinal, offset: x other {#}}", &locale)
+                .unwrap_err(),
+            ParseError::InvalidOffset("offset: x".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_format_cow() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("Cancel", &locale);
+        assert_eq!(fmt.as_static_text(), Some("Cancel"));
+        assert!(matches!(
+            fmt.format_cow([("N", 1.into())]),
+            Cow::Borrowed("Cancel")
+        ));
+
+        let fmt = MessageFormat::new("Don''t cancel", &locale);
+        assert_eq!(fmt.as_static_text(), None);
+        assert!(
+            matches!(fmt.format_cow([("N", 1.into())]), Cow::Owned(text) if text == "Don't cancel")
+        );
+
+        let fmt = MessageFormat::new("{N} items", &locale);
+        assert_eq!(fmt.as_static_text(), None);
+        assert_eq!(fmt.format_cow([("N", 1.into())]), "1 items");
+    }
+
+    #[test]
+    fn test_format_with_context() {
+        let en = locale!("en");
+        let context = RenderContext::new(locale!("de"));
+        let items = MessageFormat::new(
+            "{N, plural, one {# Artikel} other {# Artikel}} für {TOTAL, number, ::.00}",
+            &en,
+        );
+        assert_eq!(
+            items.format_with_context(&context, [("N", 1000.into()), ("TOTAL", 12.5.into())]),
+            "1.000 Artikel für 12,50"
+        );
+        let place = MessageFormat::new("{N, selectordinal, other {#.}} Platz", &en)
+            .with_compatibility(Compatibility::Icu);
+        assert_eq!(
+            place.format_with_context(&context, [("N", 2.into())]),
+            "2. Platz"
+        );
+    }
+
+    #[test]
+    fn test_shared_render_context() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RenderContext>();
+
+        let en = locale!("en");
+        let context = RenderContext::new(locale!("en"));
+        std::thread::scope(|scope| {
+            for n in [1, 2] {
+                let context = &context;
+                let en = &en;
+                scope.spawn(move || {
+                    let fmt = MessageFormat::new("{N, plural, one {# file} other {# files}}", en);
+                    let expected = if n == 1 { "1 file" } else { "2 files" };
+                    assert_eq!(
+                        fmt.format_with_context(context, [("N", n.into())]),
+                        expected
+                    );
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_stray_pound() {
+        use std::sync::{Arc, Mutex};
+
+        let locale = locale!("en");
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let handler_reported = reported.clone();
+        let fmt =
+            MessageFormat::new("Order #{ID}", &locale).with_stray_pound_handler(move |message| {
+                handler_reported.lock().unwrap().push(message.to_owned())
+            });
+        assert_eq!(fmt.format_with_params([("ID", "A7".into())]), "Order #A7");
+        assert_eq!(*reported.lock().unwrap(), ["Order #A7"]);
+
+        assert_eq!(
+            MessageFormat::parse_strict("Order #{ID}", &locale).unwrap_err(),
+            ParseError::StrayPound
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{G, select, other {Order #}}", &locale).unwrap_err(),
+            ParseError::StrayPound
+        );
+        assert!(MessageFormat::parse_strict(
+            "Order '#'{ID}, {N, plural, other {{G, select, other {# items}}}}",
+            &locale
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_format_with_map() {
+        let locale = locale!("en");
+        let params = HashMap::from([
+            ("N".to_owned(), 2.into()),
+            ("WHO".to_owned(), ParamValue::from(None::<i64>)),
+        ]);
+        let fmt = MessageFormat::new("{N, plural, one {# file} other {# files}}", &locale);
+        assert_eq!(fmt.format_with_map(&params), "2 files");
+        let fmt = MessageFormat::new("[{WHO}] {N}", &locale).with_none(NoneParam::Undefined);
+        assert_eq!(
+            fmt.format_with_map(&params),
+            "[Undefined parameter - WHO] 2"
+        );
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_format_with_positional_params() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{0} of {1, plural, one {# file} other {# files}} copied",
+            &locale,
+        );
+        assert_eq!(
+            fmt.format_with_positional_params(["3".into(), 10.into()]),
+            "3 of 10 files copied"
+        );
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let locale = locale!("en");
+        set_parse_cache_capacity(10);
+        for _ in 0..2 {
+            let fmt = MessageFormat::new("{N, number}", &locale);
+            assert_eq!(fmt.format_with_params([("N", 1000.into())]), "1,000");
+            let fmt =
+                MessageFormat::new("{N, number}", &locale).with_compatibility(Compatibility::Dart);
+            assert_eq!(
+                fmt.format_with_params([("N", 1000.into())]),
+                "Undefined parameter - N, number"
+            );
+        }
+        assert!(cache::get("{N, number}", Compatibility::Native).is_some());
+        set_parse_cache_capacity(0);
+        assert!(cache::get("{N, number}", Compatibility::Native).is_none());
+    }
+
+    #[test]
+    fn test_select_aliases() {
+        let locale = locale!("en");
+        let pattern = "{G, select, female {She} male {He} other {They}} replied";
+        let fmt = MessageFormat::new(pattern, &locale)
+            .with_select_alias("f", "female")
+            .with_select_alias("w", "f")
+            .with_select_alias("x", "y")
+            .with_select_alias("y", "x");
+        assert_eq!(fmt.format_with_params([("G", "w".into())]), "She replied");
+        assert_eq!(fmt.format_with_params([("G", "x".into())]), "They replied");
+
+        registry! {
+            static MESSAGES = {
+                "reply" => "{G, select, female {She} male {He} other {They}} replied",
+            };
+        }
+        MESSAGES.set_select_alias("m", "male");
+        assert_eq!(
+            MESSAGES.format("reply", &locale, [("G", "m".into())]),
+            Some("He replied".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_currency() {
+        let pattern = "{P, number, currency}";
+        let params = || [("P", 1234.5.into())];
+        let en_us = locale!("en-US");
+        assert_eq!(
+            MessageFormat::new(pattern, &en_us).format_with_params(params()),
+            "$1,234.50"
+        );
+        let de_at = locale!("de-AT");
+        assert_eq!(
+            MessageFormat::new(pattern, &de_at).format_with_params(params()),
+            "€\u{a0}1\u{a0}234,50"
+        );
+        let ja_jp = locale!("ja-JP");
+        assert_eq!(
+            MessageFormat::new(pattern, &ja_jp).format_with_params(params()),
+            "￥1,234"
+        );
+        let en = locale!("en");
+        assert_eq!(
+            MessageFormat::new(pattern, &en).format_with_params(params()),
+            "¤1,234.50"
+        );
+
+        let context = RenderContext::new(locale!("en-US")).with_default_currency("CHF");
+        assert_eq!(
+            MessageFormat::new(pattern, &en).format_with_context(&context, params()),
+            "CHF\u{a0}1,234.50"
+        );
+        let fmt = MessageFormat::new("{P, number, ::currency/JPY}", &en_us);
+        assert_eq!(fmt.format_with_params(params()), "¥1,234");
+        let fmt = MessageFormat::new("{P, number, ::currency/KWD}", &en_us);
+        assert_eq!(fmt.format_with_params(params()), "KWD\u{a0}1,234.500");
+        let en_gp = locale!("en-GP");
+        assert_eq!(
+            MessageFormat::new(pattern, &en_gp).format_with_params(params()),
+            "€1,234.50"
+        );
+        let bg_bg = locale!("bg-BG");
+        assert_eq!(
+            MessageFormat::new(pattern, &bg_bg).format_with_params(params()),
+            "1234,50\u{a0}€"
+        );
+
+        // the sign goes before the currency pattern
+        let negative = || [("P", (-5).into())];
+        assert_eq!(
+            MessageFormat::new(pattern, &en_us).format_with_params(negative()),
+            "-$5.00"
+        );
+        assert_eq!(
+            MessageFormat::new(pattern, &locale!("de-DE")).format_with_params(negative()),
+            "-5,00\u{a0}€"
+        );
+    }
+
+    #[test]
+    fn test_percent() {
+        let pattern = "{P, number, percent}";
+        let en = locale!("en");
+        let fmt = MessageFormat::new(pattern, &en);
+        assert_eq!(fmt.format_with_params([("P", 0.256.into())]), "26%");
+        assert_eq!(fmt.format_with_params([("P", 12.into())]), "1,200%");
+        let n = ParamValue::number(0.2567).max_fraction_digits(1);
+        assert_eq!(fmt.format_with_params([("P", n)]), "25.7%");
+        let de = locale!("de");
+        assert_eq!(
+            MessageFormat::new(pattern, &de).format_with_params([("P", 0.5.into())]),
+            "50\u{a0}%"
+        );
+        let fmt = MessageFormat::new("{P, number, ::%x100 .0}", &en);
+        assert_eq!(fmt.format_with_params([("P", 0.5.into())]), "50.0%");
+        // the pattern is loaded from the locale data, the sign goes before it
+        let format = |locale: &Locale, p: f64| {
+            MessageFormat::new(pattern, locale).format_with_params([("P", p.into())])
+        };
+        assert_eq!(format(&locale!("ar"), 0.5), "50\u{200e}%\u{200e}");
+        assert_eq!(format(&locale!("ar-SA"), 0.5), "\u{665}\u{660}\u{66a}");
+        assert_eq!(format(&locale!("tr"), -0.5), "-%50");
+        assert_eq!(format(&locale!("eu"), 0.5), "%\u{a0}50");
+
+        // unknown styles are formatted as plain numbers
+        let fmt = MessageFormat::new("{P, number, permille}", &en);
+        assert_eq!(fmt.format_with_params([("P", 0.5.into())]), "0.5");
+        assert_eq!(
+            MessageFormat::try_new("{P, number, permille}", &en).unwrap_err(),
+            ParseError::UnknownNumberStyle("permille".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_plural_offset_is_subtracted_exactly() {
+        use fixed_decimal::FixedDecimal;
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, offset:1 one {# other} other {# others}}",
+            &locale,
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", 9007199254740993i64.into())]),
+            "9,007,199,254,740,992 others"
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", i64::MIN.into())]),
+            "-9,223,372,036,854,775,809 others"
+        );
+        let n: FixedDecimal = "1000000000000000000001".parse().unwrap();
+        assert_eq!(
+            fmt.format_with_params([("N", n.into())]),
+            "1,000,000,000,000,000,000,000 others"
+        );
+        // visible fraction digits are kept
+        let n: FixedDecimal = "3.50".parse().unwrap();
+        assert_eq!(fmt.format_with_params([("N", n.into())]), "2.50 others");
+    }
+
+    #[test]
+    fn test_negative_and_decimal_offsets() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, plural, offset:-1 =0 {none} =1 {# one exact} one {# one} other {# other}}",
+            &locale,
+        );
+        // exact matches compare the value before the offset is subtracted
+        assert_eq!(fmt.format_with_params([("N", 0.into())]), "none");
+        assert_eq!(fmt.format_with_params([("N", 1.into())]), "2 one exact");
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "3 other");
+        assert_eq!(fmt.format_with_params([("N", (-2).into())]), "-1 one");
+
+        let fmt = MessageFormat::new(
+            "{N, selectordinal, offset:0.5 one {#st} other {#th}}",
+            &locale,
+        );
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "1.5th");
+        assert_eq!(fmt.format_with_params([("N", 1.5.into())]), "1.0st");
+
+        assert!(
+            MessageFormat::parse_strict("{N, plural, offset:-0.25 other {#}}", &locale).is_ok()
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{N, plural, offset:.5 other {#}}", &locale).unwrap_err(),
+            ParseError::InvalidOffset("offset:.5".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_clone_params_for_several_locales() {
+        let name: std::sync::Arc<str> = "Ana".into();
+        let params = vec![
+            ("NAME", ParamValue::from(name)),
+            ("N", 1234.into()),
+            (
+                "GREETING",
+                ParamValue::message("Hi {NAME}", [("X", "x".into())]),
+            ),
+        ];
+        let pattern = "{GREETING}: {NAME} has {N} points";
+        let en = locale!("en");
+        let de = locale!("de");
+        assert_eq!(
+            MessageFormat::new(pattern, &en).format_with_params(params.clone()),
+            "Hi Ana: Ana has 1,234 points"
+        );
+        assert_eq!(
+            MessageFormat::new(pattern, &de).format_with_params(params),
+            "Hi Ana: Ana has 1.234 points"
+        );
+    }
+
+    #[test]
+    fn test_param_value_order() {
+        use std::{cmp::Ordering, collections::BTreeMap};
+
+        use fixed_decimal::FixedDecimal;
+
+        let decimal = |s: &str| ParamValue::from(s.parse::<FixedDecimal>().unwrap());
+        assert_eq!(ParamValue::from(1).cmp(&1.0.into()), Ordering::Equal);
+        assert_eq!(decimal("1.50").cmp(&1.5.into()), Ordering::Equal);
+        assert_eq!(decimal("-0.0").cmp(&0.into()), Ordering::Equal);
+        assert_eq!(decimal("-0.0"), ParamValue::from(0));
+        assert!(ParamValue::from(9007199254740993i64) > 9007199254740992.0.into());
+        assert!(decimal("-2.5") < (-2).into());
+        assert!(ParamValue::from(f64::NAN) > f64::INFINITY.into());
+
+        let mut values = vec![
+            ParamValue::from("b"),
+            ParamValue::from(10),
+            ParamValue::from(None::<i64>),
+            ParamValue::from("a"),
+            ParamValue::from(2.5),
+            decimal("-1"),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            [
+                ParamValue::from(None::<i64>),
+                decimal("-1"),
+                2.5.into(),
+                10.into(),
+                "a".into(),
+                "b".into(),
+            ]
+        );
+
+        let mut params = BTreeMap::new();
+        params.insert(ParamValue::from(1), "one");
+        params.insert(ParamValue::from(1.0), "one again");
+        params.insert(ParamValue::from(0.5), "half");
+        assert_eq!(params.values().collect::<Vec<_>>(), [&"half", &"one again"]);
+    }
+
+    #[test]
+    fn test_source_after_formatting() {
+        let locale = locale!("en");
+        let pattern = "Don''t {N, plural, one {# file} other {# files}}";
+        let fmt = MessageFormat::new(pattern, &locale);
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "Don't 2 files");
+        assert_eq!(fmt.source(), pattern);
+        assert_eq!(fmt.to_string(), pattern);
+        assert_eq!(format!("{fmt}"), pattern);
+    }
+
+    #[test]
+    fn test_debug_tree() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "'{'{NAME}'}' {GENDER, select, \
+               female {{N, plural, offset:1 =0 {only her} other {her and # others}}} \
+               other {{N, number, ::.00}}} at {P, number}",
+            &locale,
+        );
+        assert_eq!(
+            fmt.debug_tree(),
+            r#"text "{"
+argument NAME
+text "} "
+select GENDER
+  female
+    plural N offset:1
+      =0
+        text "only her"
+      other
+        text "her and # others"
+  other
+    number N ::.00
+text " at "
+number P
+"#
+        );
+    }
+
+    #[test]
+    fn test_extract_parts() {
+        let pattern = "Don''t '{quote}' {NAME}\nO'Neil {x}: {N, plural, one {'#' #} other {''#''}}";
+        let parts = extract_parts(pattern).unwrap();
+        let summary: Vec<_> = parts
+            .iter()
+            .map(|part| (part.kind, &pattern[part.span.clone()], part.text.as_ref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (PatternPartKind::Text, "Don''t '{quote}' ", "Don't {quote} "),
+                (PatternPartKind::Block, "NAME", "NAME"),
+                (PatternPartKind::Text, "\nO'Neil ", "\nO'Neil "),
+                (PatternPartKind::Block, "x", "x"),
+                (PatternPartKind::Text, ": ", ": "),
+                (
+                    PatternPartKind::Block,
+                    "N, plural, one {'#' #} other {''#''}",
+                    "N, plural, one {'#' #} other {''#''}"
+                ),
+            ]
+        );
+        let nested = extract_parts(&parts[5].text).unwrap();
+        assert_eq!(nested[1].text, "'#' #");
+        assert_eq!(extract_parts(&nested[1].text).unwrap()[0].text, "# #");
+
+        assert_eq!(
+            extract_parts("a}b").unwrap_err(),
+            ParseError::UnbalancedBraces(1)
+        );
+        assert_eq!(
+            extract_parts("{A} {B").unwrap_err(),
+            ParseError::UnbalancedBraces(4)
+        );
+        assert_eq!(extract_parts("'{' {A} '}'").unwrap()[1].span, 5..6);
+    }
+
+    #[test]
+    fn test_param_source() {
+        let locale = locale!("en");
+        let source = ParamSource::from_vars([
+            ("APP_NAME", "Ana"),
+            ("APP_COUNT", "1"),
+            ("APP_ZIP", "01234"),
+            ("APP_PRICE", "1.50"),
+            ("COUNT", "5"),
+        ])
+        .with_prefix("APP_")
+        .with_case(NameCase::Upper);
+        let fmt = MessageFormat::new(
+            "{name}: {count, plural, =1 {one order} other {# orders}} for {price}",
+            &locale,
+        );
+        assert_eq!(source.format(&fmt), "Ana: one order for 1.50");
+        assert_eq!(source.get("missing"), None);
+        assert_eq!(source.get("zip"), Some("01234".into()));
+        assert_eq!(source.get("count"), Some(1.into()));
+        assert_eq!(source.get("COUNT"), Some(1.into()));
+        assert_eq!(ParamSource::from_vars([("COUNT", "5")]).get("count"), None);
+    }
+
+    #[test]
+    fn test_format_structured() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new(
+            "{NAME} has {N, plural, one {# file} other {# files}} in {DIR}",
+            &locale,
+        );
+        let message = fmt.format_structured([
+            ("N", 1234.into()),
+            ("NAME", "Ana".into()),
+            ("UNUSED", "x".into()),
+        ]);
+        assert_eq!(
+            message.to_string(),
+            "Ana has 1,234 files in Undefined parameter - DIR"
+        );
+        assert_eq!(
+            message.fields,
+            [
+                ("NAME".to_owned(), "Ana".into()),
+                ("N".to_owned(), 1234.into())
+            ]
+        );
+        assert_eq!(message.get("N"), Some(&1234.into()));
+        assert_eq!(message.get("DIR"), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_structured_tracing_fields() {
+        use std::{
+            fmt::Debug,
+            sync::{Arc, Mutex},
+        };
+
+        use tracing::{
+            field::{Field, Visit},
+            span, subscriber, Event, Metadata, Subscriber,
+        };
+
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Recorder {
+            fn record_i64(&mut self, field: &Field, value: i64) {
+                self.0.lock().unwrap().push(format!("{field}: i64 {value}"));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.lock().unwrap().push(format!("{field}: str {value}"));
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0.lock().unwrap().push(format!("{field}: {value:?}"));
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Recorder(self.0.clone()));
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{NAME} has {N} files from {D}", &locale);
+        let message = fmt.format_structured([
+            ("N", 1234.into()),
+            ("NAME", "Ana".into()),
+            ("D", ParamValue::range(1, 3)),
+        ]);
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        subscriber::with_default(Recorder(recorded.clone()), || {
+            tracing::info!(
+                name = message.field("NAME"),
+                count = message.field("N"),
+                range = message.field("D"),
+                missing = message.field("MISSING"),
+                "{message}"
+            );
+        });
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            [
+                "message: Ana has 1,234 files from 1–3",
+                "name: str Ana",
+                "count: i64 1234",
+                "range: 1–3",
+            ]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_load_locale() {
+        struct Cdn;
+
+        impl ResourceFetcher for Cdn {
+            async fn fetch(&self, locale: &Locale) -> Result<String, String> {
+                match locale.to_string().as_str() {
+                    "fr" => Ok(r#"{"greeting": "Bonjour {NAME}", "title": "Panier {"}"#.to_owned()),
+                    _ => Err(format!("no translations for {locale}")),
+                }
+            }
+        }
+
+        registry! {
+            static MESSAGES = {
+                "greeting" => "Hello {NAME}",
+                "title" => "Cart",
+            };
+        }
+        let (de, fr) = (locale!("de"), locale!("fr"));
+        let loaded = {
+            let fr = fr.clone();
+            tokio::spawn(async move { MESSAGES.load_locale(&Cdn, &fr).await })
+        };
+        assert_eq!(
+            loaded.await.unwrap(),
+            Ok(vec![("title".to_owned(), ParseError::UnclosedBrace)])
+        );
+        assert_eq!(
+            MESSAGES.load_locale(&Cdn, &locale!("it")).await,
+            Err("no translations for it".to_owned())
+        );
+        assert_eq!(
+            MESSAGES.format("greeting", &fr, [("NAME", "Ana".into())]),
+            Some("Bonjour Ana".to_owned())
+        );
+        assert_eq!(
+            MESSAGES.format("title", &fr, [("N", 1.into())]),
+            Some("Cart".to_owned())
+        );
+
+        let dir = std::env::temp_dir().join("message-format-test-load-locale");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("de.arb"), r#"{"title": "Warenkorb"}"#).unwrap();
+        let files = FileFetcher::new(&dir);
+        assert_eq!(MESSAGES.load_locale(&files, &de).await, Ok(vec![]));
+        assert_eq!(
+            MESSAGES.format("title", &de, [("N", 1.into())]),
+            Some("Warenkorb".to_owned())
+        );
+        assert!(MESSAGES.load_locale(&files, &fr).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let parse = |locale: Locale, input: &str| {
+            RenderContext::new(locale)
+                .parse_number(input)
+                .map(|n| n.to_string())
+        };
+        assert_eq!(parse(locale!("en"), "1,234.50"), Some("1234.50".to_owned()));
+        assert_eq!(parse(locale!("en"), " -1234 "), Some("-1234".to_owned()));
+        assert_eq!(parse(locale!("de"), "1.234,5"), Some("1234.5".to_owned()));
+        assert_eq!(parse(locale!("de"), "1,5.3"), None);
+        assert_eq!(parse(locale!("fr"), "1 234,5"), Some("1234.5".to_owned()));
+        assert_eq!(
+            parse(locale!("fr"), "1\u{202f}234,5"),
+            Some("1234.5".to_owned())
+        );
+        assert_eq!(parse(locale!("en"), "12abc"), None);
+        assert_eq!(parse(locale!("en"), "-"), None);
+
+        // round trip through the formatter, e.g. with Arabic-Indic digits
+        for locale in [locale!("ar-EG"), locale!("hi-IN"), locale!("de-CH")] {
+            let context = RenderContext::new(locale);
+            let fmt = MessageFormat::new("{N, number}", context.locale());
+            let n: fixed_decimal::FixedDecimal = "-1234567.25".parse().unwrap();
+            let formatted = fmt.format_with_context(&context, [("N", n.clone().into())]);
+            assert_eq!(context.parse_number(&formatted), Some(n), "{formatted}");
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let en = locale!("en");
+        let de = locale!("de");
+        let fingerprint = |pattern: &str, locale| MessageFormat::new(pattern, locale).fingerprint();
+
+        let base = fingerprint("{N, plural, one {# file} other {# files}}", &en);
+        assert_eq!(
+            base,
+            fingerprint("{ N ,plural,\n  one {# file}\n  other {# files}}", &en)
+        );
+        assert_eq!(fingerprint("'{'x'}'", &en), fingerprint("'{x}'", &en));
+        assert_ne!(
+            base,
+            fingerprint("{N, plural, one {# file} other {# items}}", &en)
+        );
+        assert_ne!(
+            base,
+            fingerprint("{N, plural, one {# file} other {# files}}", &de)
+        );
+        assert_ne!(
+            base,
+            MessageFormat::new("{N, plural, one {# file} other {# files}}", &en)
+                .with_casing(Casing::CapitalizeFirst)
+                .fingerprint()
+        );
+        // stable across processes and releases
+        assert_eq!(fingerprint("Cancel", &en), 11664950816325612859);
+
+        // options changing the output without changing the pattern
+        let pattern = "{A|B, select, x|y {<b>{D, duration}</b>} other {#}}";
+        let base = fingerprint(pattern, &en);
+        let fmt = || MessageFormat::new(pattern, &en);
+        let fingerprints = [
+            base,
+            fmt().with_joint_select().fingerprint(),
+            fmt()
+                .with_unknown_argument_handler(|_| String::new())
+                .fingerprint(),
+            fmt()
+                .with_tag_handler(|_, text| text.to_owned())
+                .fingerprint(),
+            fmt()
+                .with_argument_type("duration", |_, _, _| String::new())
+                .fingerprint(),
+        ];
+        let distinct: std::collections::HashSet<_> = fingerprints.iter().collect();
+        assert_eq!(distinct.len(), fingerprints.len());
+    }
+
+    #[test]
+    fn test_registry_available_locales() {
+        registry! {
+            static MESSAGES = {
+                "greeting" => "Hello!",
+                "farewell" => "Goodbye!",
+            };
+        }
+        let en = locale!("en");
+        let de = locale!("de");
+        let fr = locale!("fr");
+        assert!(MESSAGES.available_locales().is_empty());
+
+        MESSAGES.set_source_locale(&en);
+        MESSAGES.set_pattern("greeting", &fr, "Bonjour !").unwrap();
+        MESSAGES.set_pattern("greeting", &de, "Hallo!").unwrap();
+        MESSAGES.set_pattern("farewell", &de, "Tschüss!").unwrap();
+        assert_eq!(
+            MESSAGES.available_locales(),
+            [de.clone(), en.clone(), fr.clone()]
+        );
+        assert_eq!(
+            MESSAGES.key_locales("greeting"),
+            [de.clone(), en.clone(), fr]
+        );
+        assert_eq!(MESSAGES.key_locales("farewell"), [de, en]);
+        assert!(MESSAGES.key_locales("unknown").is_empty());
+    }
+
+    #[test]
+    fn test_argument_types() {
+        let en = locale!("en");
+        let fmt = MessageFormat::new(
+            "{HOST} invited {N, plural, =0 {nobody} other {# guests of {GENDER, select, \
+             female {her} other {their}}}} on {DAY}. {GENDER, select, male {He} other {They}} \
+             paid {AMOUNT, number} ({N})",
+            &en,
+        );
+        assert_eq!(
+            fmt.argument_types(),
+            [
+                ("HOST".to_owned(), ArgumentType::Any),
+                ("N".to_owned(), ArgumentType::Number),
+                (
+                    "GENDER".to_owned(),
+                    ArgumentType::Select(vec!["female".to_owned(), "male".to_owned()])
+                ),
+                ("DAY".to_owned(), ArgumentType::Any),
+                ("AMOUNT".to_owned(), ArgumentType::Number),
+            ]
+        );
+        assert_eq!(
+            fmt.argument_types()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            fmt.arguments()
+        );
+    }
+
+    #[test]
+    fn test_select_branches() {
+        let en = locale!("en");
+        let fmt = MessageFormat::new(
+            "{GENDER, select, female {{N, plural, =0 {none} one {# item} other {# items}}} \
+             other {{N, plural, offset:1 one {# other} other {# others}}}} \
+             {PLACE, selectordinal, one {#st} other {#th}}",
+            &en,
+        )
+        .with_select_alias("f", "female");
+        let branches = |fmt: &MessageFormat, params: Vec<(&str, ParamValue)>| {
+            fmt.select_branches(params)
+                .into_iter()
+                .map(|(name, key)| format!("{name}:{key}"))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            branches(&fmt, vec![("GENDER", "f".into()), ("N", 0.into())]),
+            ["GENDER:female", "N:=0"]
+        );
+        assert_eq!(
+            branches(
+                &fmt,
+                vec![
+                    ("GENDER", "male".into()),
+                    ("N", 2.into()),
+                    ("PLACE", 1.into())
+                ]
+            ),
+            ["GENDER:other", "N:one", "PLACE:one"]
+        );
+        assert_eq!(
+            branches(
+                &fmt,
+                vec![("GENDER", None::<&str>.into()), ("N", "x".into())]
+            ),
+            ["GENDER:other"]
+        );
+        assert!(branches(&fmt, vec![]).is_empty());
+
+        let fmt = fmt.with_compatibility(Compatibility::Icu);
+        assert_eq!(
+            branches(
+                &fmt,
+                vec![
+                    ("GENDER", "female".into()),
+                    ("N", 1.into()),
+                    ("PLACE", 2.into())
+                ]
+            ),
+            ["GENDER:female", "N:one", "PLACE:other"]
+        );
+    }
+
+    #[test]
+    fn test_plural_operands() {
+        let en = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, =0 {none} one {# star} other {# stars}}", &en);
+        let n = |value: i64, operands: &str| {
+            ParamValue::number(value).with_plural_operands(operands.parse().unwrap())
+        };
+        assert_eq!(fmt.format_with_params([("N", n(1, "1.0"))]), "1 stars");
+        assert_eq!(fmt.format_with_params([("N", n(2, "1"))]), "2 star");
+        // exact matches compare the number
+        assert_eq!(fmt.format_with_params([("N", n(0, "1"))]), "none");
+
+        let fmt = MessageFormat::new(
+            "{N, plural, offset:1 one {you and # other} other {you and # others}}",
+            &en,
+        );
+        assert_eq!(
+            fmt.format_with_params([("N", n(3, "1"))]),
+            "you and 2 other"
+        );
+    }
+
+    #[test]
+    fn test_joint_select() {
+        let en = locale!("en");
+        let pattern = "{GENDER|N, select, \
+                       female|=0 {She has no files} \
+                       female|one {She has # file} \
+                       female {She has # files} \
+                       other|one {They have # file} \
+                       other {They have # files}}";
+        let fmt = MessageFormat::new(pattern, &en).with_joint_select();
+        let format = |gender: &'static str, n: i64| {
+            fmt.format_with_params([("GENDER", gender.into()), ("N", n.into())])
+        };
+        assert_eq!(format("female", 0), "She has no files");
+        assert_eq!(format("female", 1), "She has 1 file");
+        assert_eq!(format("female", 3), "She has 3 files");
+        assert_eq!(format("male", 0), "They have 0 files");
+        assert_eq!(format("male", 1), "They have 1 file");
+        assert_eq!(
+            fmt.argument_types()[0],
+            (
+                "GENDER".to_owned(),
+                ArgumentType::Select(vec!["female".to_owned()])
+            )
+        );
+        assert_eq!(
+            fmt.argument_types()[1],
+            ("N".to_owned(), ArgumentType::Number)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing other key in select statement")]
+    fn test_joint_select_missing_other() {
+        let en = locale!("en");
+        MessageFormat::new("{A|B, select, x|y {xy} other|y {y}}", &en)
+            .with_joint_select()
+            .format();
+    }
+
+    #[test]
+    fn test_registry_regional_variants() {
+        registry! {
+            static MESSAGES = {
+                "color" => "Color",
+                "save" => "Save",
+                "trash" => "Trash",
+            };
+        }
+        let en = locale!("en");
+        let en_gb = locale!("en-GB");
+        let en_au = locale!("en-AU");
+        let de_at = locale!("de-AT");
+        MESSAGES.set_source_locale(&en);
+        MESSAGES.set_fallback(Fallback::Key);
+        MESSAGES.set_pattern("trash", &en_gb, "Bin").unwrap();
+        MESSAGES.set_pattern("color", &en_gb, "Colour").unwrap();
+        MESSAGES
+            .set_pattern("save", &locale!("de"), "Speichern")
+            .unwrap();
+        let format = |key, locale: &Locale| {
+            MESSAGES
+                .format(key, locale, Vec::<(String, ParamValue)>::new())
+                .unwrap()
+        };
+
+        assert_eq!(format("color", &en_gb), "Colour");
+        assert_eq!(format("save", &en_gb), "Save");
+        assert_eq!(format("trash", &en_gb), "Bin");
+        assert_eq!(format("color", &en_au), "Color");
+        assert_eq!(format("trash", &en_au), "Trash");
+        assert_eq!(format("trash", &locale!("en-GB-oxendict")), "Bin");
+        assert_eq!(format("save", &de_at), "Speichern");
+        assert_eq!(format("color", &de_at), "color");
+        assert_eq!(
+            MESSAGES
+                .pattern("color", &locale!("en-GB-u-nu-latn"))
+                .unwrap(),
+            "Colour"
+        );
+
+        // cached messages of regional locales see changed parents
+        MESSAGES
+            .set_pattern("save", &locale!("de"), "Sichern")
+            .unwrap();
+        assert_eq!(format("save", &de_at), "Sichern");
+
+        assert_eq!(
+            MESSAGES
+                .audit()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "de: color: missing translation",
+                "de: trash: missing translation"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_report() {
+        registry! {
+            static MESSAGES = {
+                "cart.items" => "{N, plural, one {# item} other {# items}}",
+                "place" => "{N, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}",
+                "greeting" => "Hello {NAME}!",
+            };
+        }
+        let en = locale!("en");
+        let de = locale!("de");
+        let pl = locale!("pl");
+        MESSAGES.set_source_locale(&en);
+        MESSAGES
+            .set_pattern(
+                "cart.items",
+                &de,
+                "{N, plural, ein {# Artikel} other {# Artikel}}",
+            )
+            .unwrap();
+        assert_eq!(
+            MESSAGES.set_pattern("greeting", &de, "Hallo {NAME!"),
+            Err(ParseError::UnclosedBrace)
+        );
+        assert_eq!(
+            MESSAGES.format("greeting", &de, [("NAME", "Ana".into())]),
+            Some("Hello Ana!".to_owned())
+        );
+        MESSAGES
+            .set_pattern(
+                "cart.items",
+                &pl,
+                "{N, plural, one {# pozycja} other {# pozycji}}",
+            )
+            .unwrap();
+        MESSAGES
+            .set_pattern("greeting", &pl, "Cześć {USER}!")
+            .unwrap();
+
+        let report = MESSAGES.report();
+        assert!(report.has_errors());
+        assert_eq!(
+            report
+                .diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "de: cart.items: error at 12..15: invalid plural key ein",
+                "pl: cart.items: warning: missing plural category few of N",
+                "pl: cart.items: warning: missing plural category many of N",
+                "de: greeting: warning: missing translation",
+                "pl: greeting: error: missing arguments [NAME], unknown arguments [USER]",
+                "de: place: warning: missing translation",
+                "pl: place: warning: missing translation",
+            ]
+        );
+
+        #[cfg(feature = "report")]
+        {
+            let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+            assert_eq!(json["errors"], 2);
+            assert_eq!(json["warnings"], 5);
+            assert_eq!(json["diagnostics"][0]["span"], serde_json::json!([12, 15]));
+            assert_eq!(json["diagnostics"][1]["span"], serde_json::Value::Null);
+        }
+    }
+
+    #[test]
+    fn test_try_new() {
+        let en = locale!("en");
+        let err = |pattern: &str| MessageFormat::try_new(pattern, &en).unwrap_err();
+        assert_eq!(err("Hello {NAME"), ParseError::UnclosedBrace);
+        assert_eq!(err("Hello NAME}"), ParseError::UnmatchedClosingBrace);
+        assert_eq!(
+            err("{N, plural, one {# file}}"),
+            ParseError::MissingOther("plural".to_owned())
+        );
+        assert_eq!(
+            err("{G, select, female {she} other}"),
+            ParseError::MissingBranch("other".to_owned())
+        );
+        assert_eq!(err("{-}"), ParseError::UnknownBlock("-".to_owned()));
+        assert_eq!(
+            err("{N, number, @#@}"),
+            ParseError::UnknownNumberStyle("@#@".to_owned())
+        );
+        assert_eq!(
+            MessageFormat::new("{A|B, select, x|y|z {xyz} other {}}", &en)
+                .with_joint_select()
+                .try_parse()
+                .unwrap_err(),
+            ParseError::TooManyKeys("x|y|z".to_owned())
+        );
+
+        // lenient patterns are accepted
+        let fmt = MessageFormat::try_new("Due {D, duration} at #{N}", &en).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("D, duration", "today".into()), ("N", 3.into())]),
+            "Due today at #3"
+        );
+        assert_eq!(
+            MessageFormat::parse_strict("{N, plural, one {# file}}", &en).unwrap_err(),
+            ParseError::MissingOther("plural".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_ordinal_rules() {
+        let sv = locale!("sv");
+        let fmt = MessageFormat::new("{N, selectordinal, one {#:a} other {#:e}}", &sv);
+        let floors: Vec<_> = [1, 2, 3, 21, 22, 23]
+            .into_iter()
+            .map(|n| fmt.format_with_params([("N", n.into())]))
+            .collect();
+        assert_eq!(floors, ["1:a", "2:a", "3:e", "21:a", "22:a", "23:e"]);
+
+        // Dart selects by the cardinal rules
+        let en = locale!("en");
+        let fmt = MessageFormat::new(
+            "{N, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}",
+            &en,
+        )
+        .with_compatibility(Compatibility::Dart);
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "2th");
+    }
+
+    #[test]
+    fn test_format_from_threads() {
+        let en = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, one {# file} other {# files}}", &en);
+        let formatted: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|n| {
+                    let fmt = &fmt;
+                    scope.spawn(move || fmt.format_with_params([("N", n.into())]))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(formatted, ["0 files", "1 file", "2 files", "3 files"]);
+    }
+
+    #[test]
+    fn test_owned_locale() {
+        struct Labels {
+            files: MessageFormat<'static>,
+        }
+
+        static LABELS: Lazy<Labels> = Lazy::new(|| Labels {
+            files: MessageFormat::new_owned(
+                "{N, plural, one {# Datei} other {# Dateien}}",
+                "de-AT".parse().unwrap(),
+            ),
+        });
+        assert_eq!(
+            LABELS.files.format_with_params([("N", 1.into())]),
+            "1 Datei"
+        );
+        assert_eq!(
+            LABELS.files.format_with_params([("N", 15.into())]),
+            "15 Dateien"
+        );
+    }
+
+    #[test]
+    fn test_date_time_arguments() {
+        use icu::calendar::{Date, DateTime};
+
+        let en = locale!("en");
+        let fmt = MessageFormat::new(
+            "Due {WHEN, date, short}, {WHEN, date} at {WHEN, time, short}",
+            &en,
+        );
+        let when = DateTime::try_new_iso_datetime(2025, 1, 3, 14, 30, 0).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("WHEN", when.into())]),
+            "Due 1/3/25, Jan 3, 2025 at 2:30\u{202f}PM"
+        );
+        assert_eq!(
+            fmt.argument_types(),
+            [("WHEN".to_owned(), ArgumentType::DateTime)]
+        );
+
+        let de = locale!("de");
+        let fmt = MessageFormat::new("{D, date, full} {D, time}", &de);
+        let date = Date::try_new_iso_date(2025, 1, 3).unwrap();
+        assert_eq!(
+            fmt.format_with_params([("D", date.into())]),
+            "Freitag, 3. Januar 2025 Invalid parameter - D"
+        );
+        assert_eq!(fmt.debug_tree(), "date D full\ntext \" \"\ntime D medium\n");
+
+        assert_eq!(
+            MessageFormat::try_new("{D, date, ::yMMMd}", &en).unwrap_err(),
+            ParseError::UnknownDateTimeStyle("::yMMMd".into())
+        );
+        let fmt = MessageFormat::new("{D, date, yyyy-MM-dd}", &en);
+        assert_eq!(fmt.format_with_params([("D", date.into())]), "Jan 3, 2025");
+        assert_eq!(fmt.debug_tree(), "date D medium\n");
+        let fmt = MessageFormat::new("{D, date}", &en).with_compatibility(Compatibility::Dart);
+        assert_eq!(
+            fmt.format_with_params([("D, date", "today".into())]),
+            "today"
+        );
+    }
+
+    #[test]
+    fn test_number_integer_style() {
+        let en = locale!("en");
+        let fmt = MessageFormat::new("{N, number} / {N, number, integer}", &en);
+        let formatted: Vec<_> = [2.5, 3.5, 1234.75]
+            .into_iter()
+            .map(|n| fmt.format_with_params([("N", n.into())]))
+            .collect();
+        assert_eq!(formatted, ["2.5 / 2", "3.5 / 4", "1,234.75 / 1,235"]);
+        assert_eq!(
+            fmt.debug_tree(),
+            "number N\ntext \" / \"\nnumber N ::precision-integer\n"
+        );
+    }
+
+    #[test]
+    fn test_tag_handler() {
+        let en = locale!("en");
+        let html = |tag: &str, children: &str| format!("<{tag}>{children}</{tag}>");
+        let fmt = MessageFormat::new(
+            "<b>{NAME}</b> sent {N, plural, one {<i>#</i> file} other {<i>#</i> files}}<br/>",
+            &en,
+        )
+        .with_tag_handler(|tag, children| match tag {
+            "br" => "\n".to_owned(),
+            _ => format!("[{tag}]{children}[/{tag}]"),
+        });
+        assert_eq!(
+            fmt.format_with_params([("NAME", "Ann".into()), ("N", 2.into())]),
+            "[b]Ann[/b] sent [i]2[/i] files\n"
+        );
+        assert_eq!(
+            fmt.format_to_parts([("NAME", "Ann".into()), ("N", 1.into())]),
+            [MessagePart::Literal(
+                "[b]Ann[/b] sent [i]1[/i] file\n".to_owned()
+            )]
+        );
+
+        // nested tags and comparisons in text
+        let fmt = MessageFormat::new("<p>a < b, <b>{X}</b></p>", &en).with_tag_handler(html);
+        assert_eq!(
+            fmt.format_with_params([("X", "c".into())]),
+            "<p>a < b, <b>c</b></p>"
+        );
+        assert!(fmt
+            .debug_tree()
+            .starts_with("tag <p>\ntext \"a < b, \"\ntag <b>\n"));
+
+        // without a handler, tags are text
+        let fmt = MessageFormat::new("<b>{X}</b>", &en);
+        assert_eq!(fmt.format_with_params([("X", 1.into())]), "<b>1</b>");
+
+        let err = |pattern: &str| {
+            MessageFormat::new(pattern, &en)
+                .with_tag_handler(html)
+                .try_parse()
+                .unwrap_err()
+        };
+        assert_eq!(
+            err("<b>{N, plural, one {#</b>} other {#}}"),
+            ParseError::UnmatchedClosingTag("b".to_owned())
+        );
+        assert_eq!(err("<b>x"), ParseError::UnclosedTag("b".to_owned()));
+    }
+
+    #[test]
+    fn test_message_bundle() {
+        let mut bundle = MessageBundle::new(locale!("de"));
+        bundle.add("greeting", "Hallo {NAME}!").unwrap();
+        bundle
+            .add("files", "{N, plural, one {# Datei} other {# Dateien}}")
+            .unwrap();
+        assert_eq!(
+            bundle.add("broken", "{N, plural, one {# Datei}}"),
+            Err(ParseError::MissingOther("plural".to_owned()))
+        );
+        assert!(!bundle.contains("broken"));
+        assert_eq!(bundle.keys().collect::<Vec<_>>(), ["greeting", "files"]);
+
+        assert_eq!(
+            bundle
+                .format("greeting", [("NAME", "Anna".into())])
+                .unwrap(),
+            "Hallo Anna!"
+        );
+        assert_eq!(
+            bundle.format("files", [("N", 1.into())]).unwrap(),
+            "1 Datei"
+        );
+        assert_eq!(
+            bundle.format("files", [("N", 15.into())]).unwrap(),
+            "15 Dateien"
+        );
+        assert_eq!(bundle.format("missing", [("N", 1.into())]), None);
+
+        bundle.add("greeting", "Servus {NAME}!").unwrap();
+        assert_eq!(bundle.len(), 2);
+        assert_eq!(
+            bundle
+                .format("greeting", [("NAME", "Anna".into())])
+                .unwrap(),
+            "Servus Anna!"
+        );
+        assert!(bundle.remove("greeting").is_some());
+        assert_eq!(bundle.keys().collect::<Vec<_>>(), ["files"]);
+    }
+
+    #[cfg(feature = "arb")]
+    #[test]
+    fn test_message_bundle_load_arb() {
+        let arb = r#"{
+            "@@locale": "en",
+            "greeting": "Hello {NAME}!",
+            "@greeting": {
+                "description": "Greeting on the start page",
+                "placeholders": {"NAME": {"example": "Alice"}}
+            },
+            "files": "{N, plural, one {# file}}",
+            "cart": "Hello {NAME"
+        }"#;
+        let mut bundle = MessageBundle::new(locale!("en"));
+        let failed = bundle.load_arb(arb).unwrap();
+        assert_eq!(
+            failed,
+            [
+                ("cart".to_owned(), ParseError::UnclosedBrace),
+                (
+                    "files".to_owned(),
+                    ParseError::MissingOther("plural".to_owned())
+                ),
+            ]
+        );
+        assert_eq!(bundle.keys().collect::<Vec<_>>(), ["greeting"]);
+        assert_eq!(
+            bundle
+                .format("greeting", [("NAME", "Alice".into())])
+                .unwrap(),
+            "Hello Alice!"
+        );
+        let metadata = bundle.metadata("greeting").unwrap();
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("Greeting on the start page")
+        );
+        assert_eq!(metadata.examples, [("NAME".to_owned(), "Alice".to_owned())]);
+
+        assert!(bundle.load_arb(r#"{"greeting": 1}"#).is_err());
+        assert!(bundle.load_arb("[]").is_err());
+    }
+
+    #[cfg(feature = "fluent")]
+    #[test]
+    fn test_message_bundle_load_ftl() {
+        let ftl = r#"
+-brand = Firefox
+
+# Shown after a download
+files = { $file-count ->
+    [0] No files
+    [one] One file
+   *[other] { $file-count } files
+} in { -brand }
+place = { NUMBER($n, type: "ordinal") ->
+    [one] {$n}st
+    [two] {$n}nd
+    [few] {$n}rd
+   *[other] {$n}th
+}
+invite = { $gender ->
+    [female] She
+    [male] He
+   *[unknown] They
+} invited you to {"{"}{ $party }{"}"}.
+login = Login
+    .placeholder = { $user }'s email
+total = Total: { NUMBER($amount) }
+date = { DATETIME($d) }
+other-ref = { files }
+"#;
+        let mut bundle = MessageBundle::new(locale!("en"));
+        let failed = bundle.load_ftl(ftl).unwrap();
+        assert_eq!(
+            failed,
+            [
+                (
+                    "date".to_owned(),
+                    "function DATETIME is not supported".to_owned()
+                ),
+                (
+                    "other-ref".to_owned(),
+                    "message reference files is not supported".to_owned()
+                ),
+            ]
+        );
+
+        assert_eq!(
+            bundle.get("files").unwrap().to_string(),
+            "{file_count, plural, =0 {No files} one {One file} other {{file_count} files}} in Firefox"
+        );
+        let files = |n: i64| bundle.format("files", [("file_count", n.into())]).unwrap();
+        assert_eq!(files(0), "No files in Firefox");
+        assert_eq!(files(1), "One file in Firefox");
+        assert_eq!(files(2), "2 files in Firefox");
+        assert_eq!(
+            bundle.metadata("files").unwrap().description.as_deref(),
+            Some("Shown after a download")
+        );
+
+        let place = |n: i64| bundle.format("place", [("n", n.into())]).unwrap();
+        assert_eq!(place(1), "1st");
+        assert_eq!(place(22), "22nd");
+        assert_eq!(place(13), "13th");
+
+        let invite = |gender: &str| {
+            bundle
+                .format(
+                    "invite",
+                    [
+                        ("gender", gender.to_owned().into()),
+                        ("party", "Tea".into()),
+                    ],
+                )
+                .unwrap()
+        };
+        assert_eq!(invite("female"), "She invited you to {Tea}.");
+        assert_eq!(invite("x"), "They invited you to {Tea}.");
+
+        assert_eq!(bundle.get("login").unwrap().format(), "Login");
+        assert_eq!(
+            bundle
+                .format("login.placeholder", [("user", "Anna".into())])
+                .unwrap(),
+            "Anna's email"
+        );
+        assert_eq!(
+            bundle.format("total", [("amount", 1234.into())]).unwrap(),
+            "Total: 1,234"
+        );
+
+        assert!(bundle.load_ftl("files = { $n ->").is_err());
+    }
+
+    #[cfg(feature = "precompiled")]
+    #[test]
+    fn test_precompiled() {
+        let en = locale!("en");
+        let patterns = [
+            "Hello '{'{NAME}'}', it''s #{N, number, ::.00 group-off}!",
+            "{G, select, female {{N, plural, offset:1 =0 {nobody} one {her and # other} \
+             other {her and '#'# others}}} other {{N, selectordinal, one {#st} two {#nd} \
+             few {#rd} other {#th}} on {D, date, short}}}",
+        ];
+        let params = [
+            ("NAME", "Alice".into()),
+            ("N", 3.into()),
+            ("G", "female".into()),
+            (
+                "D",
+                icu::calendar::Date::try_new_iso_date(2024, 3, 9)
+                    .unwrap()
+                    .into(),
+            ),
+        ];
+        for pattern in patterns {
+            let message = MessageFormat::new(pattern, &en);
+            let json = message.to_precompiled();
+            let loaded = MessageFormat::from_precompiled(&json, &en).unwrap();
+            assert_eq!(loaded.source(), pattern);
+            assert_eq!(loaded.to_precompiled(), json);
+            for g in ["female", "male"] {
+                let mut params = params.clone();
+                params[2].1 = g.into();
+                assert_eq!(
+                    loaded.format_with_params(params.clone()),
+                    message.format_with_params(params)
+                );
+            }
+        }
+
+        let files = MessageFormat::new("{N, plural, one {# file} other {# files}}", &en);
+        let json: serde_json::Value = serde_json::from_str(&files.to_precompiled()).unwrap();
+        assert_eq!(
+            json["blocks"][0]["branches"][0],
+            serde_json::json!({"key": "one", "blocks": [{"type": "pound"}, {"type": "text", "value": " file"}]})
+        );
+
+        assert_eq!(
+            MessageFormat::from_precompiled(r#"{"version": 2}"#, &en).unwrap_err(),
+            "unsupported version, expected 1"
+        );
+        assert!(MessageFormat::from_precompiled(
+            r#"{"version": 1, "pattern": "", "blocks": [{"type": "plural", "name": "N", "branches": []}]}"#,
+            &en
+        )
+        .is_err());
+
+        let mut bundle = MessageBundle::new(locale!("de"));
+        bundle.add("greeting", "Hallo {NAME}!").unwrap();
+        bundle
+            .add("files", "{N, plural, one {# Datei} other {# Dateien}}")
+            .unwrap();
+        let loaded = MessageBundle::from_precompiled(&bundle.to_precompiled()).unwrap();
+        assert_eq!(loaded.locale(), &locale!("de"));
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), ["greeting", "files"]);
+        assert_eq!(
+            loaded.format("files", [("N", 15.into())]).unwrap(),
+            "15 Dateien"
+        );
+    }
+
+    #[test]
+    fn test_mf2() {
+        let en = locale!("en");
+        let mf2 = |source: &str| MessageFormat::try_new_mf2(source, &en).unwrap();
+
+        let hello = mf2("Hello, {$user-name}! \\{x\\} It's #1");
+        assert_eq!(hello.source(), "Hello, {user_name}! '{x} It''s #1'");
+        assert_eq!(
+            hello.format_with_params([("user_name", "Alice".into())]),
+            "Hello, Alice! {x} It's #1"
+        );
+
+        let files = mf2(".input {$count :number}
+             .local $total = {$sum :number minimumFractionDigits=2}
+             .match $count
+             0 {{No files}}
+             one {{One file of {$total}}}
+             * {{{$count} files of {$total}}}");
+        assert_eq!(
+            files.source(),
+            "{count, plural, =0 {No files} one {One file of {sum, number, ::.00#}} \
+             other {{count, number} files of {sum, number, ::.00#}}}"
+        );
+        let format = |count: i64| {
+            files.format_with_params([("count", count.into()), ("sum", 1234.5.into())])
+        };
+        assert_eq!(format(0), "No files");
+        assert_eq!(format(1), "One file of 1,234.50");
+        assert_eq!(format(1000), "1,000 files of 1,234.50");
+
+        let place = mf2(".input {$n :integer select=ordinal} .match $n \
+             one {{{$n}st}} two {{{$n}nd}} few {{{$n}rd}} * {{{$n}th}}");
+        assert_eq!(place.format_with_params([("n", 22.into())]), "22nd");
+        assert_eq!(place.format_with_params([("n", 11.into())]), "11th");
+
+        let invite = mf2(".input {$n :number} .match $gender $n
+             female one {{She invited a guest}}
+             female * {{She invited {$n} guests}}
+             * one {{They invited a guest}}
+             * * {{They invited {$n} guests}}");
+        let format = |gender: &str, n: i64| {
+            invite.format_with_params([("gender", gender.to_owned().into()), ("n", n.into())])
+        };
+        assert_eq!(format("female", 1), "She invited a guest");
+        assert_eq!(format("female", 3), "She invited 3 guests");
+        assert_eq!(format("male", 1), "They invited a guest");
+        assert_eq!(format("male", 3), "They invited 3 guests");
+
+        let date = mf2("Due {$d :date style=short} at {|noon|}");
+        assert_eq!(date.source(), "Due {d, date, short} at noon");
+
+        let err = |source: &str| MessageFormat::try_new_mf2(source, &en).unwrap_err();
+        assert_eq!(
+            err("Hello {$name"),
+            ParseError::InvalidMf2(12, "expected }".to_owned())
+        );
+        assert_eq!(
+            err(".match $x a {{A}}"),
+            ParseError::MissingOther("select".to_owned())
+        );
+        assert_eq!(
+            err("{$x :currency}"),
+            ParseError::UnknownArgumentType(":currency".to_owned())
+        );
+        assert_eq!(
+            err("{#b}bold{/b}"),
+            ParseError::InvalidMf2(1, "markup is not supported".to_owned())
+        );
+        assert_eq!(
+            err(".match $x $y * {{A}}"),
+            ParseError::InvalidMf2(
+                13,
+                "number of keys differs from the number of selectors".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_custom_argument_types() {
+        let en = locale!("en");
+        let filesize = |value: &ParamValue, style: &str, locale: &Locale| {
+            let bytes = value.to_string();
+            match style {
+                "short" => format!("{bytes} B"),
+                _ => format!("{bytes} bytes ({locale})"),
+            }
+        };
+        let fmt = MessageFormat::new("{SIZE, filesize, short} of {TOTAL, filesize}", &en)
+            .with_argument_type("filesize", filesize);
+        assert_eq!(
+            fmt.format_with_params([("SIZE", 512.into()), ("TOTAL", 1024.into())]),
+            "512 B of 1024 bytes (en)"
+        );
+        assert_eq!(
+            fmt.format_with_params([("SIZE", 512.into())]),
+            "512 B of Undefined parameter - TOTAL"
+        );
+        assert_eq!(fmt.arguments(), ["SIZE", "TOTAL"]);
+        assert_eq!(
+            fmt.debug_tree(),
+            "filesize SIZE short\ntext \" of \"\nfilesize TOTAL\n"
+        );
+
+        // other types are parsed as before
+        let fmt = MessageFormat::new("{D, duration}", &en).with_argument_type("filesize", filesize);
+        assert_eq!(fmt.debug_tree(), "argument D, duration\n");
+    }
+
+    #[test]
+    fn test_any_provider() {
+        use icu_decimal::provider::{
+            AffixesV1, DecimalSymbolsV1, DecimalSymbolsV1Marker, GroupingSizesV1,
+        };
+        use icu_provider::prelude::*;
+
+        /// Decimal symbols of Swiss German, but with a middle dot
+        struct Symbols;
+
+        impl AnyProvider for Symbols {
+            fn load_any(&self, key: DataKey, _: DataRequest) -> Result<AnyResponse, DataError> {
+                if key != DecimalSymbolsV1Marker::KEY {
+                    return Err(DataErrorKind::MissingDataKey.with_key(key));
+                }
+                let symbols = DecimalSymbolsV1 {
+                    minus_sign_affixes: AffixesV1 {
+                        prefix: "-".into(),
+                        suffix: "".into(),
+                    },
+                    plus_sign_affixes: AffixesV1 {
+                        prefix: "+".into(),
+                        suffix: "".into(),
+                    },
+                    decimal_separator: "·".into(),
+                    grouping_separator: "’".into(),
+                    grouping_sizes: GroupingSizesV1 {
+                        primary: 3,
+                        secondary: 3,
+                        min_grouping: 1,
+                    },
+                    digits: ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'],
+                };
+                Ok(AnyResponse {
+                    metadata: Default::default(),
+                    payload: Some(
+                        DataPayload::<DecimalSymbolsV1Marker>::from_owned(symbols)
+                            .wrap_into_any_payload(),
+                    ),
+                })
+            }
+        }
+
+        let locale = locale!("de-CH");
+        let fmt = MessageFormat::new_with_any_provider("{N, number}", &locale, Arc::new(Symbols));
+        assert_eq!(fmt.format_with_params([("N", 1234.5.into())]), "1’234·5");
+
+        let context = RenderContext::new_with_any_provider(locale.clone(), Arc::new(Symbols));
+        let fmt = MessageFormat::new("{N, number}", &locale);
+        assert_eq!(
+            fmt.format_with_context(&context, [("N", 1234.5.into())]),
+            "1’234·5"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing locale")]
+    fn test_any_provider_dates() {
+        use icu::calendar::Date;
+        use icu_provider::prelude::*;
+
+        struct NoData;
+
+        impl AnyProvider for NoData {
+            fn load_any(&self, key: DataKey, _: DataRequest) -> Result<AnyResponse, DataError> {
+                Err(DataErrorKind::MissingDataKey.with_key(key))
+            }
+        }
+
+        // the date is formatted with the data of the provider, not the
+        // compiled data
+        let locale = locale!("en");
+        let fmt = MessageFormat::new_with_any_provider("{D}", &locale, Arc::new(NoData));
+        let date = Date::try_new_iso_date(2024, 7, 1).unwrap();
+        fmt.format_with_params([("D", date.into())]);
+    }
+
+    #[test]
+    fn test_literal_blocks() {
+        let locale = locale!("en");
+        // quoted `#` and the text of the unknown argument handler are literals
+        let fmt = MessageFormat::new(
+            "{N, plural, one {'#'#} other {{M, select, other {# '#' {-}}}}}",
+            &locale,
+        )
+        .with_unknown_argument_handler(|_| "#".to_owned());
+        assert_eq!(
+            fmt.format_to_parts([("N", 2.into()), ("M", "x".into())]),
+            [
+                MessagePart::Argument {
+                    name: "N".to_owned(),
+                    value: "2".to_owned(),
+                    kind: ParamKind::Number,
+                },
+                MessagePart::Literal(" # #".to_owned()),
+            ]
+        );
+        assert_eq!(fmt.format_with_params([("N", 1.into())]), "#1");
+        assert_eq!(
+            fmt.debug_tree(),
+            "plural N\n  one\n    text \"##\"\n  other\n    select M\n      other\n        text \"# # #\"\n"
+        );
+    }
+
+    #[test]
+    fn test_cached_formatters() {
+        let locale = locale!("en");
+        let fmt = MessageFormat::new("{N, plural, one {# file} other {# files}}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1.into())]), "1 file");
+        assert_eq!(fmt.format_with_params([("N", 1000.into())]), "1,000 files");
+        assert!(std::ptr::eq(fmt.context(), fmt.context()));
+
+        let fmt = MessageFormat::new("{N, number, ::group-off}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1000.into())]), "1000");
+        assert_eq!(fmt.format_with_params([("N", 2000.into())]), "2000");
+        let context = fmt.context();
+        assert!(Arc::ptr_eq(
+            &context.decimal_formatter(None, GroupingStrategy::Never),
+            &context.decimal_formatter(None, GroupingStrategy::Never)
+        ));
+
+        let fmt = MessageFormat::new("{N, number, ::compact-short}", &locale);
+        assert_eq!(fmt.format_with_params([("N", 1200.into())]), "1.2K");
+        assert_eq!(fmt.format_with_params([("N", 25000.into())]), "25K");
+        let context = fmt.context();
+        let formatter = || {
+            context.compact_decimal_formatter(
+                number::Notation::CompactShort,
+                None,
+                GroupingStrategy::Min2,
+            )
+        };
+        assert!(Arc::ptr_eq(&formatter(), &formatter()));
+    }
+
+    #[test]
+    fn test_quoted_literals_with_noncharacters() {
+        let en = locale!("en");
+        let fmt = MessageFormat::try_new("a _\u{FDDF}_0_ b", &en).unwrap();
+        assert_eq!(fmt.format(), "a _\u{FDDF}_0_ b");
+        let fmt = MessageFormat::try_new("x '{' _\u{FDDF}_0_ y", &en).unwrap();
+        assert_eq!(fmt.format(), "x { _\u{FDDF}_0_ y");
+        let fmt = MessageFormat::new("{N, plural, other {'{#}' ''# '#'}}", &en);
+        assert_eq!(fmt.format_with_params([("N", 2.into())]), "{#} '2 #");
+    }
 }
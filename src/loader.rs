@@ -0,0 +1,44 @@
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::path::PathBuf;
+
+use icu::locid::Locale;
+
+/// Source of locale files, e.g. a CDN or an object storage, from which
+/// [`crate::Registry::load_locale`] loads translations on demand.
+pub trait ResourceFetcher {
+    /// Returns the content of the locale file of `locale`, see
+    /// [`crate::build::parse_locale_file`] for its format. The future is
+    /// `Send`, so that translations can be loaded in spawned tasks.
+    fn fetch(&self, locale: &Locale) -> impl Future<Output = Result<String, String>> + Send;
+}
+
+/// Reads locale files like `de-CH.json` or `de-CH.arb` from a directory
+/// without blocking the tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct FileFetcher {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "tokio")]
+impl FileFetcher {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl ResourceFetcher for FileFetcher {
+    async fn fetch(&self, locale: &Locale) -> Result<String, String> {
+        let mut errors = Vec::new();
+        for extension in ["json", "arb"] {
+            let path = self.dir.join(format!("{locale}.{extension}"));
+            match tokio::fs::read_to_string(&path).await {
+                Ok(content) => return Ok(content),
+                Err(err) => errors.push(format!("{}: {err}", path.display())),
+            }
+        }
+        Err(errors.join(", "))
+    }
+}
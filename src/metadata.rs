@@ -0,0 +1,34 @@
+use fixed_decimal::FixedDecimal;
+
+use crate::ParamValue;
+
+/// Information about a message for translators and tools, e.g. from the `@`
+/// entries of ARB files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// What the message is used for, e.g. `Title of the cart page`
+    pub description: Option<String>,
+    /// Disambiguation of messages with the same text, e.g. `verb` for `Order`
+    pub meaning: Option<String>,
+    /// Where the message is displayed, e.g. `checkout`
+    pub context: Option<String>,
+    /// Example values of arguments, e.g. `("WHO", "Alice")`
+    pub examples: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Returns the examples as parameters; numeric examples are numbers, so
+    /// that they select plural branches.
+    pub fn example_params(&self) -> Vec<(String, ParamValue)> {
+        self.examples
+            .iter()
+            .map(|(name, example)| {
+                let value = match example.parse::<FixedDecimal>() {
+                    Ok(number) => number.into(),
+                    Err(_) => example.clone().into(),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
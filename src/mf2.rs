@@ -0,0 +1,485 @@
+//! Conversion of MessageFormat 2 messages to the classic syntax, see
+//! <https://unicode.org/reports/tr35/tr35-messageFormat.html>.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{MessageFormat, ParseError};
+
+/// Converts a MessageFormat 2 message to a pattern, see
+/// [`MessageFormat::try_new_mf2`].
+pub(crate) fn to_pattern(source: &str) -> Result<String, ParseError> {
+    Converter {
+        source,
+        pos: 0,
+        declarations: HashMap::new(),
+    }
+    .message()
+}
+
+/// Operand and annotation of an expression, e.g. `{$n :number}`
+#[derive(Debug, Clone)]
+enum Expression {
+    Literal(String),
+    Variable {
+        name: String,
+        function: Option<Function>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    name: String,
+    options: Vec<(String, String)>,
+}
+
+impl Function {
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Variant {
+    keys: Vec<Option<String>>,
+    pattern: String,
+}
+
+struct Converter<'s> {
+    source: &'s str,
+    pos: usize,
+    /// Expressions of `.input` and `.local` declarations by variable name
+    declarations: HashMap<String, Expression>,
+}
+
+impl Converter<'_> {
+    fn message(&mut self) -> Result<String, ParseError> {
+        self.skip_whitespace();
+        if !self.rest().starts_with('.') && !self.rest().starts_with("{{") {
+            // simple messages are patterns, incl. leading whitespace
+            self.pos = 0;
+            return self.pattern(false);
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.eat(".input") {
+                self.skip_whitespace();
+                self.expect("{")?;
+                let expression = self.expression()?;
+                let Expression::Variable { name, .. } = &expression else {
+                    return Err(self.error("expected a variable"));
+                };
+                self.declarations.insert(name.clone(), expression);
+            } else if self.eat(".local") {
+                self.skip_whitespace();
+                self.expect("$")?;
+                let name = self.name()?;
+                self.skip_whitespace();
+                self.expect("=")?;
+                self.skip_whitespace();
+                self.expect("{")?;
+                let expression = self.expression()?;
+                self.declarations.insert(name, expression);
+            } else if self.eat(".match") {
+                let pattern = self.matcher()?;
+                return self.end(pattern);
+            } else if self.eat("{{") {
+                let pattern = self.pattern(true)?;
+                return self.end(pattern);
+            } else if self.rest().starts_with('.') {
+                return Err(self.error("unknown keyword"));
+            } else {
+                return Err(self.error("expected a declaration or a body"));
+            }
+        }
+    }
+
+    fn end(&mut self, pattern: String) -> Result<String, ParseError> {
+        self.skip_whitespace();
+        if self.pos < self.source.len() {
+            return Err(self.error("unexpected text after the body"));
+        }
+        Ok(pattern)
+    }
+
+    /// Converts text and placeholders up to the end of the message or of a
+    /// quoted pattern, i.e. `}}`.
+    fn pattern(&mut self, quoted: bool) -> Result<String, ParseError> {
+        let mut pattern = String::new();
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.rest().chars().next() else {
+                if quoted {
+                    return Err(self.error("expected }}"));
+                }
+                break;
+            };
+            match c {
+                '\\' => text.push(self.escaped()?),
+                '{' => {
+                    self.pos += 1;
+                    pattern.push_str(&MessageFormat::escape(&std::mem::take(&mut text)));
+                    let expression = self.expression()?;
+                    pattern.push_str(&self.placeholder(&expression)?);
+                }
+                '}' if quoted && self.eat("}}") => break,
+                '}' => return Err(self.error("unescaped }")),
+                _ => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        pattern.push_str(&MessageFormat::escape(&text));
+        Ok(pattern)
+    }
+
+    /// Parses an expression after its `{`, resolving declared variables.
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        self.skip_whitespace();
+        let mut expression = match self.rest().chars().next() {
+            Some('$') => {
+                self.pos += 1;
+                let name = self.name()?;
+                match self.declarations.get(&name) {
+                    Some(declared) => declared.clone(),
+                    None => Expression::Variable {
+                        name,
+                        function: None,
+                    },
+                }
+            }
+            Some('#' | '/') => return Err(self.error("markup is not supported")),
+            Some(':') => return Err(self.error("expressions without operand are not supported")),
+            _ => Expression::Literal(self.literal()?),
+        };
+        self.skip_whitespace();
+        if self.eat(":") {
+            let function = self.function()?;
+            match &mut expression {
+                Expression::Variable {
+                    function: annotation,
+                    ..
+                } => *annotation = Some(function),
+                Expression::Literal(_) => {
+                    return Err(self.error("functions of literals are not supported"))
+                }
+            }
+        }
+        self.skip_whitespace();
+        self.expect("}")?;
+        Ok(expression)
+    }
+
+    /// Parses a function after its `:`, with its options; attributes are
+    /// ignored.
+    fn function(&mut self) -> Result<Function, ParseError> {
+        let name = self.name()?;
+        let mut options = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with('}') {
+                break;
+            }
+            if self.eat("@") {
+                self.name()?;
+                self.skip_whitespace();
+                if self.eat("=") {
+                    self.skip_whitespace();
+                    self.literal()?;
+                }
+                continue;
+            }
+            let option = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            if self.rest().starts_with('$') {
+                return Err(self.error("variable options are not supported"));
+            }
+            options.push((option, self.literal()?));
+        }
+        Ok(Function { name, options })
+    }
+
+    /// Converts `.match` selectors and variants to nested blocks.
+    fn matcher(&mut self) -> Result<String, ParseError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if !self.eat("$") {
+                break;
+            }
+            let name = self.name()?;
+            let selector = self
+                .declarations
+                .get(&name)
+                .cloned()
+                .unwrap_or(Expression::Variable {
+                    name,
+                    function: None,
+                });
+            selectors.push(selector);
+        }
+        if selectors.is_empty() {
+            return Err(self.error("expected a selector"));
+        }
+
+        let mut variants = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.pos == self.source.len() {
+                break;
+            }
+            let start = self.pos;
+            let mut keys = Vec::new();
+            while !self.eat("{{") {
+                if self.eat("*") {
+                    keys.push(None);
+                } else {
+                    // `other` is the branch for other values in the classic syntax
+                    keys.push(Some(self.literal()?).filter(|key| key != "other"));
+                }
+                self.skip_whitespace();
+            }
+            if keys.len() != selectors.len() {
+                return Err(ParseError::InvalidMf2(
+                    start,
+                    "number of keys differs from the number of selectors".to_owned(),
+                ));
+            }
+            let pattern = self.pattern(true)?;
+            variants.push(Variant { keys, pattern });
+        }
+        if variants.is_empty() {
+            return Err(self.error("expected a variant"));
+        }
+        let variants: Vec<_> = variants.iter().collect();
+        self.select(&selectors, &variants)
+    }
+
+    /// Nests the blocks of the remaining `selectors`, in which exact keys are
+    /// preferred over `*`.
+    fn select(
+        &self,
+        selectors: &[Expression],
+        variants: &[&Variant],
+    ) -> Result<String, ParseError> {
+        let Some((selector, inner)) = selectors.split_first() else {
+            return Ok(variants[0].pattern.clone());
+        };
+        let depth = variants[0].keys.len() - selectors.len();
+        let Expression::Variable { name, function } = selector else {
+            return Err(ParseError::UnknownBlock("literal selector".to_owned()));
+        };
+        let typ = match function {
+            Some(function) if matches!(function.name.as_str(), "number" | "integer") => {
+                match function.option("select") {
+                    None | Some("plural") => "plural",
+                    Some("ordinal") => "selectordinal",
+                    Some(_) => "select",
+                }
+            }
+            _ => "select",
+        };
+
+        let mut keys: Vec<&str> = Vec::new();
+        for variant in variants {
+            if let Some(key) = &variant.keys[depth] {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+        }
+        let fallback: Vec<_> = variants
+            .iter()
+            .copied()
+            .filter(|variant| variant.keys[depth].is_none())
+            .collect();
+        if fallback.is_empty() {
+            return Err(ParseError::MissingOther(typ.to_owned()));
+        }
+
+        let mut pattern = format!("{{{}, {typ},", argument_name(name));
+        for key in keys {
+            let matching: Vec<_> = variants
+                .iter()
+                .copied()
+                .filter(|variant| variant.keys[depth].as_deref() == Some(key))
+                .chain(fallback.iter().copied())
+                .collect();
+            let key = match typ {
+                "select" => key.to_owned(),
+                _ if key.parse::<f64>().is_ok() => format!("={key}"),
+                _ => key.to_owned(),
+            };
+            write!(pattern, " {key} {{{}}}", self.select(inner, &matching)?).unwrap();
+        }
+        write!(pattern, " other {{{}}}}}", self.select(inner, &fallback)?).unwrap();
+        Ok(pattern)
+    }
+
+    fn placeholder(&self, expression: &Expression) -> Result<String, ParseError> {
+        let (name, function) = match expression {
+            Expression::Literal(text) => return Ok(MessageFormat::escape(text)),
+            Expression::Variable { name, function } => (argument_name(name), function),
+        };
+        let Some(function) = function else {
+            return Ok(format!("{{{name}}}"));
+        };
+        let placeholder = match function.name.as_str() {
+            "string" => format!("{{{name}}}"),
+            "number" | "integer" => match number_skeleton(function)? {
+                Some(skeleton) => format!("{{{name}, number, {skeleton}}}"),
+                None => format!("{{{name}, number}}"),
+            },
+            "date" | "time" => {
+                let style = function.option("style").unwrap_or("medium");
+                format!("{{{name}, {}, {style}}}", function.name)
+            }
+            "datetime" => match (function.option("dateStyle"), function.option("timeStyle")) {
+                (Some(style), None) => format!("{{{name}, date, {style}}}"),
+                (None, Some(style)) => format!("{{{name}, time, {style}}}"),
+                _ => return Err(ParseError::UnknownDateTimeStyle(function.name.clone())),
+            },
+            _ => {
+                return Err(ParseError::UnknownArgumentType(format!(
+                    ":{}",
+                    function.name
+                )))
+            }
+        };
+        Ok(placeholder)
+    }
+
+    fn literal(&mut self) -> Result<String, ParseError> {
+        if self.eat("|") {
+            let mut literal = String::new();
+            loop {
+                match self.rest().chars().next() {
+                    None => return Err(self.error("expected |")),
+                    Some('|') => {
+                        self.pos += 1;
+                        return Ok(literal);
+                    }
+                    Some('\\') => literal.push(self.escaped()?),
+                    Some(c) => {
+                        literal.push(c);
+                        self.pos += c.len_utf8();
+                    }
+                }
+            }
+        }
+        // names and numbers
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a literal"));
+        }
+        let literal = self.rest()[..len].to_owned();
+        self.pos += len;
+        Ok(literal)
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+            .unwrap_or(self.rest().len());
+        if !self
+            .rest()
+            .starts_with(|c: char| c.is_alphabetic() || c == '_')
+        {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..len].to_owned();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn escaped(&mut self) -> Result<char, ParseError> {
+        self.pos += 1;
+        match self.rest().chars().next() {
+            Some(c @ ('\\' | '{' | '|' | '}')) => {
+                self.pos += 1;
+                Ok(c)
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.pos..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {token}")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::InvalidMf2(self.pos, message.to_owned())
+    }
+}
+
+/// Returns the skeleton of the options of `:number` and `:integer`, if any.
+fn number_skeleton(function: &Function) -> Result<Option<String>, ParseError> {
+    let mut stems = Vec::new();
+    if function.name == "integer" {
+        stems.push("precision-integer".to_owned());
+    }
+    let mut fraction_digits = (None, None);
+    for (option, value) in &function.options {
+        let digits = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| ParseError::UnknownNumberStyle(format!("{option}={value}")))
+        };
+        match (option.as_str(), value.as_str()) {
+            ("select", _) => {}
+            ("minimumFractionDigits", _) => fraction_digits.0 = Some(digits()?),
+            ("maximumFractionDigits", _) => fraction_digits.1 = Some(digits()?),
+            ("useGrouping", "never") => stems.push("group-off".to_owned()),
+            ("useGrouping", "always") => stems.push("group-on-aligned".to_owned()),
+            ("useGrouping", "min2") => stems.push("group-min2".to_owned()),
+            ("useGrouping", "auto") => stems.push("group-auto".to_owned()),
+            ("notation", "compact") => {
+                let display = function.option("compactDisplay").unwrap_or("short");
+                stems.push(format!("compact-{display}"));
+            }
+            ("notation", "standard") | ("compactDisplay", _) => {}
+            _ => return Err(ParseError::UnknownNumberStyle(format!("{option}={value}"))),
+        }
+    }
+    if let (Some(_), _) | (_, Some(_)) = fraction_digits {
+        let min = fraction_digits.0.unwrap_or(0);
+        let max = fraction_digits.1.unwrap_or(min.max(3)).max(min);
+        stems.push(format!(".{}{}", "0".repeat(min), "#".repeat(max - min)));
+    }
+    Ok((!stems.is_empty()).then(|| format!("::{}", stems.join(" "))))
+}
+
+/// Argument names are words, so e.g. `$user-name` becomes `user_name`.
+fn argument_name(variable: &str) -> String {
+    variable.replace(['-', '.'], "_")
+}
@@ -0,0 +1,824 @@
+use std::fmt;
+
+use fixed_decimal::{FixedDecimal, Sign};
+use icu::locid::{
+    extensions::unicode::{key, Value},
+    Locale,
+};
+use icu_decimal::{options::GroupingStrategy, FixedDecimalFormatter};
+use icu_experimental::dimension::{
+    currency::formatter::CurrencyCode, provider::percent::PercentEssentialsV1Marker,
+};
+#[cfg(feature = "buffer_provider")]
+use icu_provider::AsDeserializingBufferProvider;
+use icu_provider::{
+    AsDowncastingAnyProvider, DataError, DataLocale, DataPayload, DataProvider, DataRequest,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tinystr::TinyAsciiStr;
+use writeable::Writeable;
+
+use crate::{provider::DataSource, RenderContext};
+
+/// Options of a `{N, number, style}` argument.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct NumberOptions {
+    precision: Option<Precision>,
+    grouping: Option<GroupingStrategy>,
+    notation: Notation,
+    numbering_system: Option<Value>,
+    currency: Option<Currency>,
+    /// Multiplied by 100 and formatted with the percent pattern of the locale
+    percent: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Currency {
+    /// Currency of the region of the locale, see
+    /// [`RenderContext::with_default_currency`]
+    Default,
+    Code(TinyAsciiStr<3>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precision {
+    Unlimited,
+    Integer,
+    /// Min and max number of fraction digits; max is unbounded if `None`
+    Fraction(i16, Option<i16>),
+    /// Min and max number of significant digits; max is unbounded if `None`
+    Significant(i16, Option<i16>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) enum Notation {
+    #[default]
+    Simple,
+    CompactShort,
+    CompactLong,
+}
+
+impl NumberOptions {
+    /// Parses the style of a number argument.
+    ///
+    /// Supported styles:
+    ///
+    /// * significant digits in ICU pattern notation: each `@` is a required and
+    ///   each trailing `#` an optional significant digit, e.g. `@@#`.
+    /// * `integer` for a number rounded to an integer, half to even.
+    /// * `currency` for an amount in the currency of the locale's region.
+    /// * `percent` for a fraction multiplied by 100 with a percent sign, e.g.
+    ///   `0.25` as "25%", rounded to an integer unless a precision is set.
+    /// * ICU number skeletons prefixed by `::`, e.g. `::compact-short` or
+    ///   `::.00 group-off`. See [`NumberOptions::parse_skeleton`].
+    pub(crate) fn parse_style(style: &str) -> Option<Self> {
+        let mut options = Self::default();
+        if let Some(skeleton) = style.strip_prefix("::") {
+            options.parse_skeleton(skeleton)?;
+            // compact notation has no currency or percent patterns
+            let unit = options.currency.is_some() || options.percent;
+            if unit && options.notation != Notation::Simple
+                || options.currency.is_some() && options.percent
+            {
+                return None;
+            }
+        } else if style == "integer" {
+            options.precision = Some(Precision::Integer);
+        } else if style == "currency" {
+            options.currency = Some(Currency::Default);
+        } else if style == "percent" {
+            options.percent = true;
+        } else if !style.is_empty() {
+            options.precision = Some(parse_significant_digits(style)?);
+        }
+        Some(options)
+    }
+
+    /// Parses the stems of an ICU number skeleton.
+    ///
+    /// Supported stems (long and concise forms):
+    ///
+    /// * precision: `precision-integer`, `precision-unlimited`, `.00##`, `.0+`,
+    ///   `@@#`, `@@+`
+    /// * grouping: `group-off` (`,_`), `group-min2` (`,?`), `group-auto`,
+    ///   `group-on-aligned` (`,!`), `group-thousands` (`,=`)
+    /// * notation: `notation-simple`, `compact-short` (`K`), `compact-long` (`KK`)
+    /// * numbering system: `numbering-system/<nu>`, `latin`
+    /// * currency: `currency/<ISO code>`, or `currency` for the currency of the
+    ///   locale's region
+    /// * percent: `%x100`, i.e. the `percent` style
+    ///
+    /// Compact notation does its own rounding, so precision stems have no
+    /// effect on it.
+    fn parse_skeleton(&mut self, skeleton: &str) -> Option<()> {
+        for stem in skeleton.split_whitespace() {
+            match stem {
+                "precision-integer" => self.precision = Some(Precision::Integer),
+                "precision-unlimited" => self.precision = Some(Precision::Unlimited),
+                "group-off" | ",_" => self.grouping = Some(GroupingStrategy::Never),
+                "group-min2" | ",?" => self.grouping = Some(GroupingStrategy::Min2),
+                "group-auto" => self.grouping = Some(GroupingStrategy::Auto),
+                "group-on-aligned" | ",!" | "group-thousands" | ",=" => {
+                    self.grouping = Some(GroupingStrategy::Always)
+                }
+                "notation-simple" => self.notation = Notation::Simple,
+                "compact-short" | "K" => self.notation = Notation::CompactShort,
+                "compact-long" | "KK" => self.notation = Notation::CompactLong,
+                "latin" => self.numbering_system = Some("latn".parse().ok()?),
+                "currency" => self.currency = Some(Currency::Default),
+                "%x100" => self.percent = true,
+                _ => {
+                    if let Some(numbering_system) = stem.strip_prefix("numbering-system/") {
+                        self.numbering_system = Some(numbering_system.parse().ok()?);
+                    } else if let Some(code) = stem.strip_prefix("currency/") {
+                        self.currency = Some(Currency::Code(parse_currency_code(code)?));
+                    } else if stem.starts_with('.') {
+                        self.precision = Some(parse_fraction_digits(stem)?);
+                    } else {
+                        self.precision = Some(parse_significant_digits(stem)?);
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Returns an ICU number skeleton which is parsed to these options.
+    pub(crate) fn to_skeleton(&self) -> String {
+        let mut stems = Vec::new();
+        match self.precision {
+            None => {}
+            Some(Precision::Unlimited) => stems.push("precision-unlimited".to_owned()),
+            Some(Precision::Integer) => stems.push("precision-integer".to_owned()),
+            Some(Precision::Fraction(min, max)) => {
+                stems.push(format!(".{}", digits_stem('0', min, max)));
+            }
+            Some(Precision::Significant(min, max)) => stems.push(digits_stem('@', min, max)),
+        }
+        match self.grouping {
+            Some(GroupingStrategy::Never) => stems.push("group-off".to_owned()),
+            Some(GroupingStrategy::Min2) => stems.push("group-min2".to_owned()),
+            Some(GroupingStrategy::Always) => stems.push("group-on-aligned".to_owned()),
+            Some(_) => stems.push("group-auto".to_owned()),
+            None => {}
+        }
+        match self.notation {
+            Notation::Simple => {}
+            Notation::CompactShort => stems.push("compact-short".to_owned()),
+            Notation::CompactLong => stems.push("compact-long".to_owned()),
+        }
+        if let Some(numbering_system) = &self.numbering_system {
+            stems.push(format!("numbering-system/{numbering_system}"));
+        }
+        match self.currency {
+            Some(Currency::Default) => stems.push("currency".to_owned()),
+            Some(Currency::Code(code)) => stems.push(format!("currency/{code}")),
+            None => {}
+        }
+        if self.percent {
+            stems.push("%x100".to_owned());
+        }
+        format!("::{}", stems.join(" "))
+    }
+
+    /// Returns these options with the ones set in `overrides` replaced.
+    pub(crate) fn overridden_by(&self, overrides: &NumberOptions) -> Self {
+        Self {
+            precision: overrides.precision.or(self.precision),
+            grouping: overrides.grouping.or(self.grouping),
+            notation: self.notation,
+            numbering_system: overrides
+                .numbering_system
+                .clone()
+                .or_else(|| self.numbering_system.clone()),
+            currency: self.currency,
+            percent: self.percent,
+        }
+    }
+
+    pub(crate) fn set_min_fraction_digits(&mut self, min: i16) {
+        let max = match self.precision {
+            Some(Precision::Fraction(_, max)) => max.map(|max| max.max(min)),
+            _ => None,
+        };
+        self.precision = Some(Precision::Fraction(min, max));
+    }
+
+    pub(crate) fn set_max_fraction_digits(&mut self, max: i16) {
+        let min = match self.precision {
+            Some(Precision::Fraction(min, _)) => min.min(max),
+            _ => 0,
+        };
+        self.precision = Some(Precision::Fraction(min, Some(max)));
+    }
+
+    pub(crate) fn set_min_significant_digits(&mut self, min: i16) {
+        let max = match self.precision {
+            Some(Precision::Significant(_, max)) => max.map(|max| max.max(min)),
+            _ => None,
+        };
+        self.precision = Some(Precision::Significant(min, max));
+    }
+
+    pub(crate) fn set_max_significant_digits(&mut self, max: i16) {
+        let min = match self.precision {
+            Some(Precision::Significant(min, _)) => min.min(max),
+            _ => 1,
+        };
+        self.precision = Some(Precision::Significant(min, Some(max)));
+    }
+
+    pub(crate) fn set_without_grouping(&mut self) {
+        self.grouping = Some(GroupingStrategy::Never);
+    }
+
+    pub(crate) fn format(&self, value: FixedDecimal, context: &RenderContext) -> String {
+        self.format_parts(value, context).to_string()
+    }
+
+    /// Formats a range of numbers, e.g. "$10 – $20" or "10–20\u{a0}€", see
+    /// [`format_range`].
+    pub(crate) fn format_range(
+        &self,
+        start: FixedDecimal,
+        end: FixedDecimal,
+        context: &RenderContext,
+    ) -> String {
+        format_range(
+            self.format_parts(start, context),
+            self.format_parts(end, context),
+            context.locale(),
+        )
+    }
+
+    pub(crate) fn format_parts(
+        &self,
+        mut value: FixedDecimal,
+        context: &RenderContext,
+    ) -> FormattedNumber {
+        match self.notation {
+            Notation::Simple => {
+                let currency = self.currency.map(|currency| match currency {
+                    Currency::Default => context.default_currency(),
+                    Currency::Code(code) => code,
+                });
+                if self.percent {
+                    value.multiply_pow10(2);
+                    value.trim_start();
+                }
+                match (currency, self.precision) {
+                    (Some(currency), None) => {
+                        let digits = currency_digits(currency);
+                        value.half_even(-digits);
+                        value.pad_end(-digits);
+                    }
+                    (None, None) if self.percent => value.half_even(0),
+                    _ => self.apply_precision(&mut value),
+                }
+                let decimal_formatter;
+                let fdf = if self.grouping.is_none() && self.numbering_system.is_none() {
+                    context.fixed_decimal_formatter()
+                } else {
+                    decimal_formatter = context.decimal_formatter(
+                        self.numbering_system.as_ref(),
+                        self.grouping.unwrap_or_default(),
+                    );
+                    &decimal_formatter
+                };
+                let (sign, digits) = format_unsigned(value, fdf);
+                let (prefix, suffix) = match currency {
+                    Some(currency) => {
+                        currency_affixes(currency, self.numbering_system.as_ref(), context)
+                    }
+                    None if self.percent => {
+                        let pattern = context.percent_pattern(self.numbering_system.as_ref());
+                        (pattern.prefix.clone(), pattern.suffix.clone())
+                    }
+                    None => Default::default(),
+                };
+                FormattedNumber {
+                    sign,
+                    prefix,
+                    digits,
+                    suffix,
+                }
+            }
+            Notation::CompactShort | Notation::CompactLong => {
+                // the default of compact numbers, e.g. "1000" but "10,000"
+                let grouping = self.grouping.unwrap_or(GroupingStrategy::Min2);
+                let numbering_system = self.numbering_system.as_ref();
+                let formatter =
+                    context.compact_decimal_formatter(self.notation, numbering_system, grouping);
+                let formatted = formatter.format_fixed_decimal(value);
+                let text = formatted.to_string();
+                // the significand is formatted with the same data, and the
+                // pattern around it is the compact affix, e.g. "K"
+                let significand = context
+                    .decimal_formatter(numbering_system, grouping)
+                    .format_to_string(formatted.get_compact_decimal().significand());
+                match text.find(&significand) {
+                    Some(start) => FormattedNumber {
+                        sign: String::new(),
+                        prefix: text[..start].to_owned(),
+                        digits: significand.clone(),
+                        suffix: text[start + significand.len()..].to_owned(),
+                    },
+                    // e.g. a pattern without a number
+                    None => FormattedNumber {
+                        digits: text,
+                        ..Default::default()
+                    },
+                }
+            }
+        }
+    }
+
+    pub(crate) fn apply_precision(&self, value: &mut FixedDecimal) {
+        match self.precision {
+            None | Some(Precision::Unlimited) => {}
+            Some(Precision::Integer) => value.half_even(0),
+            Some(Precision::Fraction(min, max)) => {
+                if let Some(max) = max {
+                    value.half_even(-max);
+                }
+                value.pad_end(-min);
+            }
+            Some(Precision::Significant(min, max)) => {
+                let msd = value.nonzero_magnitude_start();
+                if let Some(max) = max {
+                    value.half_even(msd - max + 1);
+                }
+                value.pad_end(msd - min + 1);
+            }
+        }
+    }
+}
+
+/// Returns the text of the currency pattern of the locale before and after
+/// the amount, e.g. "$" and "" in English.
+fn currency_affixes(
+    currency: TinyAsciiStr<3>,
+    numbering_system: Option<&Value>,
+    context: &RenderContext,
+) -> (String, String) {
+    // the currency formatter does not localize the digits, so a zero is
+    // formatted and split at
+    let pattern = context
+        .currency_formatter(numbering_system)
+        .format_fixed_decimal(&FixedDecimal::from(0), CurrencyCode(currency))
+        .write_to_string()
+        .into_owned();
+    let (prefix, suffix) = pattern.split_once('0').unwrap_or((&pattern, ""));
+    (prefix.to_owned(), suffix.to_owned())
+}
+
+/// A formatted number: the digits and the text of its currency, percent or
+/// compact pattern around them, e.g. "-", "$", "5.00" and "" of "-$5.00"
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct FormattedNumber {
+    /// Minus sign of a negative number, which goes before the pattern
+    sign: String,
+    prefix: String,
+    digits: String,
+    suffix: String,
+}
+
+impl FormattedNumber {
+    /// Text which is not a number, e.g. the bound of a string range
+    pub(crate) fn text(text: String) -> Self {
+        Self {
+            digits: text,
+            ..Default::default()
+        }
+    }
+
+    fn has_affixes(&self) -> bool {
+        !(self.sign.is_empty() && self.prefix.is_empty() && self.suffix.is_empty())
+    }
+}
+
+impl fmt::Display for FormattedNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            sign,
+            prefix,
+            digits,
+            suffix,
+        } = self;
+        write!(f, "{sign}{prefix}{digits}{suffix}")
+    }
+}
+
+/// Joins two formatted numbers with the CLDR range pattern of the language,
+/// following the default ("auto") range formatting of ICU:
+///
+/// * equal bounds are formatted once, e.g. "5" instead of "5–5".
+/// * a currency, percent or compact pattern shared by both bounds is written
+///   once if its text is longer than one character, e.g. "10–20\u{a0}€" or
+///   "3–5 thousand", but "$10 – $20". Units are not supported by number
+///   styles, a unit in the message, like "{D, number} km", is shared anyway.
+/// * the range separator is surrounded by spaces if a sign or pattern text is
+///   repeated, so that e.g. "-5 – -3" does not read as "-5–-3".
+pub(crate) fn format_range(
+    start: FormattedNumber,
+    end: FormattedNumber,
+    locale: &Locale,
+) -> String {
+    if start == end {
+        return start.to_string();
+    }
+    let separator = range_separator(locale);
+    let affix_len = start.prefix.chars().count() + start.suffix.chars().count();
+    let shared = start.prefix == end.prefix && start.suffix == end.suffix && affix_len > 1;
+    if shared {
+        let FormattedNumber { prefix, suffix, .. } = &start;
+        let (start_sign, end_sign) = (&start.sign, &end.sign);
+        let (start, end) = (&start.digits, &end.digits);
+        format!("{prefix}{start_sign}{start}{separator}{end_sign}{end}{suffix}")
+    } else if start.has_affixes() || end.has_affixes() {
+        format!("{start} {separator} {end}")
+    } else {
+        format!("{start}{separator}{end}")
+    }
+}
+
+/// Separator of the CLDR number range pattern `{0}–{1}` of a language.
+///
+/// ICU4X has no range data yet, so this is a snapshot of the languages whose
+/// pattern differs from the root pattern in CLDR 45.
+fn range_separator(locale: &Locale) -> &'static str {
+    match locale.id.language.as_str() {
+        "ja" => "～",
+        "ko" => "~",
+        "zh" => "-",
+        _ => "–",
+    }
+}
+
+/// Returns the minus sign of a negative number and the formatted absolute
+/// value, so that the sign goes before a percent or currency pattern, as in
+/// the negative patterns CLDR derives from them, e.g. "-$5.00".
+fn format_unsigned(mut value: FixedDecimal, fdf: &FixedDecimalFormatter) -> (String, String) {
+    let sign = value.sign();
+    value.set_sign(Sign::None);
+    let number = fdf.format_to_string(&value);
+    if sign != Sign::Negative {
+        return (String::new(), number);
+    }
+    value.set_sign(sign);
+    let signed = fdf.format_to_string(&value);
+    match signed.strip_suffix(number.as_str()) {
+        Some(sign) => (sign.to_owned(), number),
+        // e.g. a minus sign after the number
+        None => (String::new(), signed),
+    }
+}
+
+/// Text of the CLDR percent pattern of a locale before and after the number,
+/// e.g. `#,##0\u{a0}%` in German
+#[derive(Debug)]
+pub(crate) struct PercentPattern {
+    prefix: String,
+    suffix: String,
+}
+
+impl PercentPattern {
+    /// Loads the pattern from the percent data of ICU4X, which has no percent
+    /// formatter yet.
+    pub(crate) fn load(data: &DataSource, locale: &Locale) -> Result<Self, DataError> {
+        let locale = DataLocale::from(locale);
+        let request = DataRequest {
+            locale: &locale,
+            metadata: Default::default(),
+        };
+        let payload: DataPayload<PercentEssentialsV1Marker> = match data {
+            DataSource::Compiled => icu_experimental::provider::Baked.load(request),
+            DataSource::Any(provider) => provider.as_downcasting().load(request),
+            #[cfg(feature = "buffer_provider")]
+            DataSource::Buffer(provider) => provider.as_deserializing().load(request),
+        }?
+        .take_payload()?;
+        let essentials = payload.get();
+        let (prefix, rest) = essentials.standard.split_at(essentials.number_index.into());
+        let suffix = rest.trim_start_matches(['#', ',', '.', '0']);
+        let symbol = &essentials.percent_sign_symbol;
+        Ok(Self {
+            prefix: prefix.replace('%', symbol),
+            suffix: suffix.replace('%', symbol),
+        })
+    }
+}
+
+/// Parses an ISO 4217 currency code, e.g. `EUR`.
+pub(crate) fn parse_currency_code(code: &str) -> Option<TinyAsciiStr<3>> {
+    let code = TinyAsciiStr::<3>::from_str(code).ok()?;
+    code.is_ascii_alphabetic_uppercase().then_some(code)
+}
+
+/// Number of fraction digits of amounts of a currency, i.e. its ISO 4217
+/// minor unit.
+fn currency_digits(currency: TinyAsciiStr<3>) -> i16 {
+    match currency.as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    }
+}
+
+/// Currency of a region, e.g. `EUR` for `DE`. Regions without a currency of
+/// their own, like `AQ`, have none.
+///
+/// ICU4X has no region currency data, so this is a snapshot of the current
+/// tender of the CLDR supplemental currency data as of 2026, which has to be
+/// updated when a region changes its currency (e.g. `BG` to `EUR` in 2026).
+pub(crate) fn region_currency(region: &str) -> Option<&'static str> {
+    let currency = match region {
+        "AD" | "AT" | "AX" | "BE" | "BG" | "BL" | "CY" | "DE" | "EE" | "ES" | "FI" | "FR"
+        | "GF" | "GP" | "GR" | "HR" | "IE" | "IT" | "LT" | "LU" | "LV" | "MC" | "ME" | "MF"
+        | "MQ" | "MT" | "NL" | "PM" | "PT" | "RE" | "SI" | "SK" | "SM" | "TF" | "VA" | "XK"
+        | "YT" => "EUR",
+        "AG" | "AI" | "DM" | "GD" | "KN" | "LC" | "MS" | "VC" => "XCD",
+        "AS" | "BQ" | "EC" | "FM" | "GU" | "IO" | "MH" | "MP" | "PR" | "PW" | "SV" | "TC"
+        | "TL" | "UM" | "US" | "VG" | "VI" => "USD",
+        "AU" | "CC" | "CX" | "HM" | "KI" | "NF" | "NR" | "TV" => "AUD",
+        "BF" | "BJ" | "CI" | "GW" | "ML" | "NE" | "SN" | "TG" => "XOF",
+        "BV" | "NO" | "SJ" => "NOK",
+        "CF" | "CG" | "CM" | "GA" | "GQ" | "TD" => "XAF",
+        "CH" | "LI" => "CHF",
+        "CK" | "NU" | "NZ" | "PN" | "TK" => "NZD",
+        "CW" | "SX" => "XCG",
+        "DK" | "FO" | "GL" => "DKK",
+        "EH" | "MA" => "MAD",
+        "GB" | "GG" | "GS" | "IM" | "JE" => "GBP",
+        "IL" | "PS" => "ILS",
+        "NC" | "PF" | "WF" => "XPF",
+        "AE" => "AED",
+        "AF" => "AFN",
+        "AL" => "ALL",
+        "AM" => "AMD",
+        "AO" => "AOA",
+        "AR" => "ARS",
+        "AW" => "AWG",
+        "AZ" => "AZN",
+        "BA" => "BAM",
+        "BB" => "BBD",
+        "BD" => "BDT",
+        "BH" => "BHD",
+        "BI" => "BIF",
+        "BM" => "BMD",
+        "BN" => "BND",
+        "BO" => "BOB",
+        "BR" => "BRL",
+        "BS" => "BSD",
+        "BT" => "BTN",
+        "BW" => "BWP",
+        "BY" => "BYN",
+        "BZ" => "BZD",
+        "CA" => "CAD",
+        "CD" => "CDF",
+        "CL" => "CLP",
+        "CN" => "CNY",
+        "CO" => "COP",
+        "CR" => "CRC",
+        "CU" => "CUP",
+        "CV" => "CVE",
+        "CZ" => "CZK",
+        "DJ" => "DJF",
+        "DO" => "DOP",
+        "DZ" => "DZD",
+        "EG" => "EGP",
+        "ER" => "ERN",
+        "ET" => "ETB",
+        "FJ" => "FJD",
+        "FK" => "FKP",
+        "GE" => "GEL",
+        "GH" => "GHS",
+        "GI" => "GIP",
+        "GM" => "GMD",
+        "GN" => "GNF",
+        "GT" => "GTQ",
+        "GY" => "GYD",
+        "HK" => "HKD",
+        "HN" => "HNL",
+        "HT" => "HTG",
+        "HU" => "HUF",
+        "ID" => "IDR",
+        "IN" => "INR",
+        "IQ" => "IQD",
+        "IR" => "IRR",
+        "IS" => "ISK",
+        "JM" => "JMD",
+        "JO" => "JOD",
+        "JP" => "JPY",
+        "KE" => "KES",
+        "KG" => "KGS",
+        "KH" => "KHR",
+        "KM" => "KMF",
+        "KP" => "KPW",
+        "KR" => "KRW",
+        "KW" => "KWD",
+        "KY" => "KYD",
+        "KZ" => "KZT",
+        "LA" => "LAK",
+        "LB" => "LBP",
+        "LK" => "LKR",
+        "LR" => "LRD",
+        "LS" => "LSL",
+        "LY" => "LYD",
+        "MD" => "MDL",
+        "MG" => "MGA",
+        "MK" => "MKD",
+        "MM" => "MMK",
+        "MN" => "MNT",
+        "MO" => "MOP",
+        "MR" => "MRU",
+        "MU" => "MUR",
+        "MV" => "MVR",
+        "MW" => "MWK",
+        "MX" => "MXN",
+        "MY" => "MYR",
+        "MZ" => "MZN",
+        "NA" => "NAD",
+        "NG" => "NGN",
+        "NI" => "NIO",
+        "NP" => "NPR",
+        "OM" => "OMR",
+        "PA" => "PAB",
+        "PE" => "PEN",
+        "PG" => "PGK",
+        "PH" => "PHP",
+        "PK" => "PKR",
+        "PL" => "PLN",
+        "PY" => "PYG",
+        "QA" => "QAR",
+        "RO" => "RON",
+        "RS" => "RSD",
+        "RU" => "RUB",
+        "RW" => "RWF",
+        "SA" => "SAR",
+        "SB" => "SBD",
+        "SC" => "SCR",
+        "SD" => "SDG",
+        "SE" => "SEK",
+        "SG" => "SGD",
+        "SH" => "SHP",
+        "SL" => "SLE",
+        "SO" => "SOS",
+        "SR" => "SRD",
+        "SS" => "SSP",
+        "ST" => "STN",
+        "SY" => "SYP",
+        "SZ" => "SZL",
+        "TH" => "THB",
+        "TJ" => "TJS",
+        "TM" => "TMT",
+        "TN" => "TND",
+        "TO" => "TOP",
+        "TR" => "TRY",
+        "TT" => "TTD",
+        "TW" => "TWD",
+        "TZ" => "TZS",
+        "UA" => "UAH",
+        "UG" => "UGX",
+        "UY" => "UYU",
+        "UZ" => "UZS",
+        "VE" => "VES",
+        "VN" => "VND",
+        "VU" => "VUV",
+        "WS" => "WST",
+        "YE" => "YER",
+        "ZA" => "ZAR",
+        "ZM" => "ZMW",
+        "ZW" => "ZWG",
+        _ => return None,
+    };
+    Some(currency)
+}
+
+/// Digits and separators of a locale, read from numbers formatted with its
+/// data, so that parsing matches formatting
+#[derive(Debug)]
+pub(crate) struct NumberSymbols {
+    digits: [char; 10],
+    minus: String,
+    decimal: String,
+    grouping: String,
+}
+
+impl NumberSymbols {
+    pub(crate) fn new(fdf: &FixedDecimalFormatter) -> Self {
+        let format = |s: &str| fdf.format_to_string(&s.parse().expect("valid number"));
+        let digits = std::array::from_fn(|digit| {
+            let digit = format(&digit.to_string());
+            digit.chars().next().expect("formatted digit")
+        });
+        // the first text between digits
+        let separator = |s: &str| -> String {
+            s.chars()
+                .skip_while(|c| digits.contains(c))
+                .take_while(|c| !digits.contains(c))
+                .collect()
+        };
+        Self {
+            digits,
+            minus: format("-1").replace(digits[1], ""),
+            decimal: separator(&format("1.5")),
+            // 1,234,567 is grouped even by locales which group 5 digits and more
+            grouping: separator(&format("1234567")),
+        }
+    }
+
+    /// See [`RenderContext::parse_number`].
+    pub(crate) fn parse(&self, input: &str) -> Option<FixedDecimal> {
+        let input = input.trim();
+        let (negative, mut rest) = [self.minus.as_str(), "-", "\u{2212}"]
+            .into_iter()
+            .filter(|minus| !minus.is_empty())
+            .find_map(|minus| Some((true, input.strip_prefix(minus)?)))
+            .unwrap_or((false, input));
+
+        let mut normalized = String::from(if negative { "-" } else { "" });
+        let mut fraction = false;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix(self.decimal.as_str()) {
+                if fraction {
+                    return None;
+                }
+                fraction = true;
+                normalized.push('.');
+                rest = after;
+                continue;
+            }
+            // spaces stand in for the (narrow) no-break spaces of e.g. French
+            let grouping = !self.grouping.is_empty()
+                && (rest.starts_with(self.grouping.as_str())
+                    || c == ' ' && self.grouping.chars().all(char::is_whitespace));
+            if grouping {
+                if fraction {
+                    return None;
+                }
+                rest = &rest[if c == ' ' { 1 } else { self.grouping.len() }..];
+                continue;
+            }
+            let digit = match self.digits.iter().position(|&digit| digit == c) {
+                Some(digit) => char::from(b'0' + digit as u8),
+                None if c.is_ascii_digit() => c,
+                None => return None,
+            };
+            normalized.push(digit);
+            rest = &rest[c.len_utf8()..];
+        }
+        normalized.parse().ok()
+    }
+}
+
+/// Returns a copy of the locale with its numbering system replaced.
+pub(crate) fn with_numbering_system(locale: &Locale, numbering_system: &Value) -> Locale {
+    let mut locale = locale.clone();
+    locale
+        .extensions
+        .unicode
+        .keywords
+        .set(key!("nu"), numbering_system.clone());
+    locale
+}
+
+/// `@@#` (min 2, max 3) or `@@+` (min 2, unbounded)
+fn parse_significant_digits(s: &str) -> Option<Precision> {
+    static SIGNIFICANT_DIGITS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(@+)(#*|\+)$").unwrap());
+
+    let caps = SIGNIFICANT_DIGITS_RE.captures(s)?;
+    let (min, max) = parse_min_max(&caps[1], &caps[2])?;
+    Some(Precision::Significant(min, max))
+}
+
+/// `.0#` (min 1, max 2) or `.0+` (min 1, unbounded)
+fn parse_fraction_digits(s: &str) -> Option<Precision> {
+    static FRACTION_DIGITS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\.(0*)(#*|\+)$").unwrap());
+
+    let caps = FRACTION_DIGITS_RE.captures(s)?;
+    let (min, max) = parse_min_max(&caps[1], &caps[2])?;
+    Some(Precision::Fraction(min, max))
+}
+
+fn parse_min_max(required: &str, optional: &str) -> Option<(i16, Option<i16>)> {
+    let min = i16::try_from(required.len()).ok()?;
+    if optional == "+" {
+        return Some((min, None));
+    }
+    let max = min.checked_add(i16::try_from(optional.len()).ok()?)?;
+    Some((min, Some(max)))
+}
+
+/// Inverse of `parse_min_max`, e.g. `00#` or `@@+`
+fn digits_stem(required: char, min: i16, max: Option<i16>) -> String {
+    let mut stem: String = (0..min).map(|_| required).collect();
+    match max {
+        Some(max) => stem.extend((min..max).map(|_| '#')),
+        None => stem.push('+'),
+    }
+    stem
+}
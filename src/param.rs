@@ -1,10 +1,24 @@
-use std::{borrow::Cow, fmt, hash};
+use std::{
+    cmp::Ordering,
+    fmt, hash, ops,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use icu::locid::Locale;
-use icu_decimal::FixedDecimalFormatter;
+use fixed_decimal::{FixedDecimal, Sign};
+use icu::{
+    calendar::{AsCalendar, Date, DateTime, Iso},
+    datetime::options::length,
+    plurals::PluralOperands,
+};
 use ordered_float::OrderedFloat;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+use crate::{
+    number::{format_range, FormattedNumber, NumberOptions},
+    MessageFormat, RenderContext,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ParamValue {
     inner: ParamValueInner,
 }
@@ -15,54 +29,477 @@ impl From<ParamValueInner> for ParamValue {
     }
 }
 
-#[derive(Debug, Eq)]
+/// Kind of value of an argument, see [`crate::MessagePart::Argument`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParamKind {
+    Text,
+    /// Integer or decimal number, incl. `#` of plural and ordinal blocks
+    Number,
+    /// Numeric range, see [`ParamValue::range`]
+    Range,
+    Date,
+    DateTime,
+    /// Sub-message, see [`ParamValue::message`]
+    Message,
+}
+
+#[derive(Debug, Clone)]
 enum ParamValueInner {
     Int(i64),
     Dec(OrderedFloat<f64>),
-    String(Cow<'static, str>),
+    /// Exact decimal, e.g. an amount of money
+    Decimal(FixedDecimal),
+    String(SharedStr),
+    Range(Box<ParamValue>, Box<ParamValue>),
+    Date(Date<Iso>),
+    DateTime(DateTime<Iso>),
+    /// Missing optional value, see [`crate::NoneParam`]
+    None,
+    /// Number with formatting options and plural operands set at call time
+    Number(Box<ParamValue>, NumberOptions, Option<PluralOperands>),
+    /// Sub-message pattern with its bound parameters
+    Message(Arc<str>, Arc<[(String, ParamValue)]>),
+}
+
+/// String which is cloned without copying it
+#[derive(Debug, Clone)]
+enum SharedStr {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+impl ops::Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            SharedStr::Static(s) => s,
+            SharedStr::Shared(s) => s,
+        }
+    }
 }
 
 impl PartialEq for ParamValueInner {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            (Self::Number(a, ..), b) | (b, Self::Number(a, ..)) => a.inner == *b,
             (Self::Int(a), Self::Int(b)) => a == b,
             (Self::Dec(a), Self::Dec(b)) => a == b,
-            (Self::String(a), Self::String(b)) => a == b,
-            (Self::Int(a), Self::Dec(b)) => Some(*a) == as_integer(b.into_inner()),
-            (Self::Dec(a), Self::Int(b)) => as_integer(a.into_inner()) == Some(*b),
-            _ => false,
+            (Self::String(a), Self::String(b)) => **a == **b,
+            (Self::Range(a0, a1), Self::Range(b0, b1)) => a0 == b0 && a1 == b1,
+            (Self::Date(a), Self::Date(b)) => a == b,
+            (Self::DateTime(a), Self::DateTime(b)) => a == b,
+            (Self::Message(a, a_params), Self::Message(b, b_params)) => {
+                a == b && a_params == b_params
+            }
+            (Self::None, Self::None) => true,
+            (Self::Decimal(a), Self::Decimal(b)) => trimmed(a) == trimmed(b),
+            // numbers of different types compare exactly, like in `cmp`
+            (a, b) => match (a.numeric_key(), b.numeric_key()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Eq for ParamValueInner {}
+
+/// Orders values of different types by type (none, numbers, strings, ranges,
+/// dates, date times, messages), and numbers by value across their types, so
+/// that values which are equal compare as equal.
+impl Ord for ParamValueInner {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a, ..), b) => a.inner.cmp(b),
+            (a, Self::Number(b, ..)) => a.cmp(&b.inner),
+            (Self::String(a), Self::String(b)) => (**a).cmp(&**b),
+            (Self::Range(a0, a1), Self::Range(b0, b1)) => (a0, a1).cmp(&(b0, b1)),
+            (Self::Date(a), Self::Date(b)) => date_key(a).cmp(&date_key(b)),
+            (Self::DateTime(a), Self::DateTime(b)) => date_time_key(a).cmp(&date_time_key(b)),
+            (Self::Message(a, a_params), Self::Message(b, b_params)) => {
+                (a, a_params).cmp(&(b, b_params))
+            }
+            (a, b) => match (a.numeric_key(), b.numeric_key()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => a.type_rank().cmp(&b.type_rank()),
+            },
+        }
+    }
+}
+
+impl PartialOrd for ParamValueInner {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ParamValueInner {
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Int(_) | Self::Dec(_) | Self::Decimal(_) => 1,
+            Self::String(_) => 2,
+            Self::Range(..) => 3,
+            Self::Date(_) => 4,
+            Self::DateTime(_) => 5,
+            Self::Message(..) => 6,
+            Self::Number(value, ..) => value.inner.type_rank(),
+        }
+    }
+
+    fn numeric_key(&self) -> Option<NumericKey> {
+        match self {
+            Self::Int(n) => Some(NumericKey::Finite(OrdDecimal((*n).into()))),
+            Self::Dec(x) if x.is_nan() => Some(NumericKey::NaN),
+            Self::Dec(x) if x.is_infinite() && x.is_sign_negative() => {
+                Some(NumericKey::NegativeInfinity)
+            }
+            Self::Dec(x) if x.is_infinite() => Some(NumericKey::Infinity),
+            Self::Dec(x) => Some(NumericKey::Finite(OrdDecimal(
+                float_decimal(x.into_inner()).unwrap_or_default(),
+            ))),
+            Self::Decimal(x) => Some(NumericKey::Finite(OrdDecimal(x.clone()))),
+            _ => None,
+        }
+    }
+}
+
+/// Numbers in the order of [`OrderedFloat`], i.e. NaN is the largest number
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum NumericKey {
+    NegativeInfinity,
+    Finite(OrdDecimal),
+    Infinity,
+    NaN,
+}
+
+/// Decimal ordered by value, e.g. `1.50` equals `1.5` and `-0` equals `0`
+struct OrdDecimal(FixedDecimal);
+
+impl OrdDecimal {
+    fn signum(&self) -> i8 {
+        match self.0.sign() {
+            _ if self.0.is_zero() => 0,
+            Sign::Negative => -1,
+            Sign::None | Sign::Positive => 1,
         }
     }
 }
 
+impl Ord for OrdDecimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (&self.0, &other.0);
+        let signum = self.signum();
+        if signum != other.signum() || signum == 0 {
+            return signum.cmp(&other.signum());
+        }
+        let start = a.nonzero_magnitude_start().max(b.nonzero_magnitude_start());
+        let end = a.nonzero_magnitude_end().min(b.nonzero_magnitude_end());
+        let magnitude = (end..=start)
+            .rev()
+            .map(|m| a.digit_at(m).cmp(&b.digit_at(m)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal);
+        if signum < 0 {
+            magnitude.reverse()
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl PartialOrd for OrdDecimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for OrdDecimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for OrdDecimal {}
+
+/// Returns the integer of `x` if it is integral and within the range of
+/// `i64`, i.e. without saturating
 fn as_integer(x: f64) -> Option<i64> {
-    (x.is_finite() && x.fract() == 0.0).then_some(x as i64)
+    // -2^63 and 2^63 are exact, while `i64::MAX as f64` rounds up to 2^63
+    const BOUND: f64 = 9_223_372_036_854_775_808.0;
+    (x.is_finite() && x.fract() == 0.0 && (-BOUND..BOUND).contains(&x)).then_some(x as i64)
+}
+
+/// Returns the trimmed decimal a finite float compares as: its integer if it
+/// fits into `i64`, otherwise its shortest representation
+fn float_decimal(x: f64) -> Option<FixedDecimal> {
+    match as_integer(x) {
+        Some(n) => Some(n.into()),
+        None if x.is_finite() => x.to_string().parse().ok().map(|x| trimmed(&x)),
+        None => None,
+    }
+}
+
+/// Hashes a decimal as its integer if it is integral and fits into `i64`,
+/// otherwise as its trimmed digits, so that equal numbers of all types collide
+fn hash_decimal<H: hash::Hasher>(x: &FixedDecimal, state: &mut H) {
+    let digits = trimmed(x).to_string();
+    match digits.parse::<i64>() {
+        Ok(n) => hash::Hash::hash(&n, state),
+        Err(_) => hash::Hash::hash(&digits, state),
+    }
+}
+
+/// Normalizes a decimal for comparisons, i.e. without leading or trailing
+/// zeros and with a sign only if it is negative
+fn trimmed(x: &FixedDecimal) -> FixedDecimal {
+    let mut x = x.clone().trimmed_start().trimmed_end();
+    if x.is_zero() || x.sign() == Sign::Positive {
+        x.set_sign(Sign::None);
+    }
+    x
+}
+
+fn to_f64(x: &FixedDecimal) -> f64 {
+    x.to_string().parse().unwrap_or(f64::NAN)
+}
+
+/// Splits a decimal into its digits as an integer and the number of fraction
+/// digits, e.g. `-1.50` into `(-150, 2)`.
+fn to_scaled(x: &FixedDecimal) -> Option<(i128, u32)> {
+    let s = x.to_string();
+    let fraction_digits = s.split_once('.').map_or(0, |(_, fraction)| fraction.len());
+    Some((
+        s.replace('.', "").parse().ok()?,
+        fraction_digits.try_into().ok()?,
+    ))
+}
+
+fn from_scaled(value: i128, fraction_digits: u32) -> Option<FixedDecimal> {
+    let digits = format!(
+        "{:0>width$}",
+        value.unsigned_abs(),
+        width = fraction_digits as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - fraction_digits as usize);
+    let sign = if value < 0 { "-" } else { "" };
+    let separator = if fraction.is_empty() { "" } else { "." };
+    format!("{sign}{integer}{separator}{fraction}").parse().ok()
+}
+
+/// Subtracts a plural offset without going through `f64`, so large counts
+/// keep all of their digits and exact decimals their visible fraction digits.
+pub(crate) fn subtract_offset(value: &FixedDecimal, offset: &FixedDecimal) -> Option<FixedDecimal> {
+    if offset.is_zero() {
+        return Some(value.clone());
+    }
+    let exact = || {
+        let (value, value_digits) = to_scaled(value)?;
+        let (offset, offset_digits) = to_scaled(offset)?;
+        let fraction_digits = value_digits.max(offset_digits);
+        let value = value.checked_mul(10i128.checked_pow(fraction_digits - value_digits)?)?;
+        let offset = offset.checked_mul(10i128.checked_pow(fraction_digits - offset_digits)?)?;
+        from_scaled(value.checked_sub(offset)?, fraction_digits)
+    };
+    // beyond 38 digits the difference is only approximated
+    exact().or_else(|| (to_f64(value) - to_f64(offset)).to_string().parse().ok())
 }
 
 impl hash::Hash for ParamValueInner {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         match self {
             ParamValueInner::Int(a) => a.hash(state),
-            ParamValueInner::Dec(a) => {
-                if let Some(a_int) = as_integer(a.into_inner()) {
-                    a_int.hash(state);
-                } else {
-                    a.hash(state);
-                }
+            ParamValueInner::Dec(a) => match float_decimal(a.into_inner()) {
+                Some(a) => hash_decimal(&a, state),
+                // NaN and infinities are only equal to floats
+                None => a.hash(state),
+            },
+            ParamValueInner::Decimal(a) => hash_decimal(a, state),
+            ParamValueInner::String(a) => (**a).hash(state),
+            ParamValueInner::Range(a0, a1) => {
+                a0.hash(state);
+                a1.hash(state);
             }
-            ParamValueInner::String(a) => a.hash(state),
+            ParamValueInner::Date(a) => date_key(a).hash(state),
+            ParamValueInner::DateTime(a) => date_time_key(a).hash(state),
+            ParamValueInner::None => {}
+            ParamValueInner::Number(a, ..) => a.hash(state),
+            ParamValueInner::Message(a, _) => a.hash(state),
         }
     }
 }
 
+fn date_key(date: &Date<Iso>) -> (i32, u32, u32) {
+    (
+        date.year().number,
+        date.month().ordinal,
+        date.day_of_month().0,
+    )
+}
+
+fn date_time_key(value: &DateTime<Iso>) -> ((i32, u32, u32), [u32; 4]) {
+    let time = &value.time;
+    (
+        date_key(&value.date),
+        [
+            time.hour.number().into(),
+            time.minute.number().into(),
+            time.second.number().into(),
+            time.nanosecond.number(),
+        ],
+    )
+}
+
 pub(crate) const OTHER: ParamValue = ParamValue::from_static_str("other");
 pub(crate) const ARGUMENT_NAME: ParamValue = ParamValue::from_static_str("argumentName");
 pub(crate) const ARGUMENT_OFFSET: ParamValue = ParamValue::from_static_str("argumentOffset");
 
 impl ParamValue {
+    /// Numeric range `start..=end`, e.g. for "1–3 days".
+    ///
+    /// The range is formatted with the range pattern of the locale, e.g.
+    /// "3～5" in Japanese, also by number arguments with a style: with
+    /// `{R, number, currency}`, 10 to 20 is formatted as "$10.00 – $20.00" in
+    /// `en-US` and as "10,00–20,00\u{a0}€" in `de-DE`, where the currency is
+    /// written once. In plural blocks, the branch is selected by the CLDR plural
+    /// range rules of the locale, and `#` is replaced by the formatted range.
+    pub fn range(start: impl Into<ParamValue>, end: impl Into<ParamValue>) -> Self {
+        ParamValueInner::Range(Box::new(start.into()), Box::new(end.into())).into()
+    }
+
+    /// Number whose formatting options are set by the caller, e.g.
+    /// `ParamValue::number(x).max_fraction_digits(1)`.
+    ///
+    /// The options override the style of the argument in the pattern. In plural
+    /// blocks, the number is rounded before the branch is selected.
+    pub fn number(value: impl Into<ParamValue>) -> Self {
+        let value = value.into();
+        match value.inner {
+            ParamValueInner::Number(..) => value,
+            _ => ParamValueInner::Number(Box::new(value), Default::default(), None).into(),
+        }
+    }
+
+    /// Minimum number of fraction digits, padded with zeros.
+    pub fn min_fraction_digits(self, digits: u8) -> Self {
+        self.with_number_options(|options| options.set_min_fraction_digits(digits.into()))
+    }
+
+    /// Maximum number of fraction digits, rounded half-even.
+    pub fn max_fraction_digits(self, digits: u8) -> Self {
+        self.with_number_options(|options| options.set_max_fraction_digits(digits.into()))
+    }
+
+    /// Minimum number of significant digits, padded with zeros.
+    pub fn min_significant_digits(self, digits: u8) -> Self {
+        self.with_number_options(|options| options.set_min_significant_digits(digits.into()))
+    }
+
+    /// Maximum number of significant digits, rounded half-even.
+    pub fn max_significant_digits(self, digits: u8) -> Self {
+        self.with_number_options(|options| options.set_max_significant_digits(digits.into()))
+    }
+
+    /// Formats the number without grouping separators.
+    pub fn without_grouping(self) -> Self {
+        self.with_number_options(NumberOptions::set_without_grouping)
+    }
+
+    /// Selects the plural or ordinal category by `operands` instead of by the
+    /// number, which is still formatted for `#` and matched by exact keys
+    /// like `=1`, e.g. to show "1" but select by "1.0":
+    ///
+    /// ```
+    /// use icu::locid::locale;
+    /// use message_format::{MessageFormat, ParamValue};
+    ///
+    /// let en = locale!("en");
+    /// let fmt = MessageFormat::new("{N, plural, one {# star} other {# stars}}", &en);
+    /// let n = ParamValue::number(1).with_plural_operands("1.0".parse().unwrap());
+    /// assert_eq!(fmt.format_with_params([("N", n)]), "1 stars");
+    /// ```
+    ///
+    /// The operands are used as they are, i.e. the offset of the block is not
+    /// subtracted. Ranges select by their bounds.
+    pub fn with_plural_operands(self, operands: PluralOperands) -> Self {
+        let mut value = Self::number(self);
+        if let ParamValueInner::Number(_, _, plural_operands) = &mut value.inner {
+            *plural_operands = Some(operands);
+        }
+        value
+    }
+
+    pub(crate) fn plural_operands(&self) -> Option<PluralOperands> {
+        match &self.inner {
+            ParamValueInner::Number(_, _, operands) => *operands,
+            _ => None,
+        }
+    }
+
+    fn with_number_options(self, f: impl FnOnce(&mut NumberOptions)) -> Self {
+        let mut value = Self::number(self);
+        if let ParamValueInner::Number(_, options, _) = &mut value.inner {
+            f(options);
+        }
+        value
+    }
+
+    /// Sub-message, formatted with the locale of the enclosing message.
+    ///
+    /// It sees the parameters of the enclosing message, and the given
+    /// parameters in addition. The formatted sub-message is inserted verbatim,
+    /// e.g. a `#` in its output is not replaced by the enclosing plural block.
+    pub fn message(
+        pattern: impl Into<String>,
+        params: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Self {
+        let params = params.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        ParamValueInner::Message(pattern.into().into(), params).into()
+    }
+
+    pub(crate) fn as_message(&self) -> Option<(&str, &[(String, ParamValue)])> {
+        match &self.inner {
+            ParamValueInner::Message(pattern, params) => Some((pattern, params)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn number_options(&self) -> Option<&NumberOptions> {
+        match &self.inner {
+            ParamValueInner::Number(_, options, _) => Some(options),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of the value. `None` values are text, as they are
+    /// replaced by text or not passed at all when formatting.
+    pub fn kind(&self) -> ParamKind {
+        match &self.inner {
+            ParamValueInner::Int(_)
+            | ParamValueInner::Dec(_)
+            | ParamValueInner::Decimal(_)
+            | ParamValueInner::Number(..) => ParamKind::Number,
+            ParamValueInner::String(_) | ParamValueInner::None => ParamKind::Text,
+            ParamValueInner::Range(..) => ParamKind::Range,
+            ParamValueInner::Date(_) => ParamKind::Date,
+            ParamValueInner::DateTime(_) => ParamKind::DateTime,
+            ParamValueInner::Message(..) => ParamKind::Message,
+        }
+    }
+
+    pub(crate) fn is_none(&self) -> bool {
+        match &self.inner {
+            ParamValueInner::None => true,
+            ParamValueInner::Number(value, ..) => value.is_none(),
+            _ => false,
+        }
+    }
+
     pub(crate) const fn from_static_str(s: &'static str) -> Self {
         ParamValue {
-            inner: ParamValueInner::String(Cow::Borrowed(s)),
+            inner: ParamValueInner::String(SharedStr::Static(s)),
         }
     }
 
@@ -76,24 +513,46 @@ impl ParamValue {
         }
     }
 
-    pub(crate) fn format_with_locale(&self, locale: &Locale) -> String {
+    pub(crate) fn format_with_context(&self, context: &RenderContext) -> String {
+        let locale = context.locale();
         match &self.inner {
-            ParamValueInner::Int(value) => {
-                let fdf = FixedDecimalFormatter::try_new(&locale.into(), Default::default())
-                    .expect("missing locale");
-                fdf.format_to_string(&(*value).into())
-            }
+            ParamValueInner::Int(value) => context
+                .fixed_decimal_formatter()
+                .format_to_string(&(*value).into()),
             ParamValueInner::Dec(value) => {
                 let value_str = value.to_string();
                 if let Ok(fixed_dec) = value.to_string().parse() {
-                    let fdf = FixedDecimalFormatter::try_new(&locale.into(), Default::default())
-                        .expect("missing locale");
-                    fdf.format_to_string(&fixed_dec)
+                    context
+                        .fixed_decimal_formatter()
+                        .format_to_string(&fixed_dec)
                 } else {
                     value_str
                 }
             }
-            ParamValueInner::String(value) => value.clone().into_owned(),
+            ParamValueInner::Decimal(value) => {
+                context.fixed_decimal_formatter().format_to_string(value)
+            }
+            ParamValueInner::String(value) => value.to_string(),
+            ParamValueInner::Range(start, end) => format_range(
+                start.format_range_bound(context),
+                end.format_range_bound(context),
+                locale,
+            ),
+            ParamValueInner::Date(value) => context
+                .date_formatter(length::Date::Short)
+                .format_to_string(&value.to_any())
+                .expect("ISO date"),
+            ParamValueInner::DateTime(value) => context
+                .date_time_formatter()
+                .format_to_string(&value.to_any())
+                .expect("ISO date"),
+            ParamValueInner::None => String::new(),
+            ParamValueInner::Number(value, options, _) => match value.as_fixed_decimal() {
+                Some(decimal) => options.format(decimal, context),
+                None => value.format_with_context(context),
+            },
+            ParamValueInner::Message(pattern, params) => MessageFormat::new(&**pattern, locale)
+                .format_with_context(context, params.iter().cloned()),
         }
     }
 
@@ -101,7 +560,100 @@ impl ParamValue {
         match &self.inner {
             ParamValueInner::Int(n) => Some(*n as f64),
             ParamValueInner::Dec(x) => Some(x.0),
+            ParamValueInner::Decimal(x) => Some(to_f64(x)),
+            ParamValueInner::String(s) => s.parse().ok(),
+            ParamValueInner::Number(value, ..) => value.as_decimal(),
+            ParamValueInner::Range(..)
+            | ParamValueInner::Date(_)
+            | ParamValueInner::DateTime(_)
+            | ParamValueInner::None
+            | ParamValueInner::Message(..) => None,
+        }
+    }
+
+    /// Returns the date of dates and date-times.
+    pub(crate) fn as_iso_date(&self) -> Option<&Date<Iso>> {
+        match &self.inner {
+            ParamValueInner::Date(date) => Some(date),
+            ParamValueInner::DateTime(date_time) => Some(&date_time.date),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_iso_date_time(&self) -> Option<&DateTime<Iso>> {
+        match &self.inner {
+            ParamValueInner::DateTime(date_time) => Some(date_time),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_fixed_decimal(&self) -> Option<FixedDecimal> {
+        match &self.inner {
+            ParamValueInner::Int(n) => Some((*n).into()),
+            ParamValueInner::Dec(x) => x.to_string().parse().ok(),
+            ParamValueInner::Decimal(x) => Some(x.clone()),
             ParamValueInner::String(s) => s.parse().ok(),
+            ParamValueInner::Number(value, ..) => value.as_fixed_decimal(),
+            ParamValueInner::Range(..)
+            | ParamValueInner::Date(_)
+            | ParamValueInner::DateTime(_)
+            | ParamValueInner::None
+            | ParamValueInner::Message(..) => None,
+        }
+    }
+
+    /// Returns the number plural rules are evaluated for: integers and
+    /// decimals are exact, other numbers go through `f64`.
+    pub(crate) fn as_plural_operand(&self) -> Option<FixedDecimal> {
+        match &self.inner {
+            ParamValueInner::Int(n) => Some((*n).into()),
+            ParamValueInner::Decimal(x) => Some(x.clone()),
+            ParamValueInner::Number(value, ..) => value.as_plural_operand(),
+            _ => self.as_decimal()?.to_string().parse().ok(),
+        }
+    }
+
+    pub(crate) fn as_fixed_decimal_range(&self) -> Option<(FixedDecimal, FixedDecimal)> {
+        match &self.inner {
+            ParamValueInner::Range(start, end) => {
+                Some((start.as_fixed_decimal()?, end.as_fixed_decimal()?))
+            }
+            ParamValueInner::Number(value, ..) => value.as_fixed_decimal_range(),
+            _ => None,
+        }
+    }
+
+    /// Formats a bound of a range argument, numbers with their options.
+    fn format_range_bound(&self, context: &RenderContext) -> FormattedNumber {
+        let default_options = NumberOptions::default();
+        let options = match &self.inner {
+            ParamValueInner::Int(_) | ParamValueInner::Dec(_) | ParamValueInner::Decimal(_) => {
+                &default_options
+            }
+            ParamValueInner::Number(_, options, _) => options,
+            _ => return FormattedNumber::text(self.format_with_context(context)),
+        };
+        match self.as_fixed_decimal() {
+            Some(value) => options.format_parts(value, context),
+            // e.g. infinity
+            None => FormattedNumber::text(self.format_with_context(context)),
+        }
+    }
+
+    pub(crate) fn as_plural_operand_range(&self) -> Option<(FixedDecimal, FixedDecimal)> {
+        match &self.inner {
+            ParamValueInner::Range(start, end) => {
+                Some((start.as_plural_operand()?, end.as_plural_operand()?))
+            }
+            ParamValueInner::Number(value, ..) => value.as_plural_operand_range(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match &self.inner {
+            ParamValueInner::String(s) => Some(s),
+            _ => None,
         }
     }
 }
@@ -130,15 +682,108 @@ impl_from_integer_type!(u32);
 impl_from_integer_type!(u16);
 impl_from_integer_type!(u8);
 
+impl From<FixedDecimal> for ParamValue {
+    fn from(value: FixedDecimal) -> Self {
+        ParamValueInner::Decimal(value).into()
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<rust_decimal::Decimal> for ParamValue {
+    fn from(value: rust_decimal::Decimal) -> Self {
+        let scale = i16::try_from(value.scale()).expect("rust_decimal scale is at most 28");
+        FixedDecimal::from(value.mantissa())
+            .multiplied_pow10(-scale)
+            .into()
+    }
+}
+
+/// Numbers beyond the magnitudes of a `FixedDecimal`, ±32767, are converted
+/// to `f64`, e.g. 1e40000 to infinity.
+#[cfg(feature = "bigdecimal")]
+impl From<bigdecimal::BigDecimal> for ParamValue {
+    fn from(value: bigdecimal::BigDecimal) -> Self {
+        use bigdecimal::ToPrimitive;
+
+        let (digits, exponent) = value.as_bigint_and_exponent();
+        let decimal = i16::try_from(exponent)
+            .ok()
+            .and_then(i16::checked_neg)
+            .and_then(|shift| {
+                let decimal = digits.to_string().parse::<FixedDecimal>().ok()?;
+                let shifted = decimal.clone().multiplied_pow10(shift);
+                // the shift sets the number to zero on overflow
+                (shifted.is_zero() == decimal.is_zero()).then_some(shifted)
+            });
+        match decimal {
+            Some(decimal) => decimal.into(),
+            None => value.to_f64().unwrap_or(f64::NAN).into(),
+        }
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl From<num_bigint::BigInt> for ParamValue {
+    fn from(value: num_bigint::BigInt) -> Self {
+        value
+            .to_string()
+            .parse::<FixedDecimal>()
+            .expect("BigInt digits")
+            .into()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl From<num_bigint::BigUint> for ParamValue {
+    fn from(value: num_bigint::BigUint) -> Self {
+        num_bigint::BigInt::from(value).into()
+    }
+}
+
 impl From<String> for ParamValue {
     fn from(value: String) -> Self {
-        ParamValueInner::String(Cow::Owned(value)).into()
+        ParamValueInner::String(SharedStr::Shared(value.into())).into()
+    }
+}
+
+impl From<Arc<str>> for ParamValue {
+    fn from(value: Arc<str>) -> Self {
+        ParamValueInner::String(SharedStr::Shared(value)).into()
     }
 }
 
 impl From<&'static str> for ParamValue {
     fn from(value: &'static str) -> Self {
-        ParamValueInner::String(Cow::Borrowed(value)).into()
+        ParamValueInner::String(SharedStr::Static(value)).into()
+    }
+}
+
+impl From<char> for ParamValue {
+    fn from(value: char) -> Self {
+        ParamValueInner::String(SharedStr::Shared(value.to_string().into())).into()
+    }
+}
+
+/// `None` is rendered according to the [`crate::NoneParam`] of the message.
+impl<T: Into<ParamValue>> From<Option<T>> for ParamValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ParamValueInner::None.into(), Into::into)
+    }
+}
+
+/// Numbers and strings are recorded as such, other values as their text.
+#[cfg(feature = "tracing")]
+impl ParamValue {
+    /// Returns the value as a `tracing` field value, e.g.
+    /// `tracing::info!(count = value.as_field())`.
+    pub fn as_field(&self) -> Box<dyn tracing::Value + '_> {
+        match &self.inner {
+            ParamValueInner::Int(n) => Box::new(*n),
+            ParamValueInner::Dec(x) => Box::new(x.into_inner()),
+            ParamValueInner::String(s) => Box::new(&**s),
+            ParamValueInner::Number(value, ..) => value.as_field(),
+            _ => Box::new(tracing::field::display(self)),
+        }
     }
 }
 
@@ -147,11 +792,180 @@ impl fmt::Display for ParamValue {
         match &self.inner {
             ParamValueInner::Int(value) => write!(f, "{}", value),
             ParamValueInner::Dec(value) => write!(f, "{}", value),
+            ParamValueInner::Decimal(value) => write!(f, "{}", value),
             ParamValueInner::String(value) => f.write_str(value),
+            ParamValueInner::Range(start, end) => write!(f, "{}–{}", start, end),
+            ParamValueInner::Date(value) => write_iso_date(f, value),
+            ParamValueInner::DateTime(value) => {
+                write_iso_date(f, &value.date)?;
+                write!(
+                    f,
+                    "T{:02}:{:02}:{:02}",
+                    value.time.hour.number(),
+                    value.time.minute.number(),
+                    value.time.second.number()
+                )
+            }
+            ParamValueInner::None => Ok(()),
+            ParamValueInner::Number(value, ..) => value.fmt(f),
+            ParamValueInner::Message(pattern, _) => f.write_str(pattern),
         }
     }
 }
 
+fn write_iso_date(f: &mut fmt::Formatter<'_>, date: &Date<Iso>) -> fmt::Result {
+    write!(
+        f,
+        "{:04}-{:02}-{:02}",
+        date.year().number,
+        date.month().ordinal,
+        date.day_of_month().0
+    )
+}
+
+impl<A: AsCalendar> From<Date<A>> for ParamValue {
+    fn from(value: Date<A>) -> Self {
+        ParamValueInner::Date(value.to_calendar(Iso)).into()
+    }
+}
+
+impl<A: AsCalendar> From<DateTime<A>> for ParamValue {
+    fn from(value: DateTime<A>) -> Self {
+        ParamValueInner::DateTime(value.to_calendar(Iso)).into()
+    }
+}
+
+/// Converts to a date-time in UTC. Times beyond the range of ICU date-times,
+/// i.e. more than about 4000 years from 1970, are clamped to it.
+impl From<SystemTime> for ParamValue {
+    fn from(value: SystemTime) -> Self {
+        let (seconds, nanosecond) = match value.duration_since(UNIX_EPOCH) {
+            Ok(since) => (
+                i64::try_from(since.as_secs()).unwrap_or(i64::MAX),
+                since.subsec_nanos(),
+            ),
+            Err(err) => {
+                let before = err.duration();
+                let seconds = i64::try_from(before.as_secs()).unwrap_or(i64::MAX);
+                match before.subsec_nanos() {
+                    0 => (-seconds, 0),
+                    nanos => (-seconds - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        let minutes = seconds.div_euclid(60);
+        let (minutes, second, nanosecond) = match i32::try_from(minutes) {
+            Ok(minutes) => (minutes, seconds.rem_euclid(60) as u8, nanosecond),
+            Err(_) if minutes < 0 => (i32::MIN, 0, 0),
+            Err(_) => (i32::MAX, 59, 999_999_999),
+        };
+        let mut date_time = DateTime::from_minutes_since_local_unix_epoch(minutes);
+        date_time.time.second = second.try_into().expect("valid second");
+        date_time.time.nanosecond = nanosecond.try_into().expect("valid nanosecond");
+        ParamValueInner::DateTime(date_time).into()
+    }
+}
+
+/// Converts to the number of seconds, e.g. for
+/// `{D, plural, one {# second} other {# seconds}}`. There is no `duration`
+/// argument type formatting e.g. "1 hr, 2 min", as ICU4X 1.5 has no duration
+/// formatter.
+impl From<Duration> for ParamValue {
+    fn from(value: Duration) -> Self {
+        FixedDecimal::from(value.as_nanos())
+            .multiplied_pow10(-9)
+            .trimmed_end()
+            .into()
+    }
+}
+
+/// Converts to a date-time in the wall-clock time of the time zone.
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for ParamValue {
+    fn from(value: chrono::DateTime<Tz>) -> Self {
+        value.naive_local().into()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for ParamValue {
+    fn from(value: chrono::NaiveDateTime) -> Self {
+        use chrono::Timelike;
+
+        ParamValueInner::DateTime(iso_date_time(
+            iso_date_from_chrono(value.date()),
+            value.hour() as u8,
+            value.minute() as u8,
+            value.second() as u8,
+            // chrono represents leap seconds as nanoseconds >= 1_000_000_000
+            value.nanosecond().min(999_999_999),
+        ))
+        .into()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for ParamValue {
+    fn from(value: chrono::NaiveDate) -> Self {
+        ParamValueInner::Date(iso_date_from_chrono(value)).into()
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn iso_date_from_chrono(value: chrono::NaiveDate) -> Date<Iso> {
+    use chrono::Datelike;
+
+    Date::try_new_iso_date(value.year(), value.month() as u8, value.day() as u8)
+        .expect("valid chrono date")
+}
+
+/// Converts to a date-time in the wall-clock time of the UTC offset.
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for ParamValue {
+    fn from(value: time::OffsetDateTime) -> Self {
+        time::PrimitiveDateTime::new(value.date(), value.time()).into()
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::PrimitiveDateTime> for ParamValue {
+    fn from(value: time::PrimitiveDateTime) -> Self {
+        ParamValueInner::DateTime(iso_date_time(
+            iso_date_from_time(value.date()),
+            value.hour(),
+            value.minute(),
+            value.second(),
+            value.nanosecond(),
+        ))
+        .into()
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::Date> for ParamValue {
+    fn from(value: time::Date) -> Self {
+        ParamValueInner::Date(iso_date_from_time(value)).into()
+    }
+}
+
+#[cfg(feature = "time")]
+fn iso_date_from_time(value: time::Date) -> Date<Iso> {
+    Date::try_new_iso_date(value.year(), value.month().into(), value.day())
+        .expect("valid time date")
+}
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn iso_date_time(
+    date: Date<Iso>,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+) -> DateTime<Iso> {
+    let time = icu::calendar::Time::try_new(hour, minute, second, nanosecond).expect("valid time");
+    DateTime::new(date, time)
+}
+
 #[cfg(test)]
 mod tests {
     use hash::{DefaultHasher, Hasher};
@@ -163,8 +977,10 @@ mod tests {
     fn test_as_integer() {
         assert_eq!(as_integer(0.0), Some(0));
         assert_eq!(as_integer(1.0), Some(1));
-        assert_eq!(as_integer(f64::MAX.trunc()), Some(i64::MAX));
-        assert_eq!(as_integer(f64::MIN.trunc()), Some(i64::MIN));
+        assert_eq!(as_integer(f64::MAX.trunc()), None);
+        assert_eq!(as_integer(f64::MIN.trunc()), None);
+        assert_eq!(as_integer(i64::MIN as f64), Some(i64::MIN));
+        assert_eq!(as_integer(i64::MAX as f64), None);
         assert_eq!(as_integer(0.1), None);
         assert_eq!(as_integer(f64::NAN), None);
         assert_eq!(as_integer(f64::MIN_POSITIVE), None);
@@ -180,6 +996,107 @@ mod tests {
         assert_ne!(ParamValue::from(1), ParamValue::from(1.1));
     }
 
+    #[test]
+    fn test_large_decimal_hash() {
+        // 2^53 + 1, which is not a float
+        let decimal = ParamValue::from(FixedDecimal::from(9_007_199_254_740_993i64));
+        let int = ParamValue::from(9_007_199_254_740_993i64);
+        assert_eq!(decimal, int);
+        assert_eq!(hash(&decimal), hash(&int));
+        assert_ne!(decimal, ParamValue::from(9_007_199_254_740_992.0));
+
+        let decimal = ParamValue::from(FixedDecimal::from(9_007_199_254_740_992i64));
+        let float = ParamValue::from(9_007_199_254_740_992.0);
+        assert_eq!(decimal, float);
+        assert_eq!(hash(&decimal), hash(&float));
+
+        let decimal = ParamValue::from("1.50".parse::<FixedDecimal>().unwrap());
+        assert_eq!(decimal, ParamValue::from(1.5));
+        assert_eq!(hash(&decimal), hash(ParamValue::from(1.5)));
+
+        let float = ParamValue::from(1e300);
+        let decimal = ParamValue::from(
+            format!("1{}", "0".repeat(300))
+                .parse::<FixedDecimal>()
+                .unwrap(),
+        );
+        assert_eq!(float, decimal);
+        assert_eq!(hash(&float), hash(&decimal));
+        assert_ne!(float, ParamValue::from(i64::MAX));
+    }
+
+    #[test]
+    fn test_cmp_at_integer_bounds() {
+        let float = ParamValue::from(1e300);
+        let decimal = ParamValue::from(
+            format!("1{}", "0".repeat(300))
+                .parse::<FixedDecimal>()
+                .unwrap(),
+        );
+        let max = ParamValue::from(i64::MAX);
+        assert_eq!(float.cmp(&decimal), Ordering::Equal);
+        assert_eq!(float.cmp(&max), Ordering::Greater);
+        assert_eq!(decimal.cmp(&max), Ordering::Greater);
+
+        // 2^63 is just above `i64::MAX`, and -2^63 is `i64::MIN`
+        let two_63 = ParamValue::from(9_223_372_036_854_775_808.0);
+        assert_ne!(two_63, max);
+        assert_eq!(two_63.cmp(&max), Ordering::Greater);
+        assert_eq!(max.cmp(&two_63), Ordering::Less);
+        let min = ParamValue::from(i64::MIN);
+        assert_eq!(ParamValue::from(-9_223_372_036_854_775_808.0), min);
+        assert_eq!(ParamValue::from(-1e300).cmp(&min), Ordering::Less);
+
+        // 2^60 compares by its exact value, not its shortest representation
+        let int = ParamValue::from(1i64 << 60);
+        let float = ParamValue::from((1i64 << 60) as f64);
+        let rounded = ParamValue::from("1152921504606847000".parse::<FixedDecimal>().unwrap());
+        assert_eq!(int, float);
+        assert_eq!(int.cmp(&float), Ordering::Equal);
+        assert_ne!(float, rounded);
+        assert_eq!(float.cmp(&rounded), Ordering::Less);
+        assert_eq!(
+            ParamValue::from(0.5).cmp(&ParamValue::from(0)),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_system_time() {
+        let time = UNIX_EPOCH + Duration::new(86_400 + 3_723, 5);
+        assert_eq!(ParamValue::from(time).to_string(), "1970-01-02T01:02:03");
+        let time = UNIX_EPOCH - Duration::new(1, 500_000_000);
+        assert_eq!(ParamValue::from(time).to_string(), "1969-12-31T23:59:58");
+
+        // clamped to the range of ICU date-times instead of panicking
+        let far = Duration::from_secs(1 << 40);
+        let latest = ParamValue::from(UNIX_EPOCH + far);
+        assert_eq!(latest, ParamValue::from(UNIX_EPOCH + 2 * far));
+        assert!(latest > ParamValue::from(UNIX_EPOCH + Duration::from_secs(1 << 36)));
+        let earliest = ParamValue::from(UNIX_EPOCH - far);
+        assert_eq!(earliest, ParamValue::from(UNIX_EPOCH - 2 * far));
+        assert!(earliest < ParamValue::from(UNIX_EPOCH - Duration::from_secs(1 << 36)));
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(
+            ParamValue::from(Duration::from_secs(90)),
+            ParamValue::from(90)
+        );
+        assert_eq!(
+            ParamValue::from(Duration::from_millis(1500)).to_string(),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn test_char_eq() {
+        assert_eq!(ParamValue::from('A'), ParamValue::from("A"));
+        assert_eq!(hash(ParamValue::from('A')), hash(ParamValue::from("A")));
+        assert_eq!(ParamValue::from('ß').to_string(), "ß");
+    }
+
     fn hash<T: hash::Hash>(value: T) -> u64 {
         let mut state = DefaultHasher::new();
         value.hash(&mut state);
@@ -0,0 +1,172 @@
+use std::{
+    fmt::Write,
+    io::{self, IsTerminal},
+};
+
+use crate::ParamKind;
+
+/// Segment of a formatted message, see [`crate::MessageFormat::format_to_parts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MessagePart {
+    /// Text of the pattern
+    Literal(String),
+    /// Formatted value of an argument, incl. `#` of plural and ordinal blocks
+    Argument {
+        name: String,
+        value: String,
+        kind: ParamKind,
+    },
+}
+
+impl MessagePart {
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Literal(text) => text,
+            Self::Argument { value, .. } => value,
+        }
+    }
+
+    pub(crate) fn text_mut(&mut self) -> &mut String {
+        match self {
+            Self::Literal(text) => text,
+            Self::Argument { value, .. } => value,
+        }
+    }
+}
+
+/// Output of a formatted message for a UI framework, e.g. a list of nodes in
+/// which arguments are wrapped in styled elements.
+pub trait MessageRenderer {
+    type Output;
+
+    fn literal(&mut self, text: &str);
+
+    fn argument(&mut self, name: &str, value: &str);
+
+    fn finish(self) -> Self::Output;
+
+    /// Renders all `parts` of a message.
+    fn render(mut self, parts: &[MessagePart]) -> Self::Output
+    where
+        Self: Sized,
+    {
+        for part in parts {
+            match part {
+                MessagePart::Literal(text) => self.literal(text),
+                MessagePart::Argument { name, value, .. } => self.argument(name, value),
+            }
+        }
+        self.finish()
+    }
+}
+
+/// Renders the message as plain text, as [`crate::MessageFormat::format`].
+#[derive(Debug, Clone, Default)]
+pub struct PlainText(String);
+
+impl MessageRenderer for PlainText {
+    type Output = String;
+
+    fn literal(&mut self, text: &str) {
+        self.0.push_str(text);
+    }
+
+    fn argument(&mut self, _name: &str, value: &str) {
+        self.0.push_str(value);
+    }
+
+    fn finish(self) -> String {
+        self.0
+    }
+}
+
+/// Renders the message as escaped HTML, wrapping arguments in
+/// `<span data-arg="NAME">…</span>`.
+#[derive(Debug, Clone, Default)]
+pub struct Html(String);
+
+impl MessageRenderer for Html {
+    type Output = String;
+
+    fn literal(&mut self, text: &str) {
+        escape_html(&mut self.0, text);
+    }
+
+    fn argument(&mut self, name: &str, value: &str) {
+        self.0.push_str("<span data-arg=\"");
+        escape_html(&mut self.0, name);
+        self.0.push_str("\">");
+        escape_html(&mut self.0, value);
+        self.0.push_str("</span>");
+    }
+
+    fn finish(self) -> String {
+        self.0
+    }
+}
+
+/// Renders the message for a terminal, wrapping arguments in the ANSI escape
+/// sequences of the SGR parameters returned by `style`, e.g. `"1;32"` for
+/// bold green.
+#[derive(Debug, Clone)]
+pub struct AnsiText<F> {
+    text: String,
+    style: F,
+    colored: bool,
+}
+
+impl<F: FnMut(&str) -> Option<&'static str>> AnsiText<F> {
+    pub fn new(style: F) -> Self {
+        Self {
+            text: String::new(),
+            style,
+            colored: true,
+        }
+    }
+
+    /// Renders without escape sequences if stdout is not a terminal or
+    /// `NO_COLOR` is set.
+    pub fn for_stdout(style: F) -> Self {
+        let colored = io::stdout().is_terminal()
+            && std::env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty());
+        Self::new(style).colored(colored)
+    }
+
+    pub fn colored(mut self, colored: bool) -> Self {
+        self.colored = colored;
+        self
+    }
+}
+
+impl<F: FnMut(&str) -> Option<&'static str>> MessageRenderer for AnsiText<F> {
+    type Output = String;
+
+    fn literal(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn argument(&mut self, name: &str, value: &str) {
+        match (self.style)(name).filter(|_| self.colored) {
+            Some(style) => write!(self.text, "\x1b[{style}m{value}\x1b[0m").unwrap(),
+            None => self.text.push_str(value),
+        }
+    }
+
+    fn finish(self) -> String {
+        self.text
+    }
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.write_char(c).unwrap(),
+        }
+    }
+}
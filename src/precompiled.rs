@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+
+use icu::locid::Locale;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    datetime::DateTimeStyle,
+    number::NumberOptions,
+    param::{ARGUMENT_NAME, ARGUMENT_OFFSET},
+    Block, MessageFormat, ParamValue,
+};
+
+/// Version of the format, which is increased on incompatible changes
+const VERSION: u64 = 1;
+
+/// Returns the parsed pattern as JSON, see
+/// [`crate::MessageFormat::to_precompiled`].
+pub(crate) fn to_json(message: &MessageFormat<'_>) -> Value {
+    json!({
+        "version": VERSION,
+        "pattern": message.pattern,
+        "blocks": blocks_to_json(message.parsed()),
+    })
+}
+
+fn blocks_to_json(blocks: &[Block]) -> Vec<Value> {
+    let mut values = Vec::new();
+    for block in blocks {
+        match block {
+            Block::String(text) => {
+                for (i, segment) in text.split('#').enumerate() {
+                    if i > 0 {
+                        values.push(json!({"type": "pound"}));
+                    }
+                    push_text(&mut values, segment.to_owned());
+                }
+            }
+            Block::Literal(text) => push_text(&mut values, text.clone()),
+            Block::TagOpen(name) => push_text(&mut values, format!("<{name}>")),
+            Block::TagClose(name) => push_text(&mut values, format!("</{name}>")),
+            Block::Simple(name) => values.push(json!({"type": "argument", "name": name})),
+            Block::Number(name, options) => values.push(json!({
+                "type": "number",
+                "name": name,
+                "style": options.to_skeleton(),
+            })),
+            Block::DateTime(name, style) => values.push(json!({
+                "type": style.typ(),
+                "name": name,
+                "style": style.style(),
+            })),
+            Block::Custom(name, typ, style) => values.push(json!({
+                "type": "custom",
+                "name": name,
+                "argument_type": typ,
+                "style": style,
+            })),
+            Block::Select(branches) | Block::Plural(branches, _) | Block::Ordinal(branches, _) => {
+                let typ = match block {
+                    Block::Select(_) => "select",
+                    Block::Plural(..) => "plural",
+                    _ => "selectordinal",
+                };
+                let mut value = json!({
+                    "type": typ,
+                    "name": argument_string(&branches[&ARGUMENT_NAME]),
+                });
+                if let Some(offset) = branches.get(&ARGUMENT_OFFSET) {
+                    value["offset"] = argument_string(offset).into();
+                }
+                value["branches"] = branches
+                    .iter()
+                    .filter(|(key, _)| **key != ARGUMENT_NAME && **key != ARGUMENT_OFFSET)
+                    .map(|(key, branch)| {
+                        json!({
+                            "key": key.to_string(),
+                            "blocks": blocks_to_json(branch),
+                        })
+                    })
+                    .collect();
+                values.push(value);
+            }
+        }
+    }
+    values
+}
+
+/// Merges adjacent text, e.g. of tags.
+fn push_text(values: &mut Vec<Value>, text: String) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(Value::String(last)) = values
+        .last_mut()
+        .filter(|value| value["type"] == "text")
+        .map(|value| &mut value["value"])
+    {
+        last.push_str(&text);
+        return;
+    }
+    values.push(json!({"type": "text", "value": text}));
+}
+
+fn argument_string(blocks: &[Block]) -> &str {
+    match blocks.first() {
+        Some(Block::String(text)) => text,
+        _ => "",
+    }
+}
+
+/// Reads a message written by [`to_json`], which is not parsed again.
+pub(crate) fn from_json<'l>(
+    value: &Value,
+    locale: Cow<'l, Locale>,
+) -> Result<MessageFormat<'l>, String> {
+    let Some(object) = value.as_object() else {
+        return Err("expected an object".to_owned());
+    };
+    if object.get("version").and_then(Value::as_u64) != Some(VERSION) {
+        return Err(format!("unsupported version, expected {VERSION}"));
+    }
+    let blocks = blocks_from_json(object.get("blocks"))?;
+    let message = MessageFormat::with_locale(string(object, "pattern")?.to_owned(), locale);
+    let _ = message.parsed.set(blocks);
+    Ok(message)
+}
+
+fn blocks_from_json(value: Option<&Value>) -> Result<Vec<Block>, String> {
+    let Some(values) = value.and_then(Value::as_array) else {
+        return Err("expected an array of blocks".to_owned());
+    };
+    let mut blocks = Vec::new();
+    for value in values {
+        let Some(block) = value.as_object() else {
+            return Err("expected a block object".to_owned());
+        };
+        let typ = string(block, "type")?;
+        let block = match typ {
+            "text" => Block::Literal(string(block, "value")?.to_owned()),
+            "pound" => Block::String("#".to_owned()),
+            "argument" => Block::Simple(string(block, "name")?.to_owned()),
+            "number" => {
+                let style = string(block, "style")?;
+                let Some(options) = NumberOptions::parse_style(style) else {
+                    return Err(format!("invalid number style {style}"));
+                };
+                Block::Number(string(block, "name")?.to_owned(), options)
+            }
+            "date" | "time" => {
+                let style = string(block, "style")?;
+                let Some(style) = DateTimeStyle::parse(typ, style) else {
+                    return Err(format!("invalid {typ} style {style}"));
+                };
+                Block::DateTime(string(block, "name")?.to_owned(), style)
+            }
+            "custom" => Block::Custom(
+                string(block, "name")?.to_owned(),
+                string(block, "argument_type")?.to_owned(),
+                string(block, "style")?.to_owned(),
+            ),
+            "select" | "plural" | "selectordinal" => {
+                let mut branches = IndexMap::new();
+                branches.insert(
+                    ARGUMENT_NAME,
+                    vec![Block::String(string(block, "name")?.to_owned())],
+                );
+                if typ != "select" {
+                    let offset = block.get("offset").and_then(Value::as_str).unwrap_or("0");
+                    branches.insert(ARGUMENT_OFFSET, vec![Block::String(offset.to_owned())]);
+                }
+                let Some(values) = block.get("branches").and_then(Value::as_array) else {
+                    return Err("expected an array of branches".to_owned());
+                };
+                for value in values {
+                    let Some(branch) = value.as_object() else {
+                        return Err("expected a branch object".to_owned());
+                    };
+                    let key = string(branch, "key")?;
+                    let key =
+                        ParamValue::parse_number(key).unwrap_or_else(|| key.to_owned().into());
+                    branches.insert(key, blocks_from_json(branch.get("blocks"))?);
+                }
+                if !branches.contains_key(&crate::OTHER) {
+                    return Err(format!("{typ} block without other branch"));
+                }
+                match typ {
+                    "select" => Block::Select(branches),
+                    "plural" => Block::plural(branches),
+                    _ => Block::ordinal(branches),
+                }
+            }
+            _ => return Err(format!("unknown block type {typ}")),
+        };
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+fn string<'v>(object: &'v Map<String, Value>, name: &str) -> Result<&'v str, String> {
+    object
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("expected a string {name}"))
+}
@@ -0,0 +1,44 @@
+use std::{fmt, sync::Arc};
+
+use icu_provider::AnyProvider;
+#[cfg(feature = "buffer_provider")]
+use icu_provider::BufferProvider;
+
+/// Where the ICU formatters load their data from, see
+/// [`crate::MessageFormat::new_with_any_provider`].
+#[derive(Clone, Default)]
+pub(crate) enum DataSource {
+    /// Data compiled into the binary
+    #[default]
+    Compiled,
+    Any(Arc<dyn AnyProvider + Send + Sync>),
+    #[cfg(feature = "buffer_provider")]
+    Buffer(Arc<dyn BufferProvider + Send + Sync>),
+}
+
+impl fmt::Debug for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compiled => f.write_str("Compiled"),
+            Self::Any(_) => f.write_str("Any"),
+            #[cfg(feature = "buffer_provider")]
+            Self::Buffer(_) => f.write_str("Buffer"),
+        }
+    }
+}
+
+/// Calls the constructor of an ICU type for the data source, given the names
+/// of its constructors for compiled data, an `AnyProvider` and a
+/// `BufferProvider`.
+macro_rules! try_new {
+    ($source:expr, $ty:ident::{$compiled:ident, $any:ident, $buffer:ident}($($arg:expr),* $(,)?)) => {
+        match $source {
+            $crate::provider::DataSource::Compiled => $ty::$compiled($($arg),*),
+            $crate::provider::DataSource::Any(provider) => $ty::$any(provider, $($arg),*),
+            #[cfg(feature = "buffer_provider")]
+            $crate::provider::DataSource::Buffer(provider) => $ty::$buffer(provider, $($arg),*),
+        }
+    };
+}
+
+pub(crate) use try_new;
@@ -0,0 +1,622 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use icu::locid::{subtags::Variants, Locale};
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "async")]
+use crate::{
+    build::{parse_locale_file, Entry},
+    ResourceFetcher,
+};
+use crate::{
+    report::check_pattern, BranchPath, Diagnostic, MessageFormat, MessagePart, Metadata,
+    ParamValue, ParseError, RenderContext, Report, Severity,
+};
+
+/// Declares [`Registry`] statics mapping keys to default patterns, e.g.
+///
+/// ```
+/// message_format::registry! {
+///     pub static MESSAGES = {
+///         "greeting" => "Hello {NAME}!",
+///         "items" => "{N, plural, one {# item} other {# items}}",
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! registry {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident = { $($key:literal => $pattern:literal),* $(,)? };
+    )*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::Registry =
+                $crate::Registry::new(&[$(($key, $pattern)),*]);
+        )*
+    };
+}
+
+/// Messages looked up by static keys, declared with [`registry!`].
+///
+/// Patterns are parsed on first use, once per locale. Translations replace
+/// the default pattern of a key with [`Registry::set_pattern`].
+#[derive(Debug)]
+pub struct Registry {
+    defaults: &'static [(&'static str, &'static str)],
+    state: Lazy<Mutex<State>>,
+}
+
+/// Pattern used for a key without a translation in the requested locale, see
+/// [`Registry::set_fallback`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fallback {
+    /// The key itself, e.g. `cart.empty`
+    Key,
+    /// The default pattern, usually in the source language
+    #[default]
+    Source,
+    /// The default pattern with accented letters in brackets, e.g.
+    /// `[Ýóúŕ çáŕţ íš éɱƥţý]`, to spot untranslated text
+    Pseudo,
+    /// No message, i.e. [`Registry::format`] returns `None`
+    Error,
+}
+
+/// Problem of a translation found by [`Registry::audit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditIssue {
+    /// Key without a pattern for a locale that has translations
+    MissingTranslation { key: String, locale: Locale },
+    /// Translation whose arguments differ from the default pattern
+    ArgumentMismatch {
+        key: String,
+        locale: Locale,
+        /// Arguments of the default pattern missing in the translation
+        missing: Vec<String>,
+        /// Arguments of the translation not in the default pattern
+        unknown: Vec<String>,
+    },
+}
+
+impl fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTranslation { key, locale } => {
+                write!(f, "{locale}: {key}: missing translation")
+            }
+            Self::ArgumentMismatch {
+                key,
+                locale,
+                missing,
+                unknown,
+            } => write!(
+                f,
+                "{locale}: {key}: missing arguments [{}], unknown arguments [{}]",
+                missing.join(", "),
+                unknown.join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    fallback: Fallback,
+    /// Locale of the default patterns, which need no translation
+    source_locale: Option<Locale>,
+    /// Contexts of the locales of the parsed messages
+    contexts: HashMap<Locale, Arc<RenderContext>>,
+    patterns: HashMap<(Locale, String), String>,
+    metadata: HashMap<String, Metadata>,
+    select_aliases: Vec<(String, String)>,
+    messages: HashMap<(Locale, String), Message>,
+}
+
+/// Pattern of a key found in the fallback chain of a locale
+enum Lookup<'a> {
+    Translation(&'a str),
+    /// The locale or one of its parents is the source locale
+    Source,
+}
+
+impl State {
+    /// Returns the translation of the first locale of the fallback chain of
+    /// `locale` with one, e.g. of `en-GB` or `en`, so that regional locales
+    /// only need the patterns which differ.
+    fn lookup(&self, key: &str, locale: &Locale) -> Option<Lookup<'_>> {
+        let exact = (locale.clone(), key.to_owned());
+        if let Some(pattern) = self.patterns.get(&exact) {
+            return Some(Lookup::Translation(pattern));
+        }
+        if self.source_locale.as_ref() == Some(locale) {
+            return Some(Lookup::Source);
+        }
+        for locale in fallback_chain(locale) {
+            if self.source_locale.as_ref() == Some(&locale) {
+                return Some(Lookup::Source);
+            }
+            if let Some(pattern) = self.patterns.get(&(locale, key.to_owned())) {
+                return Some(Lookup::Translation(pattern));
+            }
+        }
+        None
+    }
+}
+
+/// Cached message, shared with the callers formatting it so that the state
+/// is not locked while formatting
+#[derive(Debug, Clone)]
+enum Message {
+    /// Formatted text of a message without arguments
+    Static(Arc<str>),
+    Parsed(Arc<MessageFormat<'static>>),
+}
+
+/// Text of a formatted message
+enum Rendered {
+    /// Text of a message without arguments
+    Shared(Arc<str>),
+    Owned(String),
+}
+
+impl Registry {
+    pub const fn new(defaults: &'static [(&'static str, &'static str)]) -> Self {
+        Self {
+            defaults,
+            state: Lazy::new(Default::default),
+        }
+    }
+
+    /// Returns the keys of the default patterns.
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> {
+        self.defaults.iter().map(|(key, _)| *key)
+    }
+
+    /// Returns the locales with translations, and the source locale, if set,
+    /// sorted by their tags, e.g. for a language picker or for
+    /// [`Locale`] negotiation.
+    pub fn available_locales(&self) -> Vec<Locale> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let locales = state.patterns.keys().map(|(locale, _)| locale);
+        sorted_locales(locales.chain(&state.source_locale))
+    }
+
+    /// Returns the locales in which `key` is translated, and the source
+    /// locale if `key` has a default pattern, sorted by their tags.
+    pub fn key_locales(&self, key: &str) -> Vec<Locale> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let locales = state
+            .patterns
+            .keys()
+            .filter(|(_, k)| k == key)
+            .map(|(locale, _)| locale);
+        let source = state
+            .source_locale
+            .as_ref()
+            .filter(|_| self.default_pattern(key).is_some());
+        sorted_locales(locales.chain(source))
+    }
+
+    /// Returns the pattern of `key` in `locale`, if any: its translation,
+    /// the translation of a parent locale, e.g. `en` for `en-GB`, or the
+    /// default pattern.
+    pub fn pattern(&self, key: &str, locale: &Locale) -> Option<String> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.lookup(key, locale) {
+            Some(Lookup::Translation(pattern)) => Some(pattern.to_owned()),
+            _ => self.default_pattern(key).map(str::to_owned),
+        }
+    }
+
+    /// Replaces the pattern of `key` in `locale`, e.g. by a translation, or
+    /// adds a regional variant, e.g. in `en-GB` for a key translated in `en`.
+    ///
+    /// Fails if the pattern is malformed, see [`MessageFormat::try_new`]; the
+    /// key then keeps its earlier pattern, or falls back as without a
+    /// translation.
+    ///
+    /// A message without arguments is formatted here, so that formatting it
+    /// is a lookup of its text, see [`Registry::format_shared`].
+    pub fn set_pattern(
+        &self,
+        key: &str,
+        locale: &Locale,
+        pattern: impl Into<String>,
+    ) -> Result<(), ParseError> {
+        let pattern = pattern.into();
+        let message = MessageFormat::try_new(pattern.as_str(), locale)?;
+        let static_text = message.is_static().then(|| message.format());
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        // regional locales may use the pattern
+        state.messages.retain(|(_, k), _| k != key);
+        let id = (locale.clone(), key.to_owned());
+        if let Some(text) = static_text {
+            state
+                .messages
+                .insert(id.clone(), Message::Static(text.into()));
+        }
+        state.patterns.insert(id, pattern);
+        Ok(())
+    }
+
+    /// Loads the translations of `locale` from `fetcher`, e.g. when a user
+    /// first selects the locale. The messages replace earlier patterns of the
+    /// locale.
+    ///
+    /// Returns the keys whose patterns are malformed, with their errors; the
+    /// other messages are loaded. Fails if the translations cannot be
+    /// fetched or are not a valid ARB file.
+    #[cfg(feature = "async")]
+    pub async fn load_locale(
+        &self,
+        fetcher: &impl ResourceFetcher,
+        locale: &Locale,
+    ) -> Result<Vec<(String, ParseError)>, String> {
+        let content = fetcher.fetch(locale).await?;
+        let mut failed = Vec::new();
+        for Entry { key, pattern, .. } in parse_locale_file(&content)? {
+            if let Err(err) = self.set_pattern(&key, locale, pattern) {
+                failed.push((key, err));
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Returns the metadata of `key`, if any.
+    pub fn metadata(&self, key: &str) -> Option<Metadata> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.metadata.get(key).cloned()
+    }
+
+    /// Sets the metadata of `key`, e.g. read from an ARB file.
+    pub fn set_metadata(&self, key: &str, metadata: Metadata) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.metadata.insert(key.to_owned(), metadata);
+    }
+
+    /// Formats every branch of the message of `key` in `locale`, e.g. for
+    /// translators, with the argument examples of its metadata. Arguments
+    /// without an example are shown as `{NAME}`.
+    pub fn previews(&self, key: &str, locale: &Locale) -> Vec<(BranchPath, String)> {
+        let Some(pattern) = self.pattern(key, locale) else {
+            return Vec::new();
+        };
+        let message = MessageFormat::new(pattern, locale);
+        let mut params = self.metadata(key).unwrap_or_default().example_params();
+        for argument in message.arguments() {
+            if !params.iter().any(|(name, _)| *name == argument) {
+                let placeholder = format!("{{{argument}}}");
+                params.push((argument, placeholder.into()));
+            }
+        }
+        message.format_all_branches(params)
+    }
+
+    /// Adds a select alias to all messages, see
+    /// [`MessageFormat::with_select_alias`].
+    pub fn set_select_alias(&self, from: impl Into<String>, to: impl Into<String>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.select_aliases.push((from.into(), to.into()));
+        // messages without arguments have no select blocks
+        state
+            .messages
+            .retain(|_, message| matches!(message, Message::Static(_)));
+    }
+
+    /// Sets the pattern of keys without a translation in the requested
+    /// locale. Defaults to [`Fallback::Source`].
+    pub fn set_fallback(&self, fallback: Fallback) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.fallback = fallback;
+    }
+
+    /// Sets the locale of the default patterns, which is never handled as
+    /// missing a translation.
+    pub fn set_source_locale(&self, locale: &Locale) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.source_locale = Some(locale.clone());
+    }
+
+    /// Formats the message of `key` in `locale`. Returns `None` if there is
+    /// no such key, or if it has no translation and the fallback is
+    /// [`Fallback::Error`].
+    pub fn format(
+        &self,
+        key: &str,
+        locale: &Locale,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Option<String> {
+        let fallback = self
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .fallback;
+        self.format_with_fallback(key, locale, named_parameters, fallback)
+    }
+
+    /// Formats the message of `key` in `locale` as [`Registry::format`], but
+    /// with the given fallback instead of the one of the registry.
+    pub fn format_with_fallback(
+        &self,
+        key: &str,
+        locale: &Locale,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+        fallback: Fallback,
+    ) -> Option<String> {
+        match self.render(key, locale, named_parameters, fallback)? {
+            Rendered::Shared(text) => Some(text.to_string()),
+            Rendered::Owned(text) => Some(text),
+        }
+    }
+
+    /// Formats the message of `key` in `locale` as [`Registry::format`], but
+    /// shares the text of messages without arguments instead of copying it.
+    pub fn format_shared(
+        &self,
+        key: &str,
+        locale: &Locale,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> Option<Arc<str>> {
+        let fallback = self
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .fallback;
+        match self.render(key, locale, named_parameters, fallback)? {
+            Rendered::Shared(text) => Some(text),
+            Rendered::Owned(text) => Some(text.into()),
+        }
+    }
+
+    fn render(
+        &self,
+        key: &str,
+        locale: &Locale,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+        fallback: Fallback,
+    ) -> Option<Rendered> {
+        let (message, context, translated) = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let id = (locale.clone(), key.to_owned());
+            let translated = state.lookup(key, locale).is_some();
+            if !translated {
+                self.default_pattern(key)?;
+                match fallback {
+                    Fallback::Key => return Some(Rendered::Owned(key.to_owned())),
+                    Fallback::Error => return None,
+                    Fallback::Source | Fallback::Pseudo => {}
+                }
+            }
+
+            // set patterns are classified by `set_pattern`, this parses the
+            // default patterns and patterns used by regional locales
+            if !state.messages.contains_key(&id) {
+                let pattern = match state.lookup(key, locale) {
+                    Some(Lookup::Translation(pattern)) => pattern.to_owned(),
+                    _ => self.default_pattern(key)?.to_owned(),
+                };
+                let mut message = MessageFormat::new_owned(pattern, locale.clone());
+                for (from, to) in &state.select_aliases {
+                    message = message.with_select_alias(from, to);
+                }
+                let message = if message.is_static() {
+                    Message::Static(message.format().into())
+                } else {
+                    Message::Parsed(Arc::new(message))
+                };
+                state.messages.insert(id.clone(), message);
+            }
+            let context = Arc::clone(
+                state
+                    .contexts
+                    .entry(locale.clone())
+                    .or_insert_with(|| Arc::new(RenderContext::new(locale.clone()))),
+            );
+            (state.messages[&id].clone(), context, translated)
+        };
+
+        let pseudo = !translated && fallback == Fallback::Pseudo;
+        let rendered = match message {
+            Message::Static(text) if pseudo => {
+                Rendered::Owned(pseudo_localize(&[MessagePart::Literal(text.to_string())]))
+            }
+            Message::Static(text) => Rendered::Shared(text),
+            Message::Parsed(message) if pseudo => {
+                Rendered::Owned(pseudo_localize(&message.format_to_parts(named_parameters)))
+            }
+            Message::Parsed(message) => {
+                Rendered::Owned(message.format_with_context(&context, named_parameters))
+            }
+        };
+        Some(rendered)
+    }
+
+    /// Checks every pattern of the registry: strict parsing, plural and
+    /// ordinal keys against the plural rules of the locale, and the
+    /// consistency of the locales as in [`Registry::audit`]. Default patterns
+    /// are checked in the source locale, if set.
+    pub fn report(&self) -> Report {
+        let (source_locale, mut patterns) = {
+            let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let patterns: Vec<_> = state
+                .patterns
+                .iter()
+                .map(|((locale, key), pattern)| (key.clone(), locale.clone(), pattern.clone()))
+                .collect();
+            (state.source_locale.clone().unwrap_or(Locale::UND), patterns)
+        };
+        patterns.extend(self.defaults.iter().map(|(key, pattern)| {
+            (
+                (*key).to_owned(),
+                source_locale.clone(),
+                (*pattern).to_owned(),
+            )
+        }));
+
+        let mut diagnostics: Vec<Diagnostic> = patterns
+            .iter()
+            .flat_map(|(key, locale, pattern)| check_pattern(key, locale, pattern))
+            .collect();
+        diagnostics.extend(self.audit().into_iter().map(|issue| match issue {
+            AuditIssue::MissingTranslation { key, locale } => Diagnostic {
+                key,
+                locale,
+                severity: Severity::Warning,
+                message: "missing translation".to_owned(),
+                span: None,
+            },
+            AuditIssue::ArgumentMismatch {
+                key,
+                locale,
+                missing,
+                unknown,
+            } => Diagnostic {
+                key,
+                locale,
+                severity: Severity::Error,
+                message: format!(
+                    "missing arguments [{}], unknown arguments [{}]",
+                    missing.join(", "),
+                    unknown.join(", ")
+                ),
+                span: None,
+            },
+        }));
+        // stable order for diffs between reports
+        diagnostics.sort_by_cached_key(|diagnostic| {
+            (diagnostic.key.clone(), diagnostic.locale.to_string())
+        });
+        Report { diagnostics }
+    }
+
+    /// Checks that every key is translated in every locale with translations,
+    /// and that translations use the same arguments as the default pattern
+    /// (or, without one, as the translation of the first locale). Patterns
+    /// which cannot be parsed are skipped, see [`Registry::report`].
+    pub fn audit(&self) -> Vec<AuditIssue> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut locales: Vec<&Locale> = state.patterns.keys().map(|(locale, _)| locale).collect();
+        locales.sort_by_cached_key(|locale| locale.to_string());
+        locales.dedup();
+        let mut keys: Vec<&str> = self.keys().collect();
+        let mut translated_keys: Vec<&str> = state
+            .patterns
+            .keys()
+            .map(|(_, key)| key.as_str())
+            .filter(|key| self.default_pattern(key).is_none())
+            .collect();
+        translated_keys.sort_unstable();
+        translated_keys.dedup();
+        keys.extend(translated_keys);
+
+        let mut issues = Vec::new();
+        for key in keys {
+            let mut expected = self.default_pattern(key).and_then(arguments);
+            for locale in &locales {
+                let Some(pattern) = state.patterns.get(&((*locale).clone(), key.to_owned())) else {
+                    // regional locales only need the patterns which differ
+                    if state.lookup(key, locale).is_none() {
+                        issues.push(AuditIssue::MissingTranslation {
+                            key: key.to_owned(),
+                            locale: (*locale).clone(),
+                        });
+                    }
+                    continue;
+                };
+                let Some(arguments) = arguments(pattern) else {
+                    continue;
+                };
+                let expected = expected.get_or_insert_with(|| arguments.clone());
+                let missing: Vec<_> = expected
+                    .iter()
+                    .filter(|argument| !arguments.contains(argument))
+                    .cloned()
+                    .collect();
+                let unknown: Vec<_> = arguments
+                    .iter()
+                    .filter(|argument| !expected.contains(argument))
+                    .cloned()
+                    .collect();
+                if !missing.is_empty() || !unknown.is_empty() {
+                    issues.push(AuditIssue::ArgumentMismatch {
+                        key: key.to_owned(),
+                        locale: (*locale).clone(),
+                        missing,
+                        unknown,
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    fn default_pattern(&self, key: &str) -> Option<&'static str> {
+        self.defaults
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, pattern)| *pattern)
+    }
+}
+
+/// Parents of `locale` by removing its extensions, variants, region and
+/// script in turn, e.g. `sr-Latn` and `sr` for `sr-Latn-RS`
+fn fallback_chain(locale: &Locale) -> Vec<Locale> {
+    let mut parent = Locale::from(locale.id.clone());
+    let mut chain = Vec::new();
+    let mut push = |parent: &Locale| {
+        if parent != locale && chain.last() != Some(parent) {
+            chain.push(parent.clone());
+        }
+    };
+    push(&parent);
+    parent.id.variants = Variants::new();
+    push(&parent);
+    parent.id.region = None;
+    push(&parent);
+    parent.id.script = None;
+    push(&parent);
+    chain
+}
+
+fn sorted_locales<'a>(locales: impl Iterator<Item = &'a Locale>) -> Vec<Locale> {
+    let mut locales: Vec<Locale> = locales.cloned().collect();
+    locales.sort_by_cached_key(|locale| locale.to_string());
+    locales.dedup();
+    locales
+}
+
+fn arguments(pattern: &str) -> Option<Vec<String>> {
+    MessageFormat::try_new(pattern, &Locale::UND)
+        .ok()
+        .map(|message| message.arguments())
+}
+
+/// Replaces the letters of literal text by accented ones and adds brackets,
+/// so that untranslated text stands out but remains readable.
+fn pseudo_localize(parts: &[MessagePart]) -> String {
+    let mut text = String::from("[");
+    for part in parts {
+        match part {
+            MessagePart::Literal(literal) => text.extend(literal.chars().map(accented)),
+            part => text.push_str(part.text()),
+        }
+    }
+    text.push(']');
+    text
+}
+
+fn accented(c: char) -> char {
+    const PLAIN: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const ACCENTED: &str = "áƀçđéƒĝĥíĵķĺɱñóƥɋŕšţúṽŵẋýžÁƁÇĐÉƑĜĤÍĴĶĹṀÑÓƤɊŔŠŢÚṼŴẊÝŽ";
+    PLAIN
+        .chars()
+        .position(|plain| plain == c)
+        .and_then(|i| ACCENTED.chars().nth(i))
+        .unwrap_or(c)
+}
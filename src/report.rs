@@ -0,0 +1,240 @@
+use std::{fmt, ops::Range};
+
+use icu::{
+    locid::Locale,
+    plurals::{PluralRuleType, PluralRules},
+};
+use regex::Regex;
+
+use crate::{
+    extract_parts,
+    format::category_name,
+    param::{ARGUMENT_NAME, ARGUMENT_OFFSET},
+    Block, MessageFormat, ParseError, OTHER,
+};
+
+/// Severity of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Message which formats, but probably not as intended, e.g. with a
+    /// plural category the locale does not use
+    Warning,
+    /// Message which cannot be parsed or uses other arguments than the
+    /// default pattern
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Problem of a message of a catalog, see [`crate::Registry::report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub key: String,
+    pub locale: Locale,
+    pub severity: Severity,
+    pub message: String,
+    /// Byte range of the problem in the pattern, if it can be located
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.locale, self.key, self.severity)?;
+        if let Some(span) = &self.span {
+            write!(f, " at {}..{}", span.start, span.end)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Diagnostics of all messages of a catalog, sorted by key and locale
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    /// Whether there is a diagnostic of [`Severity::Error`], e.g. to fail a
+    /// CI job.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Returns the report as JSON, e.g. to publish it as a CI artifact:
+    ///
+    /// ```json
+    /// {"errors": 1, "warnings": 0, "diagnostics": [{"key": "cart.items",
+    /// "locale": "de", "severity": "error", "message": "invalid plural key
+    /// ein", "span": [12, 15]}]}
+    /// ```
+    #[cfg(feature = "report")]
+    pub fn to_json(&self) -> String {
+        let diagnostics: Vec<_> = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                serde_json::json!({
+                    "key": diagnostic.key,
+                    "locale": diagnostic.locale.to_string(),
+                    "severity": diagnostic.severity.to_string(),
+                    "message": diagnostic.message,
+                    "span": diagnostic.span.as_ref().map(|span| [span.start, span.end]),
+                })
+            })
+            .collect();
+        let errors = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count();
+        serde_json::json!({
+            "errors": errors,
+            "warnings": self.diagnostics.len() - errors,
+            "diagnostics": diagnostics,
+        })
+        .to_string()
+    }
+}
+
+/// Parses `pattern` strictly and checks the keys of its plural and ordinal
+/// blocks against the plural rules of `locale`.
+pub(crate) fn check_pattern(key: &str, locale: &Locale, pattern: &str) -> Vec<Diagnostic> {
+    let diagnostic = |severity, message: String, span| Diagnostic {
+        key: key.to_owned(),
+        locale: locale.clone(),
+        severity,
+        message,
+        span,
+    };
+    let message = match MessageFormat::parse_strict(pattern, locale) {
+        Ok(message) => message,
+        Err(err) => {
+            let span = error_span(pattern, &err);
+            return vec![diagnostic(Severity::Error, err.to_string(), span)];
+        }
+    };
+    // there are no plural rules without a language
+    if *locale == Locale::UND {
+        return Vec::new();
+    }
+
+    let mut keys = Vec::new();
+    plural_keys(message.parsed(), &mut keys);
+    let mut diagnostics = Vec::new();
+    for (rule_type, argument, block_keys) in keys {
+        let rules = PluralRules::try_new(&locale.into(), rule_type).expect("missing locale");
+        let categories: Vec<&str> = rules.categories().map(category_name).collect();
+        let typ = match rule_type {
+            PluralRuleType::Ordinal => "ordinal",
+            _ => "plural",
+        };
+        for block_key in &block_keys {
+            if !categories.contains(&block_key.as_str()) {
+                diagnostics.push(diagnostic(
+                    Severity::Warning,
+                    format!("{typ} category {block_key} of {argument} is not used in {locale}"),
+                    key_span(pattern, block_key),
+                ));
+            }
+        }
+        for category in categories {
+            if category != "other" && !block_keys.iter().any(|key| key == category) {
+                diagnostics.push(diagnostic(
+                    Severity::Warning,
+                    format!("missing {typ} category {category} of {argument}"),
+                    None,
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Collects the category keys of plural and ordinal blocks, i.e. without
+/// exact matches like `=1`
+fn plural_keys(blocks: &[Block], keys: &mut Vec<(PluralRuleType, String, Vec<String>)>) {
+    for block in blocks {
+        let (rule_type, branches) = match block {
+            Block::Plural(branches, _) => (Some(PluralRuleType::Cardinal), branches),
+            Block::Ordinal(branches, _) => (Some(PluralRuleType::Ordinal), branches),
+            Block::Select(branches) => (None, branches),
+            Block::String(_)
+            | Block::Literal(_)
+            | Block::Simple(_)
+            | Block::Number(..)
+            | Block::DateTime(..)
+            | Block::Custom(..)
+            | Block::TagOpen(_)
+            | Block::TagClose(_) => continue,
+        };
+        if let (Some(rule_type), Some(Block::String(argument))) =
+            (rule_type, branches[&ARGUMENT_NAME].first())
+        {
+            let categories = branches
+                .keys()
+                .filter(|key| key.as_str().is_some() && **key != OTHER)
+                .filter(|key| **key != ARGUMENT_NAME && **key != ARGUMENT_OFFSET)
+                .map(ToString::to_string)
+                .collect();
+            keys.push((rule_type, argument.clone(), categories));
+        }
+        for (key, branch) in branches {
+            if *key != ARGUMENT_NAME && *key != ARGUMENT_OFFSET {
+                plural_keys(branch, keys);
+            }
+        }
+    }
+}
+
+/// Locates the text of a parse error in the pattern, e.g. the invalid key
+fn error_span(pattern: &str, err: &ParseError) -> Option<Range<usize>> {
+    let text = match err {
+        ParseError::UnbalancedBraces(pos) | ParseError::InvalidMf2(pos, _) => {
+            return Some(*pos..pos + 1)
+        }
+        ParseError::InvalidPluralKey(key) | ParseError::NumericSelectKey(key) => {
+            return key_span(pattern, key);
+        }
+        ParseError::UnmatchedClosingBrace | ParseError::UnclosedBrace => {
+            return match extract_parts(pattern) {
+                Err(ParseError::UnbalancedBraces(pos)) => Some(pos..pos + 1),
+                _ => None,
+            };
+        }
+        ParseError::UnknownBlock(text)
+        | ParseError::UnknownArgumentType(text)
+        | ParseError::InvalidOffset(text)
+        | ParseError::UnknownNumberStyle(text)
+        | ParseError::UnknownDateTimeStyle(text)
+        | ParseError::MissingBranch(text)
+        | ParseError::TooManyKeys(text) => text.as_str(),
+        ParseError::EmptyBlock => "{}",
+        ParseError::StrayPound => "#",
+        ParseError::MissingOther(_) => return None,
+        ParseError::UnclosedTag(name) => return text_span(pattern, &format!("<{name}>")),
+        ParseError::UnmatchedClosingTag(name) => {
+            return text_span(pattern, &format!("</{name}>"));
+        }
+    };
+    text_span(pattern, text)
+}
+
+fn text_span(pattern: &str, text: &str) -> Option<Range<usize>> {
+    let start = pattern.find(text)?;
+    Some(start..start + text.len())
+}
+
+/// Finds the first branch key `key`, i.e. followed by the `{` of its branch
+fn key_span(pattern: &str, key: &str) -> Option<Range<usize>> {
+    let re = Regex::new(&format!(r"(?:^|[\s,}}])({})\s*\{{", regex::escape(key))).ok()?;
+    re.captures(pattern)?.get(1).map(|m| m.range())
+}
@@ -0,0 +1,44 @@
+use fixed_decimal::FixedDecimal;
+use icu::{locid::Locale, plurals::PluralRules};
+
+use crate::format::category_name;
+
+/// Maximum number of integer and decimal samples per category
+const MAX_SAMPLES: usize = 10;
+
+/// Numbers selecting a plural category of a locale, e.g. for test
+/// generators or previews that should exercise every branch of a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluralSamples {
+    /// Smallest integers in the category
+    pub integers: Vec<u64>,
+    /// Small decimals with visible fraction digits in the category,
+    /// e.g. `"1.5"`
+    pub decimals: Vec<String>,
+}
+
+/// Returns sample numbers selecting the cardinal plural `category` (`"zero"`,
+/// `"one"`, `"two"`, `"few"`, `"many"` or `"other"`) in `locale`.
+///
+/// The samples are found by evaluating the plural rules of the locale, so
+/// they are empty for categories the locale does not use.
+pub fn plural_samples(locale: &Locale, category: &str) -> PluralSamples {
+    let rules = PluralRules::try_new_cardinal(&locale.into()).expect("missing locale");
+    let matches = |n: &FixedDecimal| category_name(rules.category_for(n)) == category;
+
+    let integers = (0u64..=1000)
+        .chain([10_000, 100_000, 1_000_000, 10_000_000])
+        .filter(|n| matches(&FixedDecimal::from(*n)))
+        .take(MAX_SAMPLES)
+        .collect();
+
+    let decimals = (0u64..=1000)
+        .chain([10_000, 100_000, 1_000_000, 10_000_000])
+        .flat_map(|n| [1, 2].map(|digits| FixedDecimal::from(n).multiplied_pow10(-digits)))
+        .filter(matches)
+        .take(MAX_SAMPLES)
+        .map(|n| n.to_string())
+        .collect();
+
+    PluralSamples { integers, decimals }
+}
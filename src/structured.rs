@@ -0,0 +1,40 @@
+use std::fmt;
+
+use crate::ParamValue;
+
+/// Formatted message with the parameters of its arguments, see
+/// [`crate::MessageFormat::format_structured`], e.g. to log the localized
+/// text and keep the values as structured fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredMessage {
+    pub text: String,
+    /// Parameters of the arguments of the message in the order of their
+    /// first use, without parameters the message does not use
+    pub fields: Vec<(String, ParamValue)>,
+}
+
+impl StructuredMessage {
+    /// Returns the parameter of the argument `name`, if it was passed.
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.fields
+            .iter()
+            .find_map(|(field, value)| (field == name).then_some(value))
+    }
+
+    /// Returns the parameter of the argument `name` as a `tracing` field
+    /// value, which is not recorded if the parameter was not passed:
+    ///
+    /// ```ignore
+    /// tracing::info!(count = message.field("count"), "{message}");
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn field(&self, name: &str) -> Option<Box<dyn tracing::Value + '_>> {
+        self.get(name).map(ParamValue::as_field)
+    }
+}
+
+impl fmt::Display for StructuredMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
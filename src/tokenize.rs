@@ -0,0 +1,173 @@
+use std::{borrow::Cow, ops::Range};
+
+use memchr::{memchr, memchr2_iter, memchr_iter, memmem};
+
+use crate::ParseError;
+
+/// Kind of a [`PatternPart`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternPartKind {
+    /// Literal text between blocks
+    Text,
+    /// Top-level `{…}` block, e.g. an argument or a plural block
+    Block,
+}
+
+/// Top-level segment of a pattern, see [`extract_parts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternPart<'p> {
+    pub kind: PatternPartKind,
+    /// Byte range in the pattern, without the braces of blocks
+    pub span: Range<usize>,
+    /// Text with quoting resolved (`''` as `'`, `'{'` as `{`), or the raw
+    /// content of a block, which can be split again into its nested parts
+    pub text: Cow<'p, str>,
+}
+
+/// Splits a pattern into literal text and top-level blocks, with the brace
+/// and quote handling of [`crate::MessageFormat`], e.g. for extractors and
+/// converters which need the spans of arguments in the source text.
+///
+/// ```
+/// use message_format::{extract_parts, PatternPartKind};
+///
+/// let parts = extract_parts("'{'{N, plural, one {# file} other {# files}}'}'").unwrap();
+/// assert_eq!(parts[0].text, "{");
+/// assert_eq!(parts[1].kind, PatternPartKind::Block);
+/// assert_eq!(parts[1].text, "N, plural, one {# file} other {# files}");
+/// assert_eq!(parts[2].text, "}");
+/// ```
+pub fn extract_parts<'p>(pattern: &'p str) -> Result<Vec<PatternPart<'p>>, ParseError> {
+    let quotes = find_quotes(pattern);
+    let mut parts = Vec::new();
+    let push_text = |parts: &mut Vec<PatternPart<'p>>, span: Range<usize>| {
+        if !span.is_empty() {
+            parts.push(PatternPart {
+                kind: PatternPartKind::Text,
+                text: quotes.unquote(pattern, span.clone()),
+                span,
+            });
+        }
+    };
+
+    let mut depth = 0usize;
+    let mut start = 0;
+    for pos in memchr2_iter(b'{', b'}', pattern.as_bytes()) {
+        if quotes.is_quoted(pos) {
+            continue;
+        }
+        if pattern.as_bytes()[pos] == b'{' {
+            if depth == 0 {
+                push_text(&mut parts, start..pos);
+                start = pos + 1;
+            }
+            depth += 1;
+        } else {
+            depth = depth
+                .checked_sub(1)
+                .ok_or(ParseError::UnbalancedBraces(pos))?;
+            if depth == 0 {
+                parts.push(PatternPart {
+                    kind: PatternPartKind::Block,
+                    text: Cow::Borrowed(&pattern[start..pos]),
+                    span: start..pos,
+                });
+                start = pos + 1;
+            }
+        }
+    }
+    if depth > 0 {
+        return Err(ParseError::UnbalancedBraces(start - 1));
+    }
+    push_text(&mut parts, start..pattern.len());
+    Ok(parts)
+}
+
+/// Escaped apostrophes and quoted literals of a pattern
+pub(crate) struct Quotes {
+    /// Positions of escaped apostrophes, i.e. `''`
+    pub(crate) apostrophes: Vec<usize>,
+    /// Quoted literals including their apostrophes, e.g. `'{x}'`
+    pub(crate) literals: Vec<Range<usize>>,
+}
+
+/// Quoted literals start with a special character and end at the next
+/// apostrophe of the same line; other apostrophes are text.
+pub(crate) fn find_quotes(pattern: &str) -> Quotes {
+    let bytes = pattern.as_bytes();
+    let apostrophes: Vec<usize> = memmem::find_iter(bytes, "''").collect();
+    let escaped = |pos: usize| {
+        apostrophes.binary_search(&pos).is_ok()
+            || pos > 0 && apostrophes.binary_search(&(pos - 1)).is_ok()
+    };
+
+    let mut literals = Vec::new();
+    let mut singles = memchr_iter(b'\'', bytes)
+        .filter(|&pos| !escaped(pos))
+        .peekable();
+    while let Some(start) = singles.next() {
+        if !matches!(bytes.get(start + 1), Some(b'{' | b'}' | b'#')) {
+            continue;
+        }
+        let Some(&close) = singles.peek() else {
+            break;
+        };
+        if memchr(b'\n', &bytes[start..close]).is_some() {
+            continue;
+        }
+        singles.next();
+        literals.push(start..close + 1);
+    }
+    Quotes {
+        apostrophes,
+        literals,
+    }
+}
+
+impl Quotes {
+    pub(crate) fn is_quoted(&self, pos: usize) -> bool {
+        let idx = self.literals.partition_point(|literal| literal.end <= pos);
+        self.literals
+            .get(idx)
+            .is_some_and(|literal| literal.contains(&pos))
+    }
+
+    /// Escaped apostrophes in `span`
+    pub(crate) fn apostrophes_in(&self, span: Range<usize>) -> impl Iterator<Item = usize> + '_ {
+        let first = self.apostrophes.partition_point(|&pos| pos < span.start);
+        self.apostrophes[first..]
+            .iter()
+            .copied()
+            .take_while(move |&pos| pos + 2 <= span.end)
+    }
+
+    /// Appends `span` of the pattern to `text` with escaped apostrophes as
+    /// single ones
+    pub(crate) fn unescape(&self, text: &mut String, pattern: &str, span: Range<usize>) {
+        let mut end = span.start;
+        for pos in self.apostrophes_in(span.clone()) {
+            text.push_str(&pattern[end..pos + 1]);
+            end = pos + 2;
+        }
+        text.push_str(&pattern[end..span.end]);
+    }
+
+    pub(crate) fn unquote<'p>(&self, pattern: &'p str, span: Range<usize>) -> Cow<'p, str> {
+        let mut text = String::new();
+        let mut end = span.start;
+        for literal in &self.literals {
+            if literal.end <= span.start || literal.start >= span.end {
+                continue;
+            }
+            self.unescape(&mut text, pattern, end..literal.start);
+            self.unescape(&mut text, pattern, literal.start + 1..literal.end - 1);
+            end = literal.end;
+        }
+        self.unescape(&mut text, pattern, end..span.end);
+        if text == pattern[span.clone()] {
+            Cow::Borrowed(&pattern[span])
+        } else {
+            Cow::Owned(text)
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::{
+    param::{ARGUMENT_NAME, ARGUMENT_OFFSET},
+    Block, ParamValue,
+};
+
+const INDENT: &str = "  ";
+
+/// Renders parsed blocks as an indented tree, one block or branch key per
+/// line, see [`crate::MessageFormat::debug_tree`].
+pub(crate) fn write_tree(out: &mut String, blocks: &[Block], depth: usize) {
+    let indent = INDENT.repeat(depth);
+    let mut text = String::new();
+    for (idx, block) in blocks.iter().enumerate() {
+        match block {
+            Block::String(value) | Block::Literal(value) => {
+                // quoted literals are shown as part of the surrounding text
+                text.push_str(value);
+                if !matches!(
+                    blocks.get(idx + 1),
+                    Some(Block::String(_) | Block::Literal(_))
+                ) {
+                    writeln!(out, "{indent}text {:?}", std::mem::take(&mut text)).unwrap();
+                }
+            }
+            Block::Simple(name) => writeln!(out, "{indent}argument {name}").unwrap(),
+            Block::Number(name, options) => match options.to_skeleton().as_str() {
+                "::" => writeln!(out, "{indent}number {name}").unwrap(),
+                skeleton => writeln!(out, "{indent}number {name} {skeleton}").unwrap(),
+            },
+            Block::DateTime(name, style) => {
+                writeln!(out, "{indent}{} {name} {}", style.typ(), style.style()).unwrap();
+            }
+            Block::Custom(name, typ, style) => match style.as_str() {
+                "" => writeln!(out, "{indent}{typ} {name}").unwrap(),
+                style => writeln!(out, "{indent}{typ} {name} {style}").unwrap(),
+            },
+            Block::TagOpen(name) => writeln!(out, "{indent}tag <{name}>").unwrap(),
+            Block::TagClose(name) => writeln!(out, "{indent}tag </{name}>").unwrap(),
+            Block::Select(branches) => write_branches(out, "select", branches, depth),
+            Block::Plural(branches, _) => write_branches(out, "plural", branches, depth),
+            Block::Ordinal(branches, _) => {
+                write_branches(out, "selectordinal", branches, depth);
+            }
+        }
+    }
+}
+
+fn write_branches(
+    out: &mut String,
+    typ: &str,
+    branches: &IndexMap<ParamValue, Vec<Block>>,
+    depth: usize,
+) {
+    let indent = INDENT.repeat(depth);
+    let first_string = |key: &ParamValue| match branches.get(key).and_then(|b| b.first()) {
+        Some(Block::String(value)) => value.clone(),
+        _ => String::new(),
+    };
+    write!(out, "{indent}{typ} {}", first_string(&ARGUMENT_NAME)).unwrap();
+    match first_string(&ARGUMENT_OFFSET).as_str() {
+        "" | "0" => out.push('\n'),
+        offset => writeln!(out, " offset:{offset}").unwrap(),
+    }
+    for (key, branch) in branches {
+        if *key == ARGUMENT_NAME || *key == ARGUMENT_OFFSET {
+            continue;
+        }
+        // numeric keys of plural blocks are exact matches
+        if typ != "select" && key.as_decimal().is_some() {
+            writeln!(out, "{indent}{INDENT}={key}").unwrap();
+        } else {
+            writeln!(out, "{indent}{INDENT}{key}").unwrap();
+        }
+        write_tree(out, branch, depth + 2);
+    }
+}
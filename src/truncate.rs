@@ -0,0 +1,22 @@
+use icu::segmenter::GraphemeClusterSegmenter;
+
+/// CLDR uses this character in the final ellipsis pattern (`{0}…`) of nearly
+/// all locales.
+const ELLIPSIS: char = '…';
+
+/// Truncates `s` to at most `max_len` grapheme clusters including the
+/// ellipsis, so that no user-perceived character is cut in half.
+pub(crate) fn truncate(s: String, max_len: usize) -> String {
+    let segmenter = GraphemeClusterSegmenter::new();
+    // breakpoints include the start and the end of the string
+    let breakpoints: Vec<usize> = segmenter.segment_str(&s).collect();
+    if breakpoints.len().saturating_sub(1) <= max_len {
+        return s;
+    }
+    let Some(end) = max_len.checked_sub(1) else {
+        return String::new();
+    };
+    let mut truncated = s[..breakpoints[end]].trim_end().to_owned();
+    truncated.push(ELLIPSIS);
+    truncated
+}
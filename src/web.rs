@@ -0,0 +1,134 @@
+use icu::locid::Locale;
+
+use crate::{ParamValue, Registry};
+
+/// Messages and supported locales of a web application, e.g.
+/// `Localization::new(&MESSAGES, [locale!("en"), locale!("de")])`.
+///
+/// With axum, it is added as an `Extension` layer; with actix-web, as
+/// `app_data`. Handlers then take a [`Messages`] argument.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    registry: &'static Registry,
+    locales: Vec<Locale>,
+}
+
+/// Messages in the locale of a request, resolved from its `Accept-Language`
+/// header.
+#[derive(Debug, Clone)]
+pub struct Messages {
+    registry: &'static Registry,
+    locale: Locale,
+}
+
+impl Localization {
+    /// The first locale is used if the request accepts none of them.
+    pub fn new(registry: &'static Registry, locales: impl IntoIterator<Item = Locale>) -> Self {
+        let locales: Vec<_> = locales.into_iter().collect();
+        assert!(!locales.is_empty(), "missing locale");
+        Self { registry, locales }
+    }
+
+    /// Returns the messages in the supported locale preferred by an
+    /// `Accept-Language` header value, e.g. `de-CH, de;q=0.9, en;q=0.8`.
+    pub fn messages(&self, accept_language: Option<&str>) -> Messages {
+        Messages {
+            registry: self.registry,
+            locale: self.negotiate(accept_language.unwrap_or_default()),
+        }
+    }
+
+    fn negotiate(&self, accept_language: &str) -> Locale {
+        let mut ranges: Vec<(Locale, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';');
+                let locale = params.next()?.trim().parse().ok()?;
+                let quality = params
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+                Some((locale, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // stable, so that ranges of the same quality keep their order
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        ranges
+            .iter()
+            .find_map(|(range, _)| {
+                self.locales
+                    .iter()
+                    .find(|locale| *locale == range)
+                    .or_else(|| {
+                        self.locales
+                            .iter()
+                            .find(|locale| locale.id.language == range.id.language)
+                    })
+            })
+            .unwrap_or(&self.locales[0])
+            .clone()
+    }
+}
+
+impl Messages {
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Formats the message of `key`, or returns `key` itself if the registry
+    /// has no such message.
+    pub fn format(
+        &self,
+        key: &str,
+        named_parameters: impl IntoIterator<Item = (impl Into<String>, ParamValue)>,
+    ) -> String {
+        self.registry
+            .format(key, &self.locale, named_parameters)
+            .unwrap_or_else(|| key.to_owned())
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<S: Sync> axum_core::extract::FromRequestParts<S> for Messages {
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(localization) = parts.extensions.get::<Localization>() else {
+            return Err((
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+                "missing Localization extension",
+            ));
+        };
+        let accept_language = parts
+            .headers
+            .get(http::header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+        Ok(localization.messages(accept_language))
+    }
+}
+
+#[cfg(feature = "actix-web")]
+impl actix_web::FromRequest for Messages {
+    type Error = actix_web::Error;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let Some(localization) = req.app_data::<Localization>() else {
+            return std::future::ready(Err(actix_web::error::ErrorInternalServerError(
+                "missing Localization app data",
+            )));
+        };
+        let accept_language = req
+            .headers()
+            .get(actix_web::http::header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+        std::future::ready(Ok(localization.messages(accept_language)))
+    }
+}